@@ -0,0 +1,255 @@
+//! Node bindings for the msfconsole parsers and console pool, so server.js
+//! can use them instead of splitting pty output itself. Built with
+//! `npm run build`; field names are camelCased on the JS side.
+
+use napi::bindgen_prelude::*;
+use napi::{Env, Task};
+use napi_derive::napi;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use create_options_json::console::RunOutput;
+use create_options_json::events;
+use create_options_json::msf::{Exploit, ExploitDetails, MSFProcess, Parameter};
+use create_options_json::pool;
+use create_options_json::process::Process;
+
+/// One row of `show exploits`, `show payloads` or `show auxiliary`.
+#[napi(object)]
+pub struct ModuleDetails {
+    pub name: String,
+    /// `YYYY-MM-DD`, or `.` for modules without one.
+    pub disclosure_date: String,
+    /// Empty when the row's rank column could not be read.
+    pub rank: String,
+    /// false when the table did not say.
+    pub check: bool,
+    pub description: String,
+}
+
+impl From<ExploitDetails> for ModuleDetails {
+    fn from(details: ExploitDetails) -> Self {
+        Self {
+            name: details.name,
+            disclosure_date: details
+                .disclosure_date
+                .map_or_else(|| ".".to_string(), |d| d.to_string()),
+            rank: details.rank.map_or_else(String::new, |r| r.to_string()),
+            check: details.check.unwrap_or(false),
+            description: details.description,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct ModuleOption {
+    pub name: String,
+    pub default_value: Option<String>,
+    pub required: bool,
+    pub description: String,
+    /// `port`, `address`, `address_range`, `path`, `boolean`, `integer`,
+    /// `enum`, `regex` or `string`.
+    pub kind: String,
+    pub enum_values: Option<Vec<String>>,
+    /// The scraped value when `default_value` is a placeholder such as
+    /// `${LOCAL_IP}`.
+    pub original_default: Option<String>,
+    /// Listed by `show advanced` rather than `show options`.
+    pub advanced: bool,
+}
+
+impl From<Parameter> for ModuleOption {
+    fn from(parameter: Parameter) -> Self {
+        let kind = serde_json::to_value(parameter.kind())
+            .ok()
+            .and_then(|k| k.as_str().map(str::to_string))
+            .unwrap_or_default();
+        let enum_values = parameter.accepted_values();
+        Self {
+            name: parameter.name,
+            default_value: parameter.default_value,
+            required: parameter.required,
+            description: parameter.description,
+            kind,
+            enum_values,
+            original_default: parameter.original_default,
+            advanced: parameter.advanced,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct ModuleOptions {
+    pub name: String,
+    pub payload: String,
+    pub payload_options: Option<Vec<ModuleOption>>,
+    pub options: Option<Vec<ModuleOption>>,
+    pub target: Option<Vec<String>>,
+}
+
+impl From<Exploit> for ModuleOptions {
+    fn from(exploit: Exploit) -> Self {
+        let convert = |options: Option<Vec<Parameter>>| {
+            options.map(|options| options.into_iter().map(ModuleOption::from).collect())
+        };
+        Self {
+            name: exploit.name,
+            payload: exploit.payload,
+            payload_options: convert(exploit.payload_options),
+            options: convert(exploit.options),
+            target: exploit.target,
+        }
+    }
+}
+
+#[napi(object)]
+pub struct CommandOutput {
+    pub command: String,
+    pub output: String,
+    /// Console events as produced by `parseEvents`.
+    pub events: serde_json::Value,
+}
+
+impl From<RunOutput> for CommandOutput {
+    fn from(run: RunOutput) -> Self {
+        Self {
+            command: run.command,
+            output: run.output,
+            events: serde_json::to_value(run.events).unwrap_or_default(),
+        }
+    }
+}
+
+fn strip(output: &str) -> String {
+    String::from_utf8_lossy(&strip_ansi_escapes::strip(output)).to_string()
+}
+
+/// Runs a parser, turning a panic on malformed input into a JS error.
+fn guard<T>(parse: impl FnOnce() -> T) -> Result<T> {
+    catch_unwind(AssertUnwindSafe(parse))
+        .map_err(|_| Error::from_reason("Failed to parse console output"))
+}
+
+/// Parses a module table; ANSI colours are stripped first.
+#[napi]
+pub fn parse_module_table(output: String) -> Result<Vec<ModuleDetails>> {
+    let output = strip(&output);
+    let details = guard(|| MSFProcess::extract_exploit_details(&output))?;
+    Ok(details.into_iter().map(ModuleDetails::from).collect())
+}
+
+/// Parses `show options` output for module `name`.
+#[napi]
+pub fn parse_show_options(name: String, output: String) -> Result<ModuleOptions> {
+    let output = strip(&output);
+    let mut exploit = Exploit {
+        name,
+        payload: "".to_string(),
+        payload_options: None,
+        options: None,
+        target: None,
+    };
+    guard(|| MSFProcess::parse_show_options(&mut exploit, &output))?;
+    Ok(exploit.into())
+}
+
+#[napi]
+pub fn parse_events(output: String) -> serde_json::Value {
+    serde_json::to_value(events::parse_output(&strip(&output))).unwrap_or_default()
+}
+
+/// Work for the libuv thread pool, resolved as a promise.
+pub struct PoolJob<T> {
+    job: Option<Box<dyn FnOnce() -> std::result::Result<T, String> + Send>>,
+}
+
+impl<T> PoolJob<T> {
+    fn new(job: impl FnOnce() -> std::result::Result<T, String> + Send + 'static) -> AsyncTask<Self>
+    where
+        Self: Task,
+    {
+        AsyncTask::new(Self {
+            job: Some(Box::new(job)),
+        })
+    }
+}
+
+impl<T: ToNapiValue + TypeName + Send + 'static> Task for PoolJob<T> {
+    type Output = T;
+    type JsValue = T;
+
+    fn compute(&mut self) -> Result<T> {
+        let job = self
+            .job
+            .take()
+            .ok_or_else(|| Error::from_reason("Job already ran"))?;
+        job().map_err(Error::from_reason)
+    }
+
+    fn resolve(&mut self, _env: Env, output: T) -> Result<T> {
+        Ok(output)
+    }
+}
+
+/// msfconsoles shared by every caller; see `create_options_json::pool`.
+#[napi]
+pub struct ConsolePool {
+    inner: Arc<pool::ConsolePool>,
+}
+
+#[napi]
+impl ConsolePool {
+    /// `command` defaults to `msfconsole`; the `PROCESS_RUNNER_*`
+    /// environment settings apply either way.
+    #[napi(constructor)]
+    pub fn new(size: Option<u32>, command: Option<String>) -> Self {
+        let command = command.unwrap_or_else(|| "msfconsole".to_string());
+        let size = size.unwrap_or(1) as usize;
+        Self {
+            inner: Arc::new(pool::ConsolePool::new("node", size, move || {
+                Process::builder(&command).from_env()
+            })),
+        }
+    }
+
+    /// Runs the commands in order on one console.
+    #[napi(ts_return_type = "Promise<Array<CommandOutput>>")]
+    pub fn run(&self, commands: Vec<String>) -> AsyncTask<PoolJob<Vec<CommandOutput>>> {
+        let pool = Arc::clone(&self.inner);
+        PoolJob::new(move || {
+            let results = pool.get().run(commands)?;
+            Ok(results.into_iter().map(CommandOutput::from).collect())
+        })
+    }
+
+    #[napi(ts_return_type = "Promise<ModuleOptions>")]
+    pub fn module_options(&self, name: String) -> AsyncTask<PoolJob<ModuleOptions>> {
+        let pool = Arc::clone(&self.inner);
+        PoolJob::new(move || {
+            let exploit = pool.call(move |msf| msf.module_options(&name))??;
+            Ok(exploit.into())
+        })
+    }
+
+    /// `kind` is `exploits`, `payloads` or `auxiliary`.
+    #[napi(ts_return_type = "Promise<Array<ModuleDetails>>")]
+    pub fn module_details(&self, kind: String) -> Result<AsyncTask<PoolJob<Vec<ModuleDetails>>>> {
+        let list: fn(&mut MSFProcess) -> Vec<ExploitDetails> = match kind.as_str() {
+            "exploits" => MSFProcess::get_exploits_details,
+            "payloads" => MSFProcess::get_payload_details,
+            "auxiliary" => MSFProcess::get_auxiliary_details,
+            _ => return Err(Error::from_reason(format!("Unknown module kind: {}", kind))),
+        };
+        let pool = Arc::clone(&self.inner);
+        Ok(PoolJob::new(move || {
+            let details = pool.call(list)?;
+            Ok(details.into_iter().map(ModuleDetails::from).collect())
+        }))
+    }
+
+    /// Stops every console and waits for them to exit.
+    #[napi]
+    pub fn shutdown(&self) {
+        self.inner.shutdown();
+    }
+}
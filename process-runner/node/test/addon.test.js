@@ -0,0 +1,110 @@
+// Checks the exported functions of the built addon. Run by `npm test` after
+// `npm run build`, or by `cargo test -p process-runner-node`, which sets
+// PROCESS_RUNNER_ADDON to the library it just built.
+const { test } = require("node:test");
+const assert = require("node:assert");
+
+const addon = require(process.env.PROCESS_RUNNER_ADDON || "..");
+
+const SHOW_EXPLOITS = [
+  "",
+  "Exploits",
+  "========",
+  "",
+  "   #  Name                                      Disclosure Date  Rank     Check  Description",
+  "   -  ----                                      ---------------  ----     -----  -----------",
+  "   0  exploit/windows/smb/ms17_010_eternalblue  2017-03-14       average  Yes    MS17-010 \x1b[1;31mEternalBlue\x1b[0m",
+  "   1  exploit/multi/handler                     .                manual   No     Generic Payload Handler",
+  "",
+].join("\n");
+
+const SHOW_OPTIONS = [
+  "",
+  "Module options (exploit/unix/ftp/vsftpd_234_backdoor):",
+  "",
+  "   Name     Current Setting  Required  Description",
+  "   ----     ---------------  --------  -----------",
+  "   RHOSTS                    yes       The target host(s)",
+  "   RPORT    21               yes       The target port (TCP)",
+  "",
+  "",
+  "Exploit target:",
+  "",
+  "   Id  Name",
+  "   --  ----",
+  "   0   Automatic",
+  "",
+  "",
+].join("\n");
+
+test("exports the parsers and the console pool", () => {
+  assert.deepStrictEqual(Object.keys(addon).sort(), [
+    "ConsolePool",
+    "parseEvents",
+    "parseModuleTable",
+    "parseShowOptions",
+  ]);
+});
+
+test("parseModuleTable reads rows with camelCased fields", () => {
+  assert.deepStrictEqual(addon.parseModuleTable(SHOW_EXPLOITS), [
+    {
+      name: "exploit/windows/smb/ms17_010_eternalblue",
+      disclosureDate: "2017-03-14",
+      rank: "average",
+      check: true,
+      description: "MS17-010 EternalBlue",
+    },
+    {
+      name: "exploit/multi/handler",
+      disclosureDate: ".",
+      rank: "manual",
+      check: false,
+      description: "Generic Payload Handler",
+    },
+  ]);
+  assert.deepStrictEqual(addon.parseModuleTable("msf6 > "), []);
+});
+
+test("parser panics become JS errors", () => {
+  // a row without a description
+  const broken = "   -  ----  -----------\n   0  x  2020-01-01  great  Yes\n";
+  assert.throws(() => addon.parseModuleTable(broken), {
+    message: "Failed to parse console output",
+  });
+});
+
+test("parseShowOptions reads options and targets", () => {
+  const options = addon.parseShowOptions(
+    "exploit/unix/ftp/vsftpd_234_backdoor",
+    SHOW_OPTIONS,
+  );
+  assert.strictEqual(options.name, "exploit/unix/ftp/vsftpd_234_backdoor");
+  assert.deepStrictEqual(
+    options.options.map((o) => [o.name, o.defaultValue, o.required, o.kind]),
+    [
+      ["RHOSTS", undefined, true, "address_range"],
+      ["RPORT", "21", true, "port"],
+    ],
+  );
+  assert.strictEqual(options.options[0].advanced, false);
+  assert.deepStrictEqual(options.target, ["0", "Automatic"]);
+});
+
+test("parseEvents reads status lines", () => {
+  assert.deepStrictEqual(
+    addon.parseEvents("\x1b[1;32m[+]\x1b[0m 10.0.0.5:21 - Backdoor found\n[-] Exploit failed"),
+    [
+      { event: "success", message: "Backdoor found", target: "10.0.0.5:21" },
+      { event: "error", message: "Exploit failed", target: null },
+    ],
+  );
+});
+
+test("ConsolePool refuses unknown module kinds", () => {
+  const pool = new addon.ConsolePool(1, "/nonexistent/msfconsole");
+  assert.throws(() => pool.moduleDetails("encoders"), {
+    message: "Unknown module kind: encoders",
+  });
+  pool.shutdown();
+});
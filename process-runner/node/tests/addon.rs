@@ -0,0 +1,63 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Builds the addon with the profile of this test, since `cargo test` does
+/// not build cdylibs, and copies it to a `.node` file so node will load it.
+fn addon() -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().unwrap();
+    // target/<profile>/deps/<test>
+    let profile_dir = exe.parent().and_then(Path::parent).unwrap();
+    let mut build = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string()));
+    build
+        .args(["build", "-p", "process-runner-node"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"));
+    if profile_dir.ends_with("release") {
+        build.arg("--release");
+    }
+    let output = build
+        .output()
+        .map_err(|e| format!("Failed to run cargo: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to build the addon:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let lib = profile_dir.join(format!(
+        "{}process_runner_node{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    ));
+    let dir = std::env::temp_dir().join(format!("addon-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let addon = dir.join("process-runner.node");
+    std::fs::copy(&lib, &addon).map_err(|e| format!("Failed to copy {}: {}", lib.display(), e))?;
+    Ok(addon)
+}
+
+#[test]
+fn exported_functions_work_from_node() {
+    if Command::new("node").arg("--version").output().is_err() {
+        eprintln!("node not found; skipping the addon tests");
+        return;
+    }
+    let addon = match addon() {
+        Ok(addon) => addon,
+        Err(e) => {
+            eprintln!("{}; skipping the addon tests", e);
+            return;
+        }
+    };
+    let output = Command::new("node")
+        .args(["--test", "test/"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .env("PROCESS_RUNNER_ADDON", &addon)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_dir_all(addon.parent().unwrap());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -0,0 +1,247 @@
+use serde::Serialize;
+
+/// Text attributes set by SGR (`ESC [ … m`) sequences. Colours are CSS
+/// values: the 16 basic colours use the xterm palette, 256-colour and
+/// true-colour codes become `#rrggbb`.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fg: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bg: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub bold: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dim: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub italic: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub underline: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub inverse: bool,
+}
+
+impl Style {
+    pub fn is_plain(&self) -> bool {
+        *self == Style::default()
+    }
+
+    fn css(&self) -> String {
+        let (fg, bg) = if self.inverse {
+            (self.bg.as_ref(), self.fg.as_ref())
+        } else {
+            (self.fg.as_ref(), self.bg.as_ref())
+        };
+        let mut css = Vec::new();
+        if let Some(fg) = fg {
+            css.push(format!("color:{}", fg));
+        }
+        if let Some(bg) = bg {
+            css.push(format!("background-color:{}", bg));
+        }
+        if self.bold {
+            css.push("font-weight:bold".to_string());
+        }
+        if self.dim {
+            css.push("opacity:0.7".to_string());
+        }
+        if self.italic {
+            css.push("font-style:italic".to_string());
+        }
+        if self.underline {
+            css.push("text-decoration:underline".to_string());
+        }
+        css.join(";")
+    }
+}
+
+/// A run of text sharing one style.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    #[serde(flatten)]
+    pub style: Style,
+}
+
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd0000", "#00cd00", "#cdcd00", "#0000ee", "#cd00cd", "#00cdcd", "#e5e5e5",
+    "#7f7f7f", "#ff0000", "#00ff00", "#ffff00", "#5c5cff", "#ff00ff", "#00ffff", "#ffffff",
+];
+
+fn indexed_color(n: u8) -> String {
+    match n {
+        0..=15 => PALETTE[n as usize].to_string(),
+        16..=231 => {
+            let n = n - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(n / 36),
+                level((n / 6) % 6),
+                level(n % 6)
+            )
+        }
+        232..=255 => {
+            let v = 8 + (n - 232) * 10;
+            format!("#{:02x}{:02x}{:02x}", v, v, v)
+        }
+    }
+}
+
+/// Reads the colour following a 38/48 code; `params` starts after it.
+fn extended_color(params: &mut std::slice::Iter<u16>) -> Option<String> {
+    match params.next()? {
+        5 => Some(indexed_color(*params.next()? as u8)),
+        2 => {
+            let r = *params.next()? as u8;
+            let g = *params.next()? as u8;
+            let b = *params.next()? as u8;
+            Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+        }
+        _ => None,
+    }
+}
+
+fn apply_sgr(style: &mut Style, params: &[u16]) {
+    if params.is_empty() {
+        *style = Style::default();
+        return;
+    }
+    let mut iter = params.iter();
+    while let Some(code) = iter.next() {
+        match code {
+            0 => *style = Style::default(),
+            1 => style.bold = true,
+            2 => style.dim = true,
+            3 => style.italic = true,
+            4 => style.underline = true,
+            7 => style.inverse = true,
+            22 => {
+                style.bold = false;
+                style.dim = false;
+            }
+            23 => style.italic = false,
+            24 => style.underline = false,
+            27 => style.inverse = false,
+            30..=37 => style.fg = Some(PALETTE[(code - 30) as usize].to_string()),
+            38 => style.fg = extended_color(&mut iter),
+            39 => style.fg = None,
+            40..=47 => style.bg = Some(PALETTE[(code - 40) as usize].to_string()),
+            48 => style.bg = extended_color(&mut iter),
+            49 => style.bg = None,
+            90..=97 => style.fg = Some(PALETTE[(code - 90 + 8) as usize].to_string()),
+            100..=107 => style.bg = Some(PALETTE[(code - 100 + 8) as usize].to_string()),
+            _ => {}
+        }
+    }
+}
+
+/// Moves `text` onto the last span, or a new one if the style changed.
+fn push_span(spans: &mut Vec<Span>, text: &mut String, style: &Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == *style => last.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.clone(),
+            style: style.clone(),
+        }),
+    }
+    text.clear();
+}
+
+/// Splits text containing ANSI escapes into styled spans. SGR sequences
+/// change the style; every other escape sequence is dropped.
+pub fn to_spans(input: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Style::default();
+    let mut text = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            text.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('[') => {
+                chars.next();
+                let mut raw = String::new();
+                let mut terminator = None;
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        terminator = Some(c);
+                        break;
+                    }
+                    raw.push(c);
+                }
+                if terminator == Some('m') {
+                    push_span(&mut spans, &mut text, &style);
+                    let params: Vec<u16> = raw
+                        .split(';')
+                        .filter(|p| !p.is_empty())
+                        .filter_map(|p| p.parse().ok())
+                        .collect();
+                    apply_sgr(&mut style, &params);
+                }
+            }
+            Some(']') => {
+                // OSC: runs until BEL or ESC \
+                chars.next();
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            Some(' '..='/') => {
+                // nF, e.g. `ESC ( B` from `tput sgr0`: intermediates, then
+                // one final byte
+                while chars.next_if(|c| (' '..='/').contains(c)).is_some() {}
+                chars.next();
+            }
+            Some(_) => {
+                chars.next();
+            }
+            None => {}
+        }
+    }
+    push_span(&mut spans, &mut text, &style);
+    spans
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Renders ANSI-coloured text as HTML, one inline-styled `<span>` per run.
+/// Plain runs are emitted as escaped text without a wrapper.
+pub fn to_html(input: &str) -> String {
+    to_spans(input)
+        .iter()
+        .map(|span| {
+            let text = escape_html(&span.text);
+            if span.style.is_plain() {
+                text
+            } else {
+                format!("<span style=\"{}\">{}</span>", span.style.css(), text)
+            }
+        })
+        .collect()
+}
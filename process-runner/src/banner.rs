@@ -0,0 +1,126 @@
+//! Module counts from msfconsole's banner, e.g.
+//! `2482 exploits - 1279 auxiliary - 431 post`, used to tell whether a
+//! scrape found every module or lost rows while parsing `show` tables.
+
+use log::{error, warn};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::msf::ExploitDetails;
+
+/// Module types the banner counts.
+pub const MODULE_TYPES: [&str; 7] = [
+    "exploits",
+    "auxiliary",
+    "post",
+    "payloads",
+    "encoders",
+    "nops",
+    "evasion",
+];
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Banner {
+    /// The framework version, e.g. `6.4.43-dev-`.
+    pub version: Option<String>,
+    /// Modules of each type in `MODULE_TYPES`.
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl Banner {
+    /// Reads the banner printed at startup or by the `banner` command, and
+    /// the `Framework: ...` line of the `version` command. `None` without
+    /// any module counts or version.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = String::from_utf8_lossy(&strip_ansi_escapes::strip(text)).to_string();
+        let count = Regex::new(&format!(r"(\d+) ({})\b", MODULE_TYPES.join("|"))).unwrap();
+        let counts: BTreeMap<String, usize> = count
+            .captures_iter(&text)
+            .filter_map(|c| Some((c[2].to_string(), c[1].parse().ok()?)))
+            .collect();
+        let version = Regex::new(r"(?m)metasploit v(\S+)|^Framework\s*:\s*(\S+)")
+            .unwrap()
+            .captures(&text)
+            .and_then(|c| c.get(1).or(c.get(2)))
+            .map(|v| v.as_str().to_string());
+        (version.is_some() || !counts.is_empty()).then_some(Self { version, counts })
+    }
+
+    pub fn count(&self, module_type: &str) -> Option<usize> {
+        self.counts.get(module_type).copied()
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Completeness {
+    pub module_type: String,
+    /// The banner's count, if it has one for this type.
+    pub expected: Option<usize>,
+    pub found: usize,
+    pub complete: bool,
+    /// Table rows that are missing from the parsed modules or were parsed
+    /// into the wrong columns, as they appear in the output.
+    pub unparsed: Vec<String>,
+}
+
+/// Compares the modules parsed from a `show` table with the banner's count
+/// for their type, and lists the table rows that did not make it through
+/// parsing intact.
+pub fn check_completeness(
+    module_type: &str,
+    expected: Option<usize>,
+    output: &str,
+    parsed: &[ExploitDetails],
+) -> Completeness {
+    let row = Regex::new(r"^\s*\d+\s+(\S+/\S+)").unwrap();
+    let malformed: HashSet<&str> = parsed
+        .iter()
+        // a rank column that is not a rank means the columns were split
+        // wrongly
+        .filter(|d| d.rank.is_none())
+        .map(|d| d.name.as_str())
+        .collect();
+    let names: HashSet<&str> = parsed.iter().map(|d| d.name.as_str()).collect();
+    let unparsed: Vec<String> = output
+        .lines()
+        .filter(|line| {
+            row.captures(line).is_some_and(|c| {
+                let name = c.get(1).unwrap().as_str();
+                !names.contains(name) || malformed.contains(name)
+            })
+        })
+        .map(|line| line.trim().to_string())
+        .collect();
+    Completeness {
+        module_type: module_type.to_string(),
+        expected,
+        found: parsed.len() - malformed.len(),
+        complete: expected.is_none_or(|e| parsed.len() - malformed.len() >= e)
+            && unparsed.is_empty(),
+        unparsed,
+    }
+}
+
+/// Logs each incomplete type with its unparsed rows. Returns whether every
+/// type is complete.
+pub fn report(results: &[Completeness], strict: bool) -> bool {
+    for result in results.iter().filter(|r| !r.complete) {
+        let message = format!(
+            "{}: found {} of {} in the banner, {} rows not parsed",
+            result.module_type,
+            result.found,
+            result.expected.map_or("?".to_string(), |e| e.to_string()),
+            result.unparsed.len()
+        );
+        if strict {
+            error!("{}", message);
+        } else {
+            warn!("{}", message);
+        }
+        for row in &result.unparsed {
+            warn!("  {}", row);
+        }
+    }
+    results.iter().all(|r| r.complete)
+}
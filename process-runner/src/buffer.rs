@@ -0,0 +1,304 @@
+use log::{error, warn};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+
+static SPILL_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// What a buffer does with new lines once it holds `max_bytes`.
+#[derive(Clone, Debug)]
+pub enum OverflowPolicy {
+    /// Evict the oldest lines to make room, like a ring buffer.
+    DropOldest,
+    /// Stall the reader thread until the consumer drains the buffer. The
+    /// child then blocks on its own pipe writes.
+    Block,
+    /// Move the oldest lines to a file created in the given directory.
+    Spill(PathBuf),
+}
+
+#[derive(Clone, Debug)]
+pub struct BufferLimits {
+    pub max_bytes: Option<usize>,
+    pub policy: OverflowPolicy,
+}
+
+impl BufferLimits {
+    pub fn unbounded() -> Self {
+        Self {
+            max_bytes: None,
+            policy: OverflowPolicy::DropOldest,
+        }
+    }
+
+    pub fn bounded(max_bytes: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            max_bytes: Some(max_bytes),
+            policy,
+        }
+    }
+}
+
+impl FromStr for OverflowPolicy {
+    type Err = String;
+
+    /// Parses `drop-oldest`, `block`, `spill` (system temp directory) or
+    /// `spill:<dir>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop-oldest" => Ok(OverflowPolicy::DropOldest),
+            "block" => Ok(OverflowPolicy::Block),
+            "spill" => Ok(OverflowPolicy::Spill(std::env::temp_dir())),
+            _ => match s.strip_prefix("spill:") {
+                Some(dir) => Ok(OverflowPolicy::Spill(PathBuf::from(dir))),
+                None => Err(format!("Unknown overflow policy: {}", s)),
+            },
+        }
+    }
+}
+
+impl BufferLimits {
+    /// Reads `PROCESS_RUNNER_BUFFER_BYTES` (0 for no cap) and
+    /// `PROCESS_RUNNER_BUFFER_POLICY`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        Self::default().with_env()
+    }
+
+    /// Like `from_env`, but keeps `self` for the variables that are not set.
+    pub fn with_env(self) -> Self {
+        let mut limits = self;
+        if let Ok(bytes) = std::env::var("PROCESS_RUNNER_BUFFER_BYTES") {
+            match bytes.parse::<usize>() {
+                Ok(0) => limits = Self::unbounded(),
+                Ok(bytes) => limits.max_bytes = Some(bytes),
+                Err(e) => warn!("Ignoring PROCESS_RUNNER_BUFFER_BYTES={}: {}", bytes, e),
+            }
+        }
+        if let Ok(policy) = std::env::var("PROCESS_RUNNER_BUFFER_POLICY") {
+            match policy.parse() {
+                Ok(policy) => limits.policy = policy,
+                Err(e) => warn!("{}", e),
+            }
+        }
+        limits
+    }
+}
+
+impl Default for BufferLimits {
+    fn default() -> Self {
+        Self::bounded(16 * 1024 * 1024, OverflowPolicy::DropOldest)
+    }
+}
+
+/// Overflow counters for one stream. Reset every time they are taken.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct BufferStats {
+    pub dropped_bytes: u64,
+    pub dropped_lines: u64,
+    pub spilled_bytes: u64,
+    pub spill_path: Option<PathBuf>,
+}
+
+impl BufferStats {
+    pub fn merge(&mut self, other: BufferStats) {
+        self.dropped_bytes += other.dropped_bytes;
+        self.dropped_lines += other.dropped_lines;
+        self.spilled_bytes += other.spilled_bytes;
+        if other.spill_path.is_some() {
+            self.spill_path = other.spill_path;
+        }
+    }
+
+    pub fn overflowed(&self) -> bool {
+        self.dropped_bytes > 0 || self.spilled_bytes > 0
+    }
+}
+
+/// Byte-capped queue of lines. Not synchronised; see `StreamBuffer` for the
+/// version shared with a reader thread.
+pub struct LineBuffer {
+    name: &'static str,
+    lines: VecDeque<String>,
+    bytes: usize,
+    limits: BufferLimits,
+    stats: BufferStats,
+    spill: Option<File>,
+}
+
+impl LineBuffer {
+    pub fn new(name: &'static str, limits: BufferLimits) -> Self {
+        Self {
+            name,
+            lines: VecDeque::new(),
+            bytes: 0,
+            limits,
+            stats: BufferStats::default(),
+            spill: None,
+        }
+    }
+
+    /// Whether `len` more bytes fit without evicting anything.
+    fn fits(&self, len: usize) -> bool {
+        match self.limits.max_bytes {
+            Some(max) => self.bytes + len <= max,
+            None => true,
+        }
+    }
+
+    /// Appends a line, evicting or spilling older lines as the policy says.
+    /// `Block` is treated like `DropOldest` here because nothing else can
+    /// drain this buffer; `StreamBuffer::push` does the actual blocking.
+    pub fn push(&mut self, line: String) {
+        let len = line.len();
+        while !self.fits(len) {
+            match self.lines.pop_front() {
+                Some(old) => {
+                    self.bytes -= old.len();
+                    self.overflow(old);
+                }
+                None => break,
+            }
+        }
+        if !self.fits(len) {
+            // a single line larger than the whole cap
+            self.overflow(line);
+            return;
+        }
+        self.bytes += len;
+        self.lines.push_back(line);
+    }
+
+    fn overflow(&mut self, line: String) {
+        if let OverflowPolicy::Spill(dir) = &self.limits.policy {
+            let dir = dir.clone();
+            if self.write_spill(&dir, &line).is_ok() {
+                self.stats.spilled_bytes += line.len() as u64;
+                return;
+            }
+        }
+        self.stats.dropped_bytes += line.len() as u64;
+        self.stats.dropped_lines += 1;
+    }
+
+    fn write_spill(&mut self, dir: &Path, line: &str) -> std::io::Result<()> {
+        if self.spill.is_none() {
+            let path = dir.join(format!(
+                "process-runner-{}-{}-{}.spill",
+                std::process::id(),
+                self.name,
+                SPILL_SEQ.fetch_add(1, Ordering::Relaxed)
+            ));
+            match OpenOptions::new().create(true).append(true).open(&path) {
+                Ok(file) => {
+                    self.spill = Some(file);
+                    self.stats.spill_path = Some(path);
+                }
+                Err(e) => {
+                    error!("Failed to open spill file {}: {}", path.display(), e);
+                    return Err(e);
+                }
+            }
+        }
+        let file = self.spill.as_mut().unwrap();
+        let res = writeln!(file, "{}", line);
+        if let Err(e) = &res {
+            error!("Failed to write to spill file: {}", e);
+        }
+        res
+    }
+
+    pub fn drain(&mut self) -> Vec<String> {
+        self.bytes = 0;
+        self.lines.drain(..).collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.bytes = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns the counters gathered since the last call and resets them.
+    /// The spill file is kept open so one command's overflow stays together.
+    pub fn take_stats(&mut self) -> BufferStats {
+        let spill_path = self.stats.spill_path.clone();
+        let stats = std::mem::take(&mut self.stats);
+        self.stats.spill_path = spill_path;
+        stats
+    }
+}
+
+/// A `LineBuffer` shared between a reader thread and the consumer.
+pub struct StreamBuffer {
+    state: Mutex<LineBuffer>,
+    readable: Condvar,
+    writable: Condvar,
+}
+
+impl StreamBuffer {
+    pub fn new(name: &'static str, limits: BufferLimits) -> Self {
+        Self {
+            state: Mutex::new(LineBuffer::new(name, limits)),
+            readable: Condvar::new(),
+            writable: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, line: String) {
+        let mut state = self.state.lock().unwrap();
+        if let OverflowPolicy::Block = state.limits.policy {
+            while !state.is_empty() && !state.fits(line.len()) {
+                self.readable.notify_all();
+                state = self.writable.wait(state).unwrap();
+            }
+        }
+        state.push(line);
+        self.readable.notify_one();
+    }
+
+    /// Blocks until at least one line is buffered, then takes all of them.
+    pub fn wait_drain(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        let mut state = self.readable.wait_while(state, |s| s.is_empty()).unwrap();
+        let lines = state.drain();
+        self.writable.notify_all();
+        lines
+    }
+
+    /// Takes whatever is buffered without waiting.
+    pub fn try_drain(&self) -> Vec<String> {
+        let lines = self.state.lock().unwrap().drain();
+        self.writable.notify_all();
+        lines
+    }
+
+    pub fn clear(&self) {
+        self.state.lock().unwrap().clear();
+        self.writable.notify_all();
+    }
+
+    /// Lines buffered and not yet drained.
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn take_stats(&self) -> BufferStats {
+        self.state.lock().unwrap().take_stats()
+    }
+}
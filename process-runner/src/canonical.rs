@@ -0,0 +1,90 @@
+//! Canonical output, for catalogs that are committed and diffed: modules
+//! sorted by name, catalog keys sorted, whitespace in descriptions
+//! collapsed and a trailing newline, so two runs against the same
+//! Metasploit write byte-identical files. Enabled with `--canonical` or
+//! `PROCESS_RUNNER_CANONICAL=1`.
+
+use log::warn;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use crate::catalog::{CatalogEntry, PayloadCatalog};
+use crate::msf::{ExploitDetails, Parameter};
+
+/// Whether `PROCESS_RUNNER_CANONICAL=1` is set.
+pub fn enabled() -> bool {
+    std::env::var("PROCESS_RUNNER_CANONICAL").is_ok_and(|v| v == "1")
+}
+
+/// `text` with runs of whitespace, including wrapped lines, collapsed to
+/// single spaces.
+pub fn text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Module rows sorted by name, without duplicates. Of rows sharing a
+/// name the one with the first description is kept, whatever order the
+/// threads returned them in, and each dropped row is logged.
+pub fn details(mut details: Vec<ExploitDetails>) -> Vec<ExploitDetails> {
+    for detail in &mut details {
+        detail.description = text(&detail.description);
+    }
+    details.sort_by(|a, b| (&a.name, &a.description).cmp(&(&b.name, &b.description)));
+    details.dedup_by(|dropped, kept| {
+        let duplicate = dropped.name == kept.name;
+        if duplicate {
+            warn!(
+                "Dropping duplicate row for {}: {:?}",
+                dropped.name, dropped.description
+            );
+        }
+        duplicate
+    });
+    details
+}
+
+/// The entry with its option descriptions normalized. Options keep the
+/// order `show options` lists them in.
+pub fn entry(mut entry: CatalogEntry) -> CatalogEntry {
+    let options = entry.options.iter_mut().chain(&mut entry.payload_options);
+    for option in options.flatten() {
+        normalize_parameter(option);
+    }
+    entry
+}
+
+fn normalize_parameter(parameter: &mut Parameter) {
+    parameter.description = text(&parameter.description);
+    if let Some(default) = parameter.default_value.as_mut() {
+        *default = default.trim().to_string();
+    }
+}
+
+/// Entries keyed and ordered by module name.
+pub fn catalog(
+    entries: impl IntoIterator<Item = (String, CatalogEntry)>,
+) -> BTreeMap<String, CatalogEntry> {
+    entries
+        .into_iter()
+        .map(|(name, e)| (name, entry(e)))
+        .collect()
+}
+
+/// The payload catalog with its option descriptions normalized.
+pub fn payloads(mut payloads: PayloadCatalog) -> PayloadCatalog {
+    payloads
+        .values_mut()
+        .flatten()
+        .for_each(normalize_parameter);
+    payloads
+}
+
+/// Writes `value` as pretty JSON with a trailing newline.
+pub fn write_json<T: Serialize>(path: &Path, value: &T) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    serde_json::to_writer_pretty(&mut file, value)?;
+    writeln!(file)?;
+    file.flush()
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::msf::{Exploit, Parameter};
+
+/// Payload option schemas, written next to `exploits_options.json`.
+pub const PAYLOADS_FILE: &str = "payloads_options.json";
+
+/// Options of each payload as `use payload/<name>` shows them, by payload
+/// name. Catalog entries refer to these instead of repeating them.
+pub type PayloadCatalog = BTreeMap<String, Vec<Parameter>>;
+
+/// One module in `exploits_options.json`, keyed by module name.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CatalogEntry {
+    #[serde(default)]
+    pub payload: String,
+    /// Missing when the schema is in the payload catalog.
+    #[serde(default)]
+    pub payload_options: Option<Vec<Parameter>>,
+    /// Payload option defaults this module sets differently from the
+    /// payload catalog, e.g. `EXITFUNC`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_overrides: Option<BTreeMap<String, Option<String>>>,
+    #[serde(default)]
+    pub options: Option<Vec<Parameter>>,
+    #[serde(default)]
+    pub target: Option<Vec<String>>,
+}
+
+impl From<Exploit> for CatalogEntry {
+    fn from(exploit: Exploit) -> Self {
+        Self {
+            payload: exploit.payload,
+            payload_options: exploit.payload_options,
+            payload_overrides: None,
+            options: exploit.options,
+            target: exploit.target,
+        }
+    }
+}
+
+impl CatalogEntry {
+    /// Drops the payload options in favour of a reference to `schema`, the
+    /// payload's entry in the payload catalog, keeping differing defaults
+    /// as overrides. Options that differ otherwise are left in place.
+    pub fn reference_payload(&mut self, schema: &[Parameter]) {
+        let Some(options) = &self.payload_options else {
+            return;
+        };
+        let same_schema = options.len() == schema.len()
+            && options.iter().zip(schema).all(|(a, b)| {
+                a.name == b.name && a.required == b.required && a.description == b.description
+            });
+        if !same_schema {
+            return;
+        }
+        let overrides: BTreeMap<String, Option<String>> = options
+            .iter()
+            .zip(schema)
+            .filter(|(a, b)| a.default_value != b.default_value)
+            .map(|(a, _)| (a.name.clone(), a.default_value.clone()))
+            .collect();
+        self.payload_overrides = (!overrides.is_empty()).then_some(overrides);
+        self.payload_options = None;
+    }
+
+    /// Fills in payload options from the payload catalog for entries that
+    /// refer to it.
+    pub fn resolve_payload(&mut self, payloads: &PayloadCatalog) {
+        if self.payload_options.is_some() {
+            return;
+        }
+        let Some(schema) = payloads.get(&self.payload) else {
+            return;
+        };
+        let mut options = schema.clone();
+        for option in &mut options {
+            if let Some(default) = self
+                .payload_overrides
+                .as_ref()
+                .and_then(|o| o.get(&option.name))
+            {
+                option.default_value = default.clone();
+            }
+        }
+        self.payload_options = Some(options);
+    }
+
+    pub fn to_exploit(&self, name: &str) -> Exploit {
+        Exploit {
+            name: name.to_string(),
+            payload: self.payload.clone(),
+            payload_options: self.payload_options.clone(),
+            options: self.options.clone(),
+            target: self.target.clone(),
+        }
+    }
+}
+
+/// The generated options catalog, as written by the scrape.
+pub struct Catalog {
+    pub path: PathBuf,
+    /// When the file was last written.
+    pub modified: SystemTime,
+    pub modules: HashMap<String, CatalogEntry>,
+}
+
+impl Catalog {
+    /// Loads the catalog at `path`. Entries that refer to the payload
+    /// catalog get their payload options from `payloads_options.json` in
+    /// the same directory.
+    pub fn load(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        let modified = file.metadata()?.modified()?;
+        let mut modules: HashMap<String, CatalogEntry> =
+            serde_json::from_reader(BufReader::new(file))?;
+        let payloads = match path.parent().map(|dir| dir.join(PAYLOADS_FILE)) {
+            Some(payloads) if payloads.exists() => {
+                serde_json::from_reader(BufReader::new(File::open(payloads)?))?
+            }
+            _ => PayloadCatalog::new(),
+        };
+        for entry in modules.values_mut() {
+            entry.resolve_payload(&payloads);
+            let options = entry.options.iter_mut().chain(&mut entry.payload_options);
+            options.flatten().for_each(Parameter::infer_kind);
+        }
+        Ok(Self {
+            path,
+            modified,
+            modules,
+        })
+    }
+
+    /// The catalog's file time, without parsing it.
+    pub fn modified_at(path: &Path) -> std::io::Result<SystemTime> {
+        std::fs::metadata(path)?.modified()
+    }
+
+    pub fn get(&self, name: &str) -> Option<Exploit> {
+        self.modules.get(name).map(|entry| entry.to_exploit(name))
+    }
+
+    pub fn age(&self) -> Duration {
+        self.modified.elapsed().unwrap_or_default()
+    }
+
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.age() <= max_age
+    }
+}
@@ -0,0 +1,150 @@
+//! Check-only verification: runs `check` instead of `exploit` for modules
+//! that support it and reads msfconsole's verdict for each target.
+
+use chrono::{DateTime, Utc};
+use log::info;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::command::{Action, CommandBuilder};
+use crate::msf::{ExploitDetails, MSFProcess};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Vulnerable,
+    Appears,
+    Safe,
+    Unknown,
+    Error,
+}
+
+/// msfconsole's `CheckCode` messages, most specific first.
+const VERDICTS: [(&str, CheckStatus); 6] = [
+    ("The target appears to be vulnerable", CheckStatus::Appears),
+    ("The target is vulnerable", CheckStatus::Vulnerable),
+    ("The target is not exploitable", CheckStatus::Safe),
+    (
+        "The service is running, but could not be validated",
+        CheckStatus::Unknown,
+    ),
+    ("Cannot reliably check exploitability", CheckStatus::Unknown),
+    ("This module does not support check", CheckStatus::Error),
+];
+
+/// The verdict in the output of one `check` command, with the line it came
+/// from minus its `[*]` marker. Without a verdict, the last `[-]` line
+/// makes it an error; otherwise it is unknown.
+pub fn parse_check(output: &str) -> (CheckStatus, String) {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let verdict = lines.iter().rev().find_map(|line| {
+        VERDICTS
+            .iter()
+            .find(|(message, _)| line.contains(message))
+            .map(|(_, status)| (*status, strip_marker(line)))
+    });
+    if let Some(verdict) = verdict {
+        return verdict;
+    }
+    match lines.iter().rev().find(|line| line.starts_with("[-]")) {
+        Some(line) => (CheckStatus::Error, strip_marker(line)),
+        None => (
+            CheckStatus::Unknown,
+            "No check result in output".to_string(),
+        ),
+    }
+}
+
+fn strip_marker(line: &str) -> String {
+    ["[+]", "[*]", "[-]", "[!]"]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .unwrap_or(line)
+        .trim()
+        .to_string()
+}
+
+/// Modules that support `check` and, given a filter, whose name matches it.
+pub fn select_modules<'a>(
+    modules: &'a [ExploitDetails],
+    filter: Option<&Regex>,
+) -> Vec<&'a ExploitDetails> {
+    modules
+        .iter()
+        .filter(|m| m.check == Some(true))
+        .filter(|m| filter.is_none_or(|f| f.is_match(&m.name)))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CheckResult {
+    pub target: String,
+    pub module: String,
+    pub status: CheckStatus,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CheckReport {
+    pub started: DateTime<Utc>,
+    pub finished: DateTime<Utc>,
+    pub results: Vec<CheckResult>,
+}
+
+/// Runs `check` with `module` against `target`. `options` are set before
+/// the check; RHOSTS is always the target. Commands the policy or scope
+/// guard refuses make the result an error.
+pub fn check_target(
+    msf: &mut MSFProcess,
+    module: &str,
+    target: &str,
+    options: &[(String, String)],
+) -> CheckResult {
+    let result = |status, message: String| CheckResult {
+        target: target.to_string(),
+        module: module.to_string(),
+        status,
+        message,
+    };
+    let commands = CommandBuilder::new(module)
+        .options(options.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+        .option("RHOSTS", target)
+        .action(Action::Check)
+        .commands();
+    let commands = match commands {
+        Ok(commands) => commands,
+        Err(e) => return result(CheckStatus::Error, e),
+    };
+    for command in &commands {
+        if let Some(reason) = msf.run_command(command).refused {
+            msf.run_command("back");
+            return result(CheckStatus::Error, reason);
+        }
+    }
+    let (status, message) = parse_check(&msf.output().join("\n"));
+    msf.run_command("back");
+    result(status, message)
+}
+
+/// Checks every target with every module, target by target.
+pub fn run_checks(
+    msf: &mut MSFProcess,
+    modules: &[&ExploitDetails],
+    targets: &[String],
+    options: &[(String, String)],
+) -> CheckReport {
+    let started = Utc::now();
+    let mut results = Vec::new();
+    for target in targets {
+        for module in modules {
+            let result = check_target(msf, &module.name, target, options);
+            info!("{} {}: {:?}", target, module.name, result.status);
+            results.push(result);
+        }
+    }
+    CheckReport {
+        started,
+        finished: Utc::now(),
+        results,
+    }
+}
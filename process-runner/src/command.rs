@@ -0,0 +1,186 @@
+//! Builds the msfconsole commands that configure and launch a module, so
+//! callers do not concatenate `set` lines themselves.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::catalog::Catalog;
+use crate::msf;
+use crate::validate::{self, Validation};
+
+/// What to do once the module is configured.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Run,
+    Exploit,
+    Check,
+}
+
+impl Action {
+    /// `exploit` for exploit modules, `run` for everything else.
+    pub fn default_for(module: &str) -> Self {
+        if module.starts_with("exploit/") {
+            Action::Exploit
+        } else {
+            Action::Run
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Run => "run",
+            Action::Exploit => "exploit",
+            Action::Check => "check",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CommandBuilder {
+    module: String,
+    payload: Option<String>,
+    target: Option<String>,
+    options: Vec<(String, String)>,
+    action: Option<Action>,
+}
+
+impl CommandBuilder {
+    pub fn new(module: &str) -> Self {
+        Self {
+            module: module.to_string(),
+            payload: None,
+            target: None,
+            options: Vec::new(),
+            action: None,
+        }
+    }
+
+    pub fn payload(mut self, payload: &str) -> Self {
+        self.payload = Some(payload.to_string());
+        self
+    }
+
+    /// Target index or name.
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    /// Options are set in the order given; setting one again replaces it.
+    pub fn option(mut self, name: &str, value: &str) -> Self {
+        match self
+            .options
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            Some(option) => option.1 = value.to_string(),
+            None => self.options.push((name.to_string(), value.to_string())),
+        }
+        self
+    }
+
+    pub fn options<'a>(self, options: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        options
+            .into_iter()
+            .fold(self, |builder, (name, value)| builder.option(name, value))
+    }
+
+    pub fn action(mut self, action: Action) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Every value the commands set, keyed by option name, including
+    /// PAYLOAD and TARGET.
+    pub fn values(&self) -> HashMap<String, String> {
+        let mut values: HashMap<String, String> = self.options.iter().cloned().collect();
+        if let Some(payload) = &self.payload {
+            values.insert("PAYLOAD".to_string(), payload.clone());
+        }
+        if let Some(target) = &self.target {
+            values.insert("TARGET".to_string(), target.clone());
+        }
+        values
+    }
+
+    /// Checks the configuration against the catalog.
+    pub fn validate(&self, catalog: &Catalog) -> Result<Validation, String> {
+        validate::validate_module(catalog, &self.module, &self.values())
+    }
+
+    /// The commands, in order: `use`, `set PAYLOAD`, `set TARGET`, one `set`
+    /// per option and the action. Values are quoted where msfconsole would
+    /// otherwise split or strip them; names and values that cannot be sent
+    /// on one console line are refused.
+    pub fn commands(&self) -> Result<Vec<String>, String> {
+        if !msf::is_module_name(&self.module) {
+            return Err(format!("Invalid module name: {:?}", self.module));
+        }
+        let mut commands = vec![format!("use {}", self.module)];
+        if let Some(payload) = &self.payload {
+            if !msf::is_module_name(payload) {
+                return Err(format!("Invalid payload name: {:?}", payload));
+            }
+            commands.push(format!("set PAYLOAD {}", payload));
+        }
+        if let Some(target) = &self.target {
+            commands.push(format!("set TARGET {}", quote(target)?));
+        }
+        for (name, value) in &self.options {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid option name: {:?}", name));
+            }
+            commands.push(format!("set {} {}", name, quote(value)?));
+        }
+        let action = self
+            .action
+            .unwrap_or_else(|| Action::default_for(&self.module));
+        commands.push(action.as_str().to_string());
+        Ok(commands)
+    }
+
+    /// Validates against the catalog, then returns the commands.
+    pub fn build(&self, catalog: &Catalog) -> Result<Vec<String>, String> {
+        let validation = self.validate(catalog)?;
+        if !validation.valid {
+            let problems: Vec<String> = validation.problems.iter().map(|p| p.to_string()).collect();
+            return Err(problems.join("; "));
+        }
+        self.commands()
+    }
+
+    /// The commands as an msfconsole resource script, for `msfconsole -r`.
+    pub fn resource_script(&self) -> Result<String, String> {
+        let mut script = format!("# {}\n", self.module);
+        for command in self.commands()? {
+            script.push_str(&command);
+            script.push('\n');
+        }
+        Ok(script)
+    }
+}
+
+/// Quotes `value` for a `set` line. msfconsole splits arguments on
+/// whitespace and joins them back with single spaces, strips quotes and
+/// backslashes, and treats `;` and `#` specially, so such values go in
+/// double quotes with `"` and `\` escaped. An empty value becomes `""`,
+/// since a bare `set NAME` shows the option instead of clearing it.
+pub fn quote(value: &str) -> Result<String, String> {
+    if value.contains(['\n', '\r']) {
+        return Err(format!("Value contains a line break: {:?}", value));
+    }
+    let plain = !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || ['"', '\'', '\\', ';', '#'].contains(&c));
+    if plain {
+        return Ok(value.to_string());
+    }
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    Ok(format!("\"{}\"", escaped))
+}
@@ -0,0 +1,393 @@
+//! Cross-checks two catalogs of the same modules, e.g. one scraped from the
+//! console and one extracted from module sources, so either extraction
+//! path can be trusted only where the other agrees with it.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::catalog::{Catalog, CatalogEntry};
+use crate::manifest::Manifest;
+use crate::msf::{ExploitDetails, Parameter};
+use crate::rank::Rank;
+
+/// One catalog: `exploits_options.json` and, for ranks, `exploits.json`
+/// from the same directory, plus the version from its manifest.
+pub struct CatalogSource {
+    pub label: String,
+    pub msf_version: Option<String>,
+    pub entries: HashMap<String, CatalogEntry>,
+    pub details: HashMap<String, ExploitDetails>,
+}
+
+impl CatalogSource {
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let options = dir.join("exploits_options.json");
+        let catalog = Catalog::load(&options)
+            .map_err(|e| format!("Failed to load {}: {}", options.display(), e))?;
+        let modules = dir.join("exploits.json");
+        let details: Vec<ExploitDetails> = match File::open(&modules) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| format!("Failed to parse {}: {}", modules.display(), e))?,
+            Err(_) => Vec::new(),
+        };
+        Ok(Self {
+            label: dir.display().to_string(),
+            msf_version: Manifest::load(dir).ok().and_then(|m| m.msf_version),
+            entries: catalog.modules,
+            details: details.into_iter().map(|d| (d.name.clone(), d)).collect(),
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "discrepancy", rename_all = "snake_case")]
+pub enum Discrepancy {
+    MissingOption {
+        option: String,
+        missing_from: Side,
+    },
+    Required {
+        option: String,
+        left: bool,
+        right: bool,
+    },
+    Default {
+        option: String,
+        left: Option<String>,
+        right: Option<String>,
+    },
+    /// One side's description stops where the other's goes on, which is
+    /// what a wrapped description row that was not joined looks like.
+    TruncatedDescription {
+        option: String,
+        truncated_on: Side,
+    },
+    Target {
+        left: Option<Vec<String>>,
+        right: Option<Vec<String>>,
+    },
+    Rank {
+        left: Option<Rank>,
+        right: Option<Rank>,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Discrepancy::MissingOption {
+                option,
+                missing_from,
+            } => write!(f, "{} is missing on the {:?} side", option, missing_from),
+            Discrepancy::Required {
+                option,
+                left,
+                right,
+            } => write!(f, "{} required: {} vs {}", option, left, right),
+            Discrepancy::Default {
+                option,
+                left,
+                right,
+            } => write!(f, "{} default: {:?} vs {:?}", option, left, right),
+            Discrepancy::TruncatedDescription {
+                option,
+                truncated_on,
+            } => write!(
+                f,
+                "{} description is truncated on the {:?} side",
+                option, truncated_on
+            ),
+            Discrepancy::Target { left, right } => {
+                write!(f, "target: {:?} vs {:?}", left, right)
+            }
+            Discrepancy::Rank { left, right } => {
+                let rank = |r: &Option<Rank>| r.map_or("unknown", Rank::as_str);
+                write!(f, "rank: {} vs {}", rank(left), rank(right))
+            }
+        }
+    }
+}
+
+impl Discrepancy {
+    /// The summary table column that counts this discrepancy.
+    pub fn column(&self) -> &'static str {
+        match self {
+            Discrepancy::MissingOption { .. } => "missing",
+            Discrepancy::Required { .. } => "required",
+            Discrepancy::Default { .. } => "default",
+            Discrepancy::TruncatedDescription { .. } => "truncated",
+            Discrepancy::Target { .. } => "target",
+            Discrepancy::Rank { .. } => "rank",
+        }
+    }
+}
+
+const COLUMNS: [&str; 6] = [
+    "missing",
+    "required",
+    "default",
+    "truncated",
+    "target",
+    "rank",
+];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ModuleComparison {
+    pub module: String,
+    pub discrepancies: Vec<Discrepancy>,
+    /// Options are missing on a side that also has a truncated description:
+    /// rows after a wrapped description were likely dropped while parsing
+    /// `show options`.
+    pub dropped_rows_suspected: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Comparison {
+    pub left: String,
+    pub right: String,
+    pub left_version: Option<String>,
+    pub right_version: Option<String>,
+    pub compared: usize,
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+    /// Modules with at least one discrepancy, by name.
+    pub modules: Vec<ModuleComparison>,
+    /// Discrepancies of each kind over all modules.
+    pub totals: BTreeMap<String, usize>,
+}
+
+/// Compares every module both catalogs have. Payload options are only
+/// compared when both sides list them, as module sources have none.
+pub fn compare(left: &CatalogSource, right: &CatalogSource) -> Comparison {
+    let left_names: BTreeSet<&String> = left.entries.keys().collect();
+    let right_names: BTreeSet<&String> = right.entries.keys().collect();
+    let mut modules = Vec::new();
+    for name in left_names.intersection(&right_names) {
+        let comparison = compare_module(
+            name,
+            &left.entries[*name],
+            &right.entries[*name],
+            left.details.get(*name),
+            right.details.get(*name),
+        );
+        if !comparison.discrepancies.is_empty() {
+            modules.push(comparison);
+        }
+    }
+    let mut totals: BTreeMap<String, usize> = COLUMNS.iter().map(|c| (c.to_string(), 0)).collect();
+    for discrepancy in modules.iter().flat_map(|m| &m.discrepancies) {
+        *totals.entry(discrepancy.column().to_string()).or_default() += 1;
+    }
+    Comparison {
+        left: left.label.clone(),
+        right: right.label.clone(),
+        left_version: left.msf_version.clone(),
+        right_version: right.msf_version.clone(),
+        compared: left_names.intersection(&right_names).count(),
+        only_left: left_names
+            .difference(&right_names)
+            .map(|n| n.to_string())
+            .collect(),
+        only_right: right_names
+            .difference(&left_names)
+            .map(|n| n.to_string())
+            .collect(),
+        modules,
+        totals,
+    }
+}
+
+pub fn compare_module(
+    name: &str,
+    left: &CatalogEntry,
+    right: &CatalogEntry,
+    left_details: Option<&ExploitDetails>,
+    right_details: Option<&ExploitDetails>,
+) -> ModuleComparison {
+    let mut discrepancies = Vec::new();
+    compare_options(
+        left.options.as_deref().unwrap_or_default(),
+        right.options.as_deref().unwrap_or_default(),
+        &mut discrepancies,
+    );
+    if let (Some(left), Some(right)) = (&left.payload_options, &right.payload_options) {
+        compare_options(left, right, &mut discrepancies);
+    }
+    if left.target != right.target {
+        discrepancies.push(Discrepancy::Target {
+            left: left.target.clone(),
+            right: right.target.clone(),
+        });
+    }
+    if let (Some(left), Some(right)) = (left_details, right_details) {
+        if left.rank != right.rank {
+            discrepancies.push(Discrepancy::Rank {
+                left: left.rank,
+                right: right.rank,
+            });
+        }
+    }
+
+    let suspected = |side: Side| {
+        let truncated = discrepancies.iter().any(|d| {
+            matches!(d, Discrepancy::TruncatedDescription { truncated_on, .. } if *truncated_on == side)
+        });
+        let missing = discrepancies.iter().any(|d| {
+            matches!(d, Discrepancy::MissingOption { missing_from, .. } if *missing_from == side)
+        });
+        truncated && missing
+    };
+    ModuleComparison {
+        module: name.to_string(),
+        dropped_rows_suspected: suspected(Side::Left) || suspected(Side::Right),
+        discrepancies,
+    }
+}
+
+fn compare_options(left: &[Parameter], right: &[Parameter], discrepancies: &mut Vec<Discrepancy>) {
+    let find = |options: &[Parameter], name: &str| options.iter().find(|o| o.name == name).cloned();
+    for option in left {
+        match find(right, &option.name) {
+            Some(other) => compare_option(option, &other, discrepancies),
+            None => discrepancies.push(Discrepancy::MissingOption {
+                option: option.name.clone(),
+                missing_from: Side::Right,
+            }),
+        }
+    }
+    for option in right {
+        if find(left, &option.name).is_none() {
+            discrepancies.push(Discrepancy::MissingOption {
+                option: option.name.clone(),
+                missing_from: Side::Left,
+            });
+        }
+    }
+}
+
+fn compare_option(left: &Parameter, right: &Parameter, discrepancies: &mut Vec<Discrepancy>) {
+    let option = left.name.clone();
+    if left.required != right.required {
+        discrepancies.push(Discrepancy::Required {
+            option: option.clone(),
+            left: left.required,
+            right: right.required,
+        });
+    }
+    // an empty current setting and no default look the same in `show options`
+    let default = |p: &Parameter| p.default_value.clone().filter(|d| !d.is_empty());
+    if default(left) != default(right) {
+        discrepancies.push(Discrepancy::Default {
+            option: option.clone(),
+            left: default(left),
+            right: default(right),
+        });
+    }
+    let words = |p: &Parameter| {
+        p.description
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let (left_words, right_words) = (words(left), words(right));
+    let truncated_on =
+        if left_words.len() < right_words.len() && right_words.starts_with(&left_words) {
+            Some(Side::Left)
+        } else if right_words.len() < left_words.len() && left_words.starts_with(&right_words) {
+            Some(Side::Right)
+        } else {
+            None
+        };
+    if let Some(truncated_on) = truncated_on {
+        discrepancies.push(Discrepancy::TruncatedDescription {
+            option,
+            truncated_on,
+        });
+    }
+}
+
+/// The comparison as a plain-text table: one row per module with
+/// discrepancies, counted by kind, then the totals.
+pub fn summary_table(comparison: &Comparison) -> String {
+    let width = comparison
+        .modules
+        .iter()
+        .map(|m| m.module.len())
+        .chain(["Module".len(), "Total".len()])
+        .max()
+        .unwrap_or_default();
+    let row = |name: &str, counts: Vec<String>, flag: &str| {
+        let counts: Vec<String> = counts.iter().map(|c| format!("{:>9}", c)).collect();
+        format!("{:<width$}  {}  {}", name, counts.join(" "), flag)
+            .trim_end()
+            .to_string()
+    };
+    let mut lines = vec![row(
+        "Module",
+        COLUMNS.iter().map(|c| c.to_string()).collect(),
+        "",
+    )];
+    for module in &comparison.modules {
+        let counts = COLUMNS
+            .iter()
+            .map(|c| {
+                let count = module
+                    .discrepancies
+                    .iter()
+                    .filter(|d| d.column() == *c)
+                    .count();
+                count.to_string()
+            })
+            .collect();
+        let flag = if module.dropped_rows_suspected {
+            "dropped rows?"
+        } else {
+            ""
+        };
+        lines.push(row(&module.module, counts, flag));
+    }
+    lines.push(row(
+        "Total",
+        COLUMNS
+            .iter()
+            .map(|c| {
+                comparison
+                    .totals
+                    .get(*c)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect(),
+        "",
+    ));
+    lines.push(format!(
+        "{} modules compared, {} with discrepancies, {} only in {}, {} only in {}",
+        comparison.compared,
+        comparison.modules.len(),
+        comparison.only_left.len(),
+        comparison.left,
+        comparison.only_right.len(),
+        comparison.right
+    ));
+    if let (Some(left), Some(right)) = (&comparison.left_version, &comparison.right_version) {
+        if left != right {
+            lines.push(format!(
+                "Catalogs are from different Metasploit versions: {} and {}",
+                left, right
+            ));
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,170 @@
+use log::{error, info};
+use serde::Serialize;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::events::ConsoleEvent;
+use crate::msf::{CommandResult, MSFProcess, OutputHook};
+use crate::spawn::ProcessBuilder;
+
+type Job = Box<dyn FnOnce(&mut MSFProcess) + Send>;
+
+enum Message {
+    Run(Job),
+    Stop,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "state", content = "error", rename_all = "lowercase")]
+pub enum ConsoleState {
+    /// msfconsole is still loading; jobs queue up until it's ready.
+    Starting,
+    Ready,
+    Failed(String),
+    Stopped,
+}
+
+/// Output of one command run through `Console::run`.
+#[derive(Serialize, Clone, Debug)]
+pub struct RunOutput {
+    pub command: String,
+    pub output: String,
+    pub events: Vec<ConsoleEvent>,
+    pub result: CommandResult,
+}
+
+/// An msfconsole owned by a worker thread. Jobs sent to it run one at a
+/// time, in order, so callers on other threads never interleave commands.
+pub struct Console {
+    name: String,
+    tx: Sender<Message>,
+    state: Arc<Mutex<ConsoleState>>,
+    last_used: Mutex<Instant>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Console {
+    /// Starts the worker; msfconsole itself is spawned on that thread, so
+    /// this returns immediately in the `Starting` state.
+    pub fn spawn(name: &str, builder: ProcessBuilder, hook: Option<OutputHook>) -> Arc<Self> {
+        let (tx, rx) = mpsc::channel::<Message>();
+        let state = Arc::new(Mutex::new(ConsoleState::Starting));
+        let worker_state = Arc::clone(&state);
+        let worker_name = name.to_string();
+
+        let worker = thread::spawn(move || {
+            let mut msf = match MSFProcess::try_with_builder(builder) {
+                Ok(msf) => msf,
+                Err(e) => {
+                    error!("{}: Failed to start msfconsole: {}", worker_name, e);
+                    *worker_state.lock().unwrap() = ConsoleState::Failed(e.to_string());
+                    return;
+                }
+            };
+            if let Some(hook) = hook {
+                msf.set_output_hook(hook);
+            }
+            *worker_state.lock().unwrap() = ConsoleState::Ready;
+            info!("{}: msfconsole ready", worker_name);
+
+            for message in rx {
+                match message {
+                    Message::Run(job) => {
+                        // a parser panic fails the job, not the console
+                        if catch_unwind(AssertUnwindSafe(|| job(&mut msf))).is_err() {
+                            error!("{}: job panicked", worker_name);
+                        }
+                    }
+                    Message::Stop => break,
+                }
+            }
+            *worker_state.lock().unwrap() = ConsoleState::Stopped;
+            info!("{}: msfconsole stopped", worker_name);
+        });
+
+        Arc::new(Self {
+            name: name.to_string(),
+            tx,
+            state,
+            last_used: Mutex::new(Instant::now()),
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> ConsoleState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn is_running(&self) -> bool {
+        matches!(self.state(), ConsoleState::Starting | ConsoleState::Ready)
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_used.lock().unwrap().elapsed()
+    }
+
+    /// Queues `job` without waiting for it.
+    pub fn submit<F>(&self, job: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut MSFProcess) + Send + 'static,
+    {
+        *self.last_used.lock().unwrap() = Instant::now();
+        self.tx
+            .send(Message::Run(Box::new(job)))
+            .map_err(|_| format!("{}: Metasploit is not running", self.name))
+    }
+
+    /// Runs `job` on the console and waits for its result.
+    pub fn call<R, F>(&self, job: F) -> Result<R, String>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut MSFProcess) -> R + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        self.submit(move |msf| {
+            let _ = reply_tx.send(job(msf));
+        })?;
+        reply_rx.recv().map_err(|_| match self.state() {
+            ConsoleState::Failed(e) => format!("{}: Metasploit failed to start: {}", self.name, e),
+            _ => format!("{}: command failed", self.name),
+        })
+    }
+
+    /// Runs each command in turn and collects their output.
+    pub fn run(&self, commands: Vec<String>) -> Result<Vec<RunOutput>, String> {
+        self.call(move |msf| {
+            commands
+                .into_iter()
+                .map(|command| {
+                    let result = msf.run_command(&command);
+                    RunOutput {
+                        output: msf.output().join("\n"),
+                        events: msf.events(),
+                        command,
+                        result,
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Asks the worker to exit once queued jobs are done. Dropping the
+    /// `MSFProcess` kills msfconsole.
+    pub fn stop(&self) {
+        let _ = self.tx.send(Message::Stop);
+    }
+
+    /// Blocks until the worker has exited, e.g. after `stop`.
+    pub fn wait(&self) {
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,366 @@
+//! `daemon` mode: JSON-RPC 2.0 over stdio, one request or response per
+//! line, so the Node server can drive consoles without node-pty.
+//!
+//! Methods:
+//! - `catalog.list {prefix?}` -> `{modules, fresh, age_secs}`
+//! - `module.options {name}` -> options, payload options and targets
+//! - `module.validate {name, options}` -> `{module, valid, problems}`,
+//!   checking option values against the catalog
+//! - `console.open {}` -> `{console}`, a dedicated console for stateful work
+//! - `console.run {command | commands, console?}` -> one result per command,
+//!   on the given console or a pooled one
+//! - `console.close {console}` -> `{closed: true}`
+//!
+//! Commands the policy refuses fail with code -32001.
+//!
+//! Requests are handled concurrently, so responses may come back out of
+//! order; match them by `id`. Logs go to stderr.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::console::Console;
+use crate::lookup::OptionsLookup;
+use crate::policy::Policy;
+use crate::pool::ConsolePool;
+use crate::spawn::ProcessBuilder;
+use crate::validate;
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// A console or lookup failed while handling a valid request.
+pub const SERVER_ERROR: i64 = -32000;
+/// The policy does not allow a command.
+pub const REFUSED: i64 = -32001;
+
+#[derive(Serialize, Debug)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcRequest {
+    jsonrpc: Option<String>,
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Params {
+    prefix: Option<String>,
+    name: Option<String>,
+    command: Option<String>,
+    commands: Option<Vec<String>>,
+    console: Option<String>,
+    options: Option<HashMap<String, String>>,
+}
+
+pub struct DaemonConfig {
+    pub catalog: PathBuf,
+    pub catalog_max_age: Duration,
+    pub pool_size: usize,
+    /// Commands `console.run` accepts; `None` allows all.
+    pub policy: Option<Arc<Policy>>,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            catalog: PathBuf::from("exploits_options.json"),
+            catalog_max_age: Duration::from_secs(24 * 60 * 60),
+            pool_size: 1,
+            policy: Policy::from_env(),
+        }
+    }
+}
+
+type BuilderFn = Arc<dyn Fn() -> ProcessBuilder + Send + Sync>;
+
+pub struct Daemon {
+    builder: BuilderFn,
+    pool: Arc<ConsolePool>,
+    lookup: OptionsLookup,
+    consoles: Mutex<HashMap<String, Arc<Console>>>,
+    next_console: AtomicU64,
+    policy: Option<Arc<Policy>>,
+}
+
+impl Daemon {
+    /// `builder` makes the command each console runs: msfconsole normally,
+    /// or a replay console in tests. Consoles opened with `console.open`
+    /// also enforce the policy themselves.
+    pub fn new(
+        config: DaemonConfig,
+        builder: impl Fn() -> ProcessBuilder + Send + Sync + 'static,
+    ) -> Self {
+        let builder: BuilderFn = Arc::new(builder);
+        let pool_builder = Arc::clone(&builder);
+        let pool = Arc::new(ConsolePool::new("pool", config.pool_size, move || {
+            pool_builder()
+        }));
+        Self {
+            builder,
+            lookup: OptionsLookup::new(config.catalog, config.catalog_max_age, Arc::clone(&pool)),
+            pool,
+            consoles: Mutex::new(HashMap::new()),
+            next_console: AtomicU64::new(1),
+            policy: config.policy,
+        }
+    }
+
+    /// Reads requests until `input` ends, then waits for in-flight
+    /// requests and stops every console.
+    pub fn run<I, O>(self: Arc<Self>, input: I, output: O) -> std::io::Result<()>
+    where
+        I: BufRead,
+        O: Write + Send + 'static,
+    {
+        let output = Arc::new(Mutex::new(output));
+        let mut handlers = Vec::new();
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let request: RpcRequest = match serde_json::from_str::<Value>(&line) {
+                Err(e) => {
+                    respond(
+                        &output,
+                        Value::Null,
+                        Err(RpcError::new(PARSE_ERROR, e.to_string())),
+                    );
+                    continue;
+                }
+                Ok(value) => {
+                    let id = value.get("id").cloned().unwrap_or(Value::Null);
+                    match serde_json::from_value(value) {
+                        Ok(request) => request,
+                        Err(e) => {
+                            respond(
+                                &output,
+                                id,
+                                Err(RpcError::new(INVALID_REQUEST, e.to_string())),
+                            );
+                            continue;
+                        }
+                    }
+                }
+            };
+            if request.jsonrpc.as_deref().is_some_and(|v| v != "2.0") {
+                let id = request.id.unwrap_or(Value::Null);
+                respond(
+                    &output,
+                    id,
+                    Err(RpcError::new(
+                        INVALID_REQUEST,
+                        "Unsupported jsonrpc version",
+                    )),
+                );
+                continue;
+            }
+
+            let daemon = Arc::clone(&self);
+            let output = Arc::clone(&output);
+            handlers.push(thread::spawn(move || {
+                let result = daemon.handle(&request.method, request.params);
+                // requests without an id are notifications
+                if let Some(id) = request.id {
+                    respond(&output, id, result);
+                }
+            }));
+            handlers.retain(|h| !h.is_finished());
+        }
+        for handler in handlers {
+            let _ = handler.join();
+        }
+        self.shutdown();
+        Ok(())
+    }
+
+    pub fn handle(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let params: Params = match params {
+            Value::Null => Params::default(),
+            params => serde_json::from_value(params)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))?,
+        };
+        match method {
+            "catalog.list" => self.catalog_list(params),
+            "module.options" => self.module_options(params),
+            "module.validate" => self.module_validate(params),
+            "console.open" => self.console_open(),
+            "console.run" => self.console_run(params),
+            "console.close" => self.console_close(params),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    fn catalog_list(&self, params: Params) -> Result<Value, RpcError> {
+        let catalog = self
+            .lookup
+            .load_catalog()
+            .ok_or_else(|| RpcError::new(SERVER_ERROR, "No catalog available"))?;
+        let prefix = params.prefix.unwrap_or_default();
+        let mut modules: Vec<&String> = catalog
+            .modules
+            .keys()
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        modules.sort();
+        Ok(json!({
+            "modules": modules,
+            "fresh": catalog.is_fresh(self.lookup.max_age()),
+            "age_secs": catalog.age().as_secs(),
+        }))
+    }
+
+    fn module_options(&self, params: Params) -> Result<Value, RpcError> {
+        let name = params
+            .name
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing name"))?;
+        let options = self
+            .lookup
+            .lookup(&name)
+            .map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        Ok(json!(options))
+    }
+
+    fn module_validate(&self, params: Params) -> Result<Value, RpcError> {
+        let name = params
+            .name
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing name"))?;
+        let catalog = self
+            .lookup
+            .load_catalog()
+            .ok_or_else(|| RpcError::new(SERVER_ERROR, "No catalog available"))?;
+        let validation =
+            validate::validate_module(&catalog, &name, &params.options.unwrap_or_default())
+                .map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        Ok(json!(validation))
+    }
+
+    fn console_open(&self) -> Result<Value, RpcError> {
+        let id = format!(
+            "console-{}",
+            self.next_console.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut builder = (self.builder)();
+        if let Some(policy) = &self.policy {
+            builder = builder.policy(Arc::clone(policy));
+        }
+        let console = Console::spawn(&id, builder, None);
+        self.consoles.lock().unwrap().insert(id.clone(), console);
+        info!("Opened {}", id);
+        Ok(json!({ "console": id }))
+    }
+
+    fn console_run(&self, params: Params) -> Result<Value, RpcError> {
+        let commands = match (params.command, params.commands) {
+            (Some(command), None) => vec![command],
+            (None, Some(commands)) if !commands.is_empty() => commands,
+            _ => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "Expected one of command or commands",
+                ))
+            }
+        };
+        if let Some(bad) = commands.iter().find(|c| c.contains(['\n', '\r'])) {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("Command contains a line break: {:?}", bad),
+            ));
+        }
+        if let Some(policy) = &self.policy {
+            for command in &commands {
+                policy
+                    .check(command)
+                    .map_err(|e| RpcError::new(REFUSED, e))?;
+            }
+        }
+        let console = match params.console {
+            Some(id) => self
+                .consoles
+                .lock()
+                .unwrap()
+                .get(&id)
+                .cloned()
+                .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown console: {}", id)))?,
+            None => self.pool.get(),
+        };
+        let results = console
+            .run(commands)
+            .map_err(|e| RpcError::new(SERVER_ERROR, e))?;
+        Ok(json!(results))
+    }
+
+    fn console_close(&self, params: Params) -> Result<Value, RpcError> {
+        let id = params
+            .console
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing console"))?;
+        let console = self
+            .consoles
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown console: {}", id)))?;
+        console.stop();
+        console.wait();
+        info!("Closed {}", id);
+        Ok(json!({ "closed": true }))
+    }
+
+    fn shutdown(&self) {
+        let consoles: Vec<Arc<Console>> = self
+            .consoles
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, c)| c)
+            .collect();
+        for console in &consoles {
+            console.stop();
+        }
+        for console in &consoles {
+            console.wait();
+        }
+        self.pool.shutdown();
+    }
+}
+
+fn respond<O: Write>(output: &Mutex<O>, id: Value, result: Result<Value, RpcError>) {
+    let response = match result {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(error) => json!({"jsonrpc": "2.0", "id": id, "error": error}),
+    };
+    let mut output = output.lock().unwrap();
+    let res = writeln!(output, "{}", response).and_then(|_| output.flush());
+    if let Err(e) = res {
+        warn!("Failed to write response: {}", e);
+    }
+}
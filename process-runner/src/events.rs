@@ -0,0 +1,178 @@
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+/// Something msfconsole reported on a status line. Lines that only carry a
+/// `[*]`/`[+]`/`[-]`/`[!]` prefix become `Info`, `Success`, `Error` or
+/// `Warning`; `target` is the `host:port - ` prefix modules put in front of
+/// their messages, when present.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum ConsoleEvent {
+    Info {
+        message: String,
+        target: Option<String>,
+    },
+    Success {
+        message: String,
+        target: Option<String>,
+    },
+    Error {
+        message: String,
+        target: Option<String>,
+    },
+    Warning {
+        message: String,
+        target: Option<String>,
+    },
+    JobStarted {
+        job_id: u32,
+    },
+    HandlerStarted {
+        /// e.g. "reverse TCP", "bind TCP", "HTTPS reverse"
+        handler: String,
+        address: String,
+        port: Option<u16>,
+    },
+    SessionOpened {
+        session_id: u32,
+        /// e.g. "Command shell", "Meterpreter"
+        session_type: String,
+        local: Option<String>,
+        remote: Option<String>,
+    },
+    SessionClosed {
+        session_id: u32,
+        session_type: String,
+        reason: Option<String>,
+    },
+    ExploitCompleted {
+        session_created: bool,
+    },
+    ExploitFailed {
+        /// The bracketed failure kind, e.g. "unreachable" or "not-vulnerable"
+        kind: Option<String>,
+        reason: String,
+    },
+    ModuleCompleted,
+    ScanProgress {
+        scanned: u32,
+        total: u32,
+    },
+}
+
+static STATUS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|> )\[([*+!-])\] (.*?)\s*$").unwrap());
+static TARGET_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\S+:\d+|\d{1,3}(?:\.\d{1,3}){3})\s+- (.*)$").unwrap());
+static JOB_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"running as background job (\d+)").unwrap());
+static HANDLER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Started (.+?) handler (?:on|against) (\S+?)(?::(\d+))?\.?$").unwrap()
+});
+static SESSION_OPENED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.+?) session (\d+) opened(?: \((\S+) -> (\S+)\))?").unwrap());
+static SESSION_CLOSED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(.+?) session (\d+) closed\.?(?:\s+Reason: (.+?)\.?)?$").unwrap()
+});
+static EXPLOIT_FAILED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^Exploit (?:failed|aborted due to failure)(?: \[([\w-]+)\])?: (?:([\w-]+): )?(.*)$",
+    )
+    .unwrap()
+});
+static SCANNED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Scanned\s+(\d+) of (\d+) hosts").unwrap());
+
+fn parse_message(message: &str) -> Option<ConsoleEvent> {
+    if let Some(caps) = JOB_RE.captures(message) {
+        return Some(ConsoleEvent::JobStarted {
+            job_id: caps[1].parse().ok()?,
+        });
+    }
+    if let Some(caps) = HANDLER_RE.captures(message) {
+        return Some(ConsoleEvent::HandlerStarted {
+            handler: caps[1].to_string(),
+            address: caps[2].to_string(),
+            port: caps.get(3).and_then(|p| p.as_str().parse().ok()),
+        });
+    }
+    if let Some(caps) = SESSION_OPENED_RE.captures(message) {
+        return Some(ConsoleEvent::SessionOpened {
+            session_id: caps[2].parse().ok()?,
+            session_type: caps[1].to_string(),
+            local: caps.get(3).map(|m| m.as_str().to_string()),
+            remote: caps.get(4).map(|m| m.as_str().to_string()),
+        });
+    }
+    if let Some(caps) = SESSION_CLOSED_RE.captures(message) {
+        return Some(ConsoleEvent::SessionClosed {
+            session_id: caps[2].parse().ok()?,
+            session_type: caps[1].to_string(),
+            reason: caps.get(3).map(|m| m.as_str().to_string()),
+        });
+    }
+    if message.starts_with("Exploit completed, but no session was created") {
+        return Some(ConsoleEvent::ExploitCompleted {
+            session_created: false,
+        });
+    }
+    if let Some(caps) = EXPLOIT_FAILED_RE.captures(message) {
+        return Some(ConsoleEvent::ExploitFailed {
+            kind: caps.get(1).or(caps.get(2)).map(|m| m.as_str().to_string()),
+            reason: caps[3].to_string(),
+        });
+    }
+    if message.starts_with("Auxiliary module execution completed") {
+        return Some(ConsoleEvent::ModuleCompleted);
+    }
+    if let Some(caps) = SCANNED_RE.captures(message) {
+        return Some(ConsoleEvent::ScanProgress {
+            scanned: caps[1].parse().ok()?,
+            total: caps[2].parse().ok()?,
+        });
+    }
+    None
+}
+
+/// Parses one line of stripped console output. Returns `None` for lines
+/// without a status prefix, e.g. table rows or the prompt.
+pub fn parse_line(line: &str) -> Option<ConsoleEvent> {
+    let caps = STATUS_RE.captures(line)?;
+    let marker = &caps[1];
+    let message = &caps[2];
+
+    // sessions and handlers are often reported with a target prefix
+    let (target, message) = match TARGET_RE.captures(message) {
+        Some(t) => (
+            Some(t[1].to_string()),
+            t.get(2).unwrap().as_str().to_string(),
+        ),
+        None => (None, message.to_string()),
+    };
+    if let Some(event) = parse_message(&message) {
+        return Some(event);
+    }
+    Some(match marker {
+        "+" => ConsoleEvent::Success { message, target },
+        "-" => ConsoleEvent::Error { message, target },
+        "!" => ConsoleEvent::Warning { message, target },
+        _ => ConsoleEvent::Info { message, target },
+    })
+}
+
+/// Parses every status line in a block of output, in order.
+pub fn parse_output(output: &str) -> Vec<ConsoleEvent> {
+    output.lines().filter_map(parse_line).collect()
+}
+
+/// Parses the output of one `MSFProcess::run_command`, leaving out the
+/// `[*] exec: ping` echo of the ping that marks the end of it.
+pub fn parse_command_output(output: &[String]) -> Vec<ConsoleEvent> {
+    output
+        .iter()
+        .flat_map(|chunk| chunk.lines())
+        .filter(|line| !line.contains("exec: ping"))
+        .filter_map(parse_line)
+        .collect()
+}
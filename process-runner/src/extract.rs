@@ -0,0 +1,367 @@
+//! Builds the catalog from Metasploit module sources instead of the
+//! console: each `.rb` file's `update_info` hash and `register_options`
+//! calls are read statically, which takes seconds instead of hours.
+//!
+//! Options registered by mixins are only known for the common ones in
+//! `mixin_options`; compare against a scraped catalog to find the rest.
+
+use chrono::NaiveDate;
+use log::{debug, warn};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::msf::{Exploit, ExploitDetails, Parameter};
+use crate::rank::Rank;
+use crate::ruby::{self, RbValue};
+
+const RHOSTS_DESCRIPTION: &str = "The target host(s), see https://docs.metasploit.com/docs/using-metasploit/basics/using-metasploit.html";
+
+/// Everything read from one module source.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ModuleSource {
+    pub name: String,
+    pub path: PathBuf,
+    pub title: String,
+    pub description: String,
+    pub authors: Vec<String>,
+    /// `[type, id]` pairs such as `["CVE", "2017-0143"]`.
+    pub references: Vec<Vec<String>>,
+    pub platforms: Vec<String>,
+    pub arches: Vec<String>,
+    pub targets: Vec<String>,
+    pub default_target: usize,
+    pub disclosure_date: Option<NaiveDate>,
+    pub rank: Rank,
+    pub check: bool,
+    pub default_payload: String,
+    /// `Notes` values, e.g. `Stability` => `["crash-safe"]`.
+    pub notes: Vec<(String, Vec<String>)>,
+    pub options: Vec<Parameter>,
+}
+
+impl ModuleSource {
+    /// The row `show exploits` would give for this module.
+    pub fn details(&self) -> ExploitDetails {
+        ExploitDetails {
+            name: self.name.clone(),
+            disclosure_date: self.disclosure_date,
+            rank: Some(self.rank),
+            check: Some(self.check),
+            description: self.title.clone(),
+        }
+    }
+
+    /// Options and default target as `show options` would give them.
+    /// Payload options are not in module sources.
+    pub fn exploit(&self) -> Exploit {
+        let target = self
+            .targets
+            .get(self.default_target)
+            .map(|name| vec![self.default_target.to_string(), name.clone()]);
+        Exploit {
+            name: self.name.clone(),
+            payload: self.default_payload.clone(),
+            payload_options: None,
+            options: (!self.options.is_empty()).then(|| self.options.clone()),
+            target,
+        }
+    }
+}
+
+/// Module type directories and the prefix of their module names.
+const MODULE_TYPES: [(&str, &str); 3] = [
+    ("exploits", "exploit"),
+    ("auxiliary", "auxiliary"),
+    ("post", "post"),
+];
+
+/// Every module under a Metasploit `modules` directory, sorted by name.
+pub fn extract_tree(modules_dir: &Path) -> Vec<ModuleSource> {
+    let mut modules = Vec::new();
+    for (dir, prefix) in MODULE_TYPES {
+        let root = modules_dir.join(dir);
+        let mut files = Vec::new();
+        collect_rb_files(&root, &mut files);
+        for path in files {
+            let relative = path.strip_prefix(&root).unwrap_or(&path).with_extension("");
+            let name = format!("{}/{}", prefix, relative.to_string_lossy());
+            match std::fs::read_to_string(&path) {
+                Ok(src) => modules.push(extract_module(&name, &path, &src)),
+                Err(e) => warn!("Failed to read {}: {}", path.display(), e),
+            }
+        }
+    }
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules
+}
+
+fn collect_rb_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_rb_files(&path, files);
+        } else if path.extension().is_some_and(|e| e == "rb") {
+            files.push(path);
+        }
+    }
+}
+
+/// Reads one module source.
+pub fn extract_module(name: &str, path: &Path, src: &str) -> ModuleSource {
+    let mut module = ModuleSource {
+        name: name.to_string(),
+        path: path.to_path_buf(),
+        rank: rank(src),
+        check: src.lines().any(|l| {
+            let l = l.trim_start();
+            l == "def check" || l.starts_with("def check(") || l.starts_with("def check ")
+        }),
+        ..Default::default()
+    };
+
+    // most modules pass `update_info(info, {...})` to `super`; some pass
+    // the hash directly
+    let info = ["update_info", "super"].iter().find_map(|method| {
+        ruby::calls(src, method)
+            .into_iter()
+            .flatten()
+            .find(|arg| matches!(arg, RbValue::Hash(_)))
+    });
+    match &info {
+        Some(info) => read_info(&mut module, info),
+        None => debug!("{}: no module info", name),
+    }
+
+    let mut options: Vec<Parameter> = mixin_options(src);
+    for call in ruby::calls(src, "register_options") {
+        for option in call.iter().filter_map(RbValue::as_array).flatten() {
+            if let Some(parameter) = parameter(option) {
+                options.retain(|o| o.name != parameter.name);
+                options.push(parameter);
+            }
+        }
+    }
+    for call in ruby::calls(src, "deregister_options") {
+        for name in call.iter().filter_map(RbValue::as_str) {
+            options.retain(|o| o.name != name);
+        }
+    }
+    options.sort_by(|a, b| a.name.cmp(&b.name));
+    module.options = options;
+    module
+}
+
+fn read_info(module: &mut ModuleSource, info: &RbValue) {
+    let text = |key: &str| info.get(key).and_then(RbValue::to_text).unwrap_or_default();
+    let list = |key: &str| -> Vec<String> {
+        match info.get(key) {
+            Some(RbValue::Array(items)) => items.iter().filter_map(RbValue::to_text).collect(),
+            Some(value) => value.to_text().into_iter().collect(),
+            None => Vec::new(),
+        }
+    };
+    module.title = text("Name");
+    module.description = text("Description")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    module.authors = list("Author");
+    module.platforms = list("Platform")
+        .iter()
+        .flat_map(|p| p.split(',').map(|p| p.trim().to_string()))
+        .collect();
+    // `ARCH_X64` is shown as `x64`
+    module.arches = list("Arch")
+        .iter()
+        .map(|a| a.strip_prefix("ARCH_").unwrap_or(a).to_lowercase())
+        .collect();
+    module.disclosure_date = disclosure_date(&text("DisclosureDate"));
+    if let Some(RbValue::Const(rank)) = info.get("Rank") {
+        if let Ok(rank) = rank.parse() {
+            module.rank = rank;
+        }
+    }
+    if let Some(RbValue::Array(references)) = info.get("References") {
+        module.references = references
+            .iter()
+            .filter_map(RbValue::as_array)
+            .map(|r| r.iter().filter_map(RbValue::to_text).collect())
+            .collect();
+    }
+    if let Some(RbValue::Array(targets)) = info.get("Targets") {
+        module.targets = targets
+            .iter()
+            .filter_map(|t| t.as_array()?.first()?.to_text())
+            .collect();
+    }
+    if let Some(RbValue::Int(default)) = info.get("DefaultTarget") {
+        module.default_target = *default as usize;
+    }
+    if let Some(payload) = info
+        .get("DefaultOptions")
+        .and_then(|o| o.get("PAYLOAD").or_else(|| o.get("Payload")))
+        .and_then(RbValue::to_text)
+    {
+        module.default_payload = payload;
+    }
+    if let Some(RbValue::Hash(notes)) = info.get("Notes") {
+        module.notes = notes
+            .iter()
+            .filter_map(|(key, value)| {
+                let values = match value {
+                    RbValue::Array(items) => items.iter().filter_map(note_value).collect(),
+                    value => note_value(value).into_iter().collect(),
+                };
+                Some((key.as_str()?.to_string(), values))
+            })
+            .collect();
+    }
+}
+
+/// Notes use constants such as `CRASH_SAFE`, which msfconsole shows as
+/// `crash-safe`.
+fn note_value(value: &RbValue) -> Option<String> {
+    match value {
+        RbValue::Const(c) => Some(c.to_lowercase().replace('_', "-")),
+        value => value.to_text(),
+    }
+}
+
+fn rank(src: &str) -> Rank {
+    src.lines()
+        .filter_map(|l| l.trim().strip_prefix("Rank = "))
+        .find_map(|r| r.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Dates as `show exploits` prints them (`2017-03-14`); older modules use
+/// `Mar 14 2017`.
+fn disclosure_date(date: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%b %d %Y", "%B %d %Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(date.trim(), format).ok())
+}
+
+/// One `register_options` entry: `OptX.new('NAME', [required, desc,
+/// default, enums])`, the keyword form `OptX.new('NAME', required: ...)`,
+/// or a shorthand such as `Opt::RPORT(445)`.
+fn parameter(option: &RbValue) -> Option<Parameter> {
+    let (class, args) = match option {
+        RbValue::Call(class, args) => (class.as_str(), args.as_slice()),
+        RbValue::Const(class) => (class.as_str(), &[][..]),
+        _ => return None,
+    };
+    if let Some(short) = class.strip_prefix("Opt::") {
+        return shorthand(short, args.first().and_then(RbValue::to_text));
+    }
+    let name = args.first()?.as_str()?.to_string();
+    let (mut required, mut description, mut default, mut enums) =
+        (false, String::new(), None, None);
+    for arg in &args[1..] {
+        match arg {
+            RbValue::Array(spec) => {
+                required = spec.first() == Some(&RbValue::Bool(true));
+                description = spec.get(1).and_then(RbValue::to_text).unwrap_or_default();
+                default = spec.get(2).and_then(RbValue::to_text);
+                enums = spec
+                    .get(3)
+                    .and_then(RbValue::as_array)
+                    .map(<[RbValue]>::to_vec);
+            }
+            RbValue::Hash(_) => {
+                required = arg.get("required") == Some(&RbValue::Bool(true)) || required;
+                if let Some(desc) = arg.get("desc").and_then(RbValue::to_text) {
+                    description = desc;
+                }
+                if let Some(value) = arg.get("default").and_then(RbValue::to_text) {
+                    default = Some(value);
+                }
+                if let Some(values) = arg.get("enums").and_then(RbValue::as_array) {
+                    enums = Some(values.to_vec());
+                }
+            }
+            _ => {}
+        }
+    }
+    if class.ends_with("OptEnum") {
+        if let Some(values) = enums {
+            // msfconsole appends the choices to the description
+            let values: Vec<String> = values.iter().filter_map(RbValue::to_text).collect();
+            description = format!("{} (Accepted: {})", description, values.join(", "));
+        }
+    }
+    // `show options` has no value for empty defaults
+    let default = default.filter(|d| !d.is_empty());
+    Some(Parameter::new(name, default, required, description))
+}
+
+/// `Opt::RHOSTS`, `Opt::RPORT(445)` and friends, with msfconsole's wording.
+fn shorthand(name: &str, default: Option<String>) -> Option<Parameter> {
+    let (required, description, fallback) = match name {
+        "RHOSTS" | "RHOST" => (true, RHOSTS_DESCRIPTION, None),
+        "RPORT" => (true, "The target port (TCP)", None),
+        "LHOST" => (
+            true,
+            "The listen address (an interface may be specified)",
+            None,
+        ),
+        "LPORT" => (true, "The listen port", None),
+        "CHOST" => (false, "The local client address", None),
+        "CPORT" => (false, "The local client port", None),
+        "SSLVersion" => (
+            false,
+            "Specify the version of SSL/TLS to be used",
+            Some("Auto"),
+        ),
+        "Proxies" => (
+            false,
+            "A proxy chain of format type:host:port[,type:host:port][...]",
+            None,
+        ),
+        _ => return None,
+    };
+    let name = if name == "RHOST" { "RHOSTS" } else { name };
+    Some(Parameter::new(
+        name.to_string(),
+        default.or(fallback.map(str::to_string)),
+        required,
+        description.to_string(),
+    ))
+}
+
+/// Options the common connection mixins register.
+fn mixin_options(src: &str) -> Vec<Parameter> {
+    let includes = |mixin: &str| src.contains(&format!("include Msf::{}", mixin));
+    let mut options = Vec::new();
+    let mut add = |name: &str, default: Option<&str>| {
+        if let Some(p) = shorthand(name, default.map(str::to_string)) {
+            options.retain(|o: &Parameter| o.name != p.name);
+            options.push(p);
+        }
+    };
+    if includes("Exploit::Remote::Tcp") || includes("Exploit::Remote::Ftp") {
+        add("RHOSTS", None);
+        add("RPORT", None);
+        add("CHOST", None);
+        add("CPORT", None);
+    }
+    if includes("Exploit::Remote::Ftp") {
+        add("RPORT", Some("21"));
+    }
+    if includes("Exploit::Remote::HttpClient") {
+        add("RHOSTS", None);
+        add("RPORT", Some("80"));
+        add("Proxies", None);
+    }
+    if includes("Exploit::Remote::SMB::Client") {
+        add("RHOSTS", None);
+        add("RPORT", Some("445"));
+    }
+    if includes("Auxiliary::Scanner") {
+        add("RHOSTS", None);
+    }
+    options
+}
@@ -0,0 +1,118 @@
+//! Facts about the host the scrape runs on. msfconsole fills some defaults
+//! from them, e.g. LHOST with the container's own address, so the catalog
+//! replaces them with placeholders and keeps the original value aside.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::msf::{Exploit, Parameter};
+
+pub const LOCAL_IP: &str = "${LOCAL_IP}";
+pub const HOSTNAME: &str = "${HOSTNAME}";
+pub const HOME: &str = "${HOME}";
+
+/// Options msfconsole fills with a local address when it starts.
+const LOCAL_ADDRESS_OPTIONS: [&str; 2] = ["LHOST", "ReverseListenerBindAddress"];
+
+#[derive(Clone, Debug, Default)]
+pub struct HostEnvironment {
+    /// Interface addresses, without loopback.
+    pub addresses: Vec<IpAddr>,
+    pub hostname: Option<String>,
+    pub home: Option<String>,
+}
+
+impl HostEnvironment {
+    pub fn detect() -> Self {
+        let hostname = hostname();
+        Self {
+            addresses: local_addresses(),
+            hostname: (!hostname.is_empty()).then_some(hostname),
+            home: std::env::var("HOME").ok(),
+        }
+    }
+
+    /// The placeholder form of `value` as a default of option `name`, if
+    /// it came from this host.
+    pub fn placeholder(&self, name: &str, value: &str) -> Option<String> {
+        let value = value.trim();
+        if let Ok(address) = value.parse::<IpAddr>() {
+            let local = LOCAL_ADDRESS_OPTIONS.contains(&name) && !address.is_unspecified();
+            return (local || self.addresses.contains(&address)).then(|| LOCAL_IP.to_string());
+        }
+        if self
+            .hostname
+            .as_ref()
+            .is_some_and(|h| h.eq_ignore_ascii_case(value))
+        {
+            return Some(HOSTNAME.to_string());
+        }
+        // `/` as home would match every path
+        let home = self.home.as_deref().filter(|h| h.len() > 1)?;
+        let rest = value.strip_prefix(home.trim_end_matches('/'))?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", HOME, rest))
+    }
+
+    /// Replaces a host-derived default with its placeholder, keeping the
+    /// value in `original_default`.
+    pub fn normalize(&self, parameter: &mut Parameter) {
+        let Some(default) = parameter.default_value.as_deref() else {
+            return;
+        };
+        if let Some(placeholder) = self.placeholder(&parameter.name, default) {
+            parameter.original_default = parameter.default_value.replace(placeholder);
+        }
+    }
+
+    pub fn normalize_exploit(&self, exploit: &mut Exploit) {
+        let options = exploit
+            .options
+            .iter_mut()
+            .chain(&mut exploit.payload_options);
+        options.flatten().for_each(|p| self.normalize(p));
+    }
+}
+
+pub fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    // gethostname writes at most `len` bytes into the buffer
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return String::new();
+    }
+    let end = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+    String::from_utf8_lossy(&buffer[..end]).to_string()
+}
+
+/// Addresses of the host's interfaces, without loopback.
+pub fn local_addresses() -> Vec<IpAddr> {
+    let mut addresses = Vec::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        return addresses;
+    }
+    let mut entry = list;
+    while !entry.is_null() {
+        // getifaddrs returns a linked list that stays valid until freed
+        let ifaddr = unsafe { &*entry };
+        entry = ifaddr.ifa_next;
+        if ifaddr.ifa_addr.is_null() {
+            continue;
+        }
+        let address = match i32::from(unsafe { (*ifaddr.ifa_addr).sa_family }) {
+            libc::AF_INET => {
+                let sockaddr = unsafe { &*(ifaddr.ifa_addr as *const libc::sockaddr_in) };
+                IpAddr::V4(Ipv4Addr::from(u32::from_be(sockaddr.sin_addr.s_addr)))
+            }
+            libc::AF_INET6 => {
+                let sockaddr = unsafe { &*(ifaddr.ifa_addr as *const libc::sockaddr_in6) };
+                IpAddr::V6(Ipv6Addr::from(sockaddr.sin6_addr.s6_addr))
+            }
+            _ => continue,
+        };
+        if !address.is_loopback() && !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    unsafe { libc::freeifaddrs(list) };
+    addresses
+}
@@ -0,0 +1,111 @@
+//! Option types. msfconsole only shows options as text, so the type is
+//! inferred from the name, default value and description.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OptionKind {
+    Port,
+    /// A single host, e.g. LHOST.
+    Address,
+    /// A list of hosts, CIDR blocks or ranges, e.g. RHOSTS.
+    AddressRange,
+    Path,
+    Boolean,
+    Integer,
+    /// One of the values listed after "Accepted:".
+    Enum,
+    Regex,
+    String,
+}
+
+/// Name tokens of path options, e.g. the `FILE` of `USER_FILE`.
+const PATH_TOKENS: [&str; 9] = [
+    "PATH",
+    "FILE",
+    "FILENAME",
+    "DIR",
+    "DIRECTORY",
+    "FOLDER",
+    "URI",
+    "URIPATH",
+    "TARGETURI",
+];
+/// Description phrases of single-host options. A bare "address" is left
+/// out, since it is as often an e-mail address.
+const ADDRESS_PHRASES: [&str; 6] = [
+    "ip address",
+    "host address",
+    "listen address",
+    "target address",
+    "ipv4",
+    "ipv6",
+];
+
+/// The kind of an option and, for enums, its accepted values. Names are
+/// matched by `_`-separated token and descriptions by whole word, so
+/// `SECURITY_LEVEL` is not a URI and "account" is not a count.
+pub fn infer(
+    name: &str,
+    default_value: Option<&str>,
+    description: &str,
+) -> (OptionKind, Option<Vec<String>>) {
+    if let Some(values) = accepted_values(description) {
+        return (OptionKind::Enum, Some(values));
+    }
+    let name = name.to_uppercase();
+    let tokens: Vec<&str> = name.split('_').collect();
+    let words = words(description);
+    let says = |phrase: &str| words.contains(&format!(" {} ", phrase));
+    let default_value = default_value.unwrap_or("");
+    let kind = if default_value.eq_ignore_ascii_case("true")
+        || default_value.eq_ignore_ascii_case("false")
+    {
+        OptionKind::Boolean
+    } else if name.ends_with("PORT") {
+        OptionKind::Port
+    } else if name.ends_with("HOSTS") {
+        OptionKind::AddressRange
+    } else if name.ends_with("HOST") || ADDRESS_PHRASES.iter().any(|p| says(p)) {
+        OptionKind::Address
+    } else if says("regex") || says("regular expression") {
+        OptionKind::Regex
+    } else if tokens.iter().any(|t| PATH_TOKENS.contains(t))
+        || ["path", "file", "directory"].iter().any(|p| says(p))
+    {
+        OptionKind::Path
+    } else if default_value.parse::<i64>().is_ok()
+        || ["number of", "timeout", "seconds", "count"]
+            .iter()
+            .any(|p| says(p))
+    {
+        OptionKind::Integer
+    } else {
+        OptionKind::String
+    };
+    (kind, None)
+}
+
+/// The lowercase words of `text`, space-separated and padded with a space
+/// at each end so a phrase can be matched as `" phrase "`.
+fn words(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    format!(" {} ", words.join(" "))
+}
+
+/// The values listed after "Accepted:" in an option description.
+pub fn accepted_values(description: &str) -> Option<Vec<String>> {
+    let (_, list) = description.split_once("Accepted:")?;
+    // msfconsole appends the list in parentheses: "(Accepted: a, b)"
+    let list = list.split(')').next().unwrap_or_default();
+    let values: Vec<String> = list
+        .split(',')
+        .map(|v| v.trim().trim_matches(['\'', '"']).to_string())
+        .collect();
+    (!values.is_empty()).then_some(values)
+}
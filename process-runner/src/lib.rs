@@ -0,0 +1,30 @@
+pub mod ansi;
+pub mod banner;
+pub mod buffer;
+pub mod canonical;
+pub mod catalog;
+pub mod check;
+pub mod command;
+pub mod compare;
+pub mod console;
+pub mod daemon;
+pub mod events;
+pub mod extract;
+pub mod host;
+pub mod kind;
+pub mod lookup;
+pub mod manifest;
+pub mod msf;
+pub mod policy;
+pub mod pool;
+pub mod process;
+pub mod query;
+pub mod rank;
+pub mod replay;
+pub mod ruby;
+pub mod scope;
+pub mod server;
+pub mod spawn;
+pub mod transcript;
+pub mod tree;
+pub mod validate;
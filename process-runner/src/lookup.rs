@@ -0,0 +1,127 @@
+use log::{debug, warn};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::catalog::Catalog;
+use crate::msf::{self, Exploit};
+use crate::pool::ConsolePool;
+
+/// Where a lookup was answered from.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OptionsSource {
+    Catalog,
+    Live,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ModuleOptions {
+    #[serde(flatten)]
+    pub module: Exploit,
+    pub source: OptionsSource,
+}
+
+/// Answers module option lookups from the generated catalog while it is
+/// younger than `max_age`, and from a live console otherwise. The catalog
+/// is re-read whenever the file on disk changes. Live answers are kept for
+/// `max_age` too, or until the catalog is re-read.
+pub struct OptionsLookup {
+    catalog_path: PathBuf,
+    max_age: Duration,
+    catalog: Mutex<Option<Arc<Catalog>>>,
+    live: Mutex<HashMap<String, (Instant, Exploit)>>,
+    pool: Arc<ConsolePool>,
+}
+
+impl OptionsLookup {
+    pub fn new(
+        catalog_path: impl Into<PathBuf>,
+        max_age: Duration,
+        pool: Arc<ConsolePool>,
+    ) -> Self {
+        Self {
+            catalog_path: catalog_path.into(),
+            max_age,
+            catalog: Mutex::new(None),
+            live: Mutex::new(HashMap::new()),
+            pool,
+        }
+    }
+
+    /// Reads `PROCESS_RUNNER_CATALOG` (default `exploits_options.json`) and
+    /// `PROCESS_RUNNER_CATALOG_MAX_AGE` in seconds (default one day).
+    pub fn from_env(pool: Arc<ConsolePool>) -> Self {
+        let path = std::env::var("PROCESS_RUNNER_CATALOG")
+            .unwrap_or_else(|_| "exploits_options.json".to_string());
+        let mut max_age = Duration::from_secs(24 * 60 * 60);
+        if let Ok(secs) = std::env::var("PROCESS_RUNNER_CATALOG_MAX_AGE") {
+            match secs.parse() {
+                Ok(secs) => max_age = Duration::from_secs(secs),
+                Err(e) => warn!("Ignoring PROCESS_RUNNER_CATALOG_MAX_AGE={}: {}", secs, e),
+            }
+        }
+        Self::new(path, max_age, pool)
+    }
+
+    /// The catalog on disk whatever its age, re-read if the file changed.
+    pub fn load_catalog(&self) -> Option<Arc<Catalog>> {
+        let modified = Catalog::modified_at(&self.catalog_path).ok()?;
+        let mut cached = self.catalog.lock().unwrap();
+        if cached.as_ref().is_none_or(|c| c.modified != modified) {
+            self.live.lock().unwrap().clear();
+            match Catalog::load(&self.catalog_path) {
+                Ok(catalog) => *cached = Some(Arc::new(catalog)),
+                Err(e) => {
+                    warn!("Failed to load {}: {}", self.catalog_path.display(), e);
+                    *cached = None;
+                }
+            }
+        }
+        cached.clone()
+    }
+
+    /// The current catalog, if the file exists and is fresh.
+    pub fn catalog(&self) -> Option<Arc<Catalog>> {
+        self.load_catalog().filter(|c| c.is_fresh(self.max_age))
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    pub fn lookup(&self, name: &str) -> Result<ModuleOptions, String> {
+        if !msf::is_module_name(name) {
+            return Err(format!("Invalid module name: {:?}", name));
+        }
+        if let Some(module) = self.catalog().and_then(|c| c.get(name)) {
+            return Ok(ModuleOptions {
+                module,
+                source: OptionsSource::Catalog,
+            });
+        }
+        let cached = self.live.lock().unwrap().get(name).cloned();
+        let module = match cached {
+            Some((at, module)) if at.elapsed() < self.max_age => module,
+            _ => {
+                debug!("{} not in a fresh catalog, looking it up live", name);
+                let name = name.to_string();
+                let module = self.pool.call({
+                    let name = name.clone();
+                    move |msf| msf.module_options(&name)
+                })??;
+                self.live
+                    .lock()
+                    .unwrap()
+                    .insert(name, (Instant::now(), module.clone()));
+                module
+            }
+        };
+        Ok(ModuleOptions {
+            module,
+            source: OptionsSource::Live,
+        })
+    }
+}
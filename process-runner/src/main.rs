@@ -1,871 +1,682 @@
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use indicatif_log_bridge::LogWrapper;
-use log::{debug, error, info, warn};
-use num_cpus;
-use std::collections::{HashMap, VecDeque};
-use std::io::{BufRead, BufReader, Write};
-use std::process::Stdio;
-use std::sync::{Arc, Condvar, Mutex};
+use log::{error, info, warn};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use std::{env, thread};
 
 use colored::Colorize;
-use env_logger;
-use regex::Regex;
-use serde::Serialize;
-struct Process {
-    process: std::process::Child,
-    stdin: std::process::ChildStdin,
-    output_buf: Arc<Mutex<Vec<String>>>,
-    readable: Arc<Condvar>,
-    err_readable: Arc<Condvar>,
-    err_buf: Arc<Mutex<Vec<String>>>,
-}
-
-impl Process {
-    pub fn new(command: &str, args: Option<&[&str]>) -> Self {
-        let mut process;
-        if args.is_none() {
-            process = std::process::Command::new(command)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .expect("Failed to spawn process");
-        } else {
-            process = std::process::Command::new(command)
-                .args(args.unwrap())
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::piped())
-                .spawn()
-                .expect("Failed to spawn process");
-        }
-
-        let stdin = process.stdin.take().expect("Failed to get stdin");
-
-        Self {
-            process,
-            stdin,
-            output_buf: Arc::new(Mutex::new(Vec::new())),
-            err_buf: Arc::new(Mutex::new(Vec::new())),
-            readable: Arc::new(Condvar::new()),
-            err_readable: Arc::new(Condvar::new()),
+use create_options_json::catalog::{Catalog, CatalogEntry, PayloadCatalog, PAYLOADS_FILE};
+use create_options_json::command::{Action, CommandBuilder};
+use create_options_json::daemon::{Daemon, DaemonConfig};
+use create_options_json::host::HostEnvironment;
+use create_options_json::manifest::{Manifest, MANIFEST_FILE};
+use create_options_json::msf::{self, ExploitDetails, MSFProcess};
+use create_options_json::policy::Policy;
+use create_options_json::process::Process;
+use create_options_json::replay::{self, ReplayConsole};
+use create_options_json::server::{self, ServerConfig};
+use create_options_json::{
+    ansi, banner, canonical, check, compare, events, extract, query, transcript, tree, validate,
+};
+
+/// `replay <transcript.jsonl> [--wall]`: prints a console transcript in order.
+fn replay(args: &[String]) -> std::io::Result<()> {
+    let wall = args.iter().any(|a| a == "--wall");
+    let path = match args.iter().find(|a| !a.starts_with("--")) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: replay <transcript.jsonl> [--wall]");
+            std::process::exit(2);
         }
-    }
+    };
+    let records = transcript::read_transcript(std::path::Path::new(path))?;
+    let stdout = std::io::stdout();
+    transcript::render(&records, &mut stdout.lock(), wall)
+}
 
-    pub fn write(&mut self, data: &str) -> std::io::Result<()> {
-        writeln!(self.stdin, "{}", data).expect("Failed to write to stdin");
-        self.stdin.flush().expect("Failed to flush stdin");
-        Ok(())
-    }
-
-    pub fn start_reader(&mut self) -> thread::JoinHandle<()> {
-        let out = self.process.stdout.take().expect("Failed to get stdout");
-        let output_buf = Arc::clone(&self.output_buf);
-        let readable = Arc::clone(&self.readable);
-        thread::spawn(move || {
-            let reader = BufReader::new(out);
-            for line in reader.lines() {
-                // thread::sleep(std::time::Duration::from_millis(1));
-                let ln = match line {
-                    Ok(output) => output,
-                    Err(e) => {
-                        error!("Error reading output: {}", e);
-                        return;
-                    }
-                };
-                let ln: Vec<String> = ln
-                    .split("\n")
-                    .map(|s| String::from_utf8(strip_ansi_escapes::strip(s)).unwrap())
-                    .collect();
-                // eprintln!("{}", ln.join(" \n"));
-                let mut output_buf = output_buf.lock().unwrap();
-                output_buf.push(ln.join("\n"));
-                readable.notify_one();
-            }
-        })
+/// `ansi [--html] [file]`: converts ANSI-coloured console output (stdin when
+/// no file is given) to JSON spans, or to HTML with `--html`.
+fn convert_ansi(args: &[String]) -> std::io::Result<()> {
+    let html = args.iter().any(|a| a == "--html");
+    let input = match args.iter().find(|a| !a.starts_with("--")) {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    if html {
+        println!("<pre>{}</pre>", ansi::to_html(&input));
+    } else {
+        let spans = ansi::to_spans(&input);
+        serde_json::to_writer_pretty(std::io::stdout(), &spans)?;
+        println!();
     }
+    Ok(())
+}
 
-    pub fn start_error_reader(&mut self) -> thread::JoinHandle<()> {
-        let stderr = self.process.stderr.take().expect("Failed to get stderr");
-        let err_buf = Arc::clone(&self.err_buf);
-        let err_readable = Arc::clone(&self.err_readable);
-        thread::spawn(move || {
-            let reader = BufReader::new(stderr);
-            for line in reader.lines() {
-                // thread::sleep(std::time::Duration::from_millis(1));
-                let ln = match line {
-                    Ok(output) => output,
-                    Err(e) => {
-                        error!("Error reading output: {}", e);
-                        return;
-                    }
-                };
-                let ln_vec: Vec<u8> = strip_ansi_escapes::strip(&ln);
-                let striped_line = String::from_utf8(ln_vec).unwrap();
-                // eprintln!("{}", ln);
-                let mut output_buf = err_buf.lock().unwrap();
-                output_buf.push(striped_line);
-                err_readable.notify_one();
-            }
-        })
+/// `events [file]`: prints the console events found in msfconsole output
+/// (stdin when no file is given) as JSON Lines.
+fn print_events(args: &[String]) -> std::io::Result<()> {
+    let input = match args.first() {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => std::io::read_to_string(std::io::stdin())?,
+    };
+    let input = String::from_utf8_lossy(&strip_ansi_escapes::strip(&input)).to_string();
+    for event in events::parse_output(&input) {
+        println!("{}", serde_json::to_string(&event)?);
     }
+    Ok(())
+}
 
-    pub fn read(&mut self) -> String {
-        let mut output_buf = self.readable.wait(self.output_buf.lock().unwrap()).unwrap();
-        let res = output_buf.join("\n");
-        output_buf.clear();
-        res
+/// `serve [--http addr] [--ws addr] [--idle-timeout secs] [--catalog file]
+/// [--catalog-max-age secs] [--pool-size n] [--role name] [--policy file]
+/// [--replay transcript]`: runs the REST and WebSocket API in place of the
+/// Node server. Consoles only accept commands of the `read-only` role unless
+/// `--role` or `PROCESS_RUNNER_ROLE` names another. With `--replay`,
+/// consoles replay a transcript instead of running msfconsole.
+fn serve(args: &[String]) -> std::io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let mut config = ServerConfig::default();
+    let (mut role, mut profiles) = (None, None);
+    let mut replay = None;
+    let usage = || -> ! {
+        eprintln!("Usage: serve [--http addr] [--ws addr] [--idle-timeout secs] [--catalog file] [--catalog-max-age secs] [--pool-size n] [--role name] [--policy file] [--replay transcript]");
+        std::process::exit(2);
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--http" => config.http_addr = value.clone(),
+            "--ws" => config.ws_addr = value.clone(),
+            "--catalog" => config.catalog = value.into(),
+            "--catalog-max-age" => {
+                let secs = value.parse().unwrap_or_else(|_| usage());
+                config.catalog_max_age = std::time::Duration::from_secs(secs);
+            }
+            "--pool-size" => config.pool_size = value.parse().unwrap_or_else(|_| usage()),
+            "--role" => role = Some(value.clone()),
+            "--policy" => profiles = Some(value.clone()),
+            "--replay" => replay = Some(value.clone()),
+            "--idle-timeout" => {
+                let secs = value.parse().unwrap_or_else(|_| usage());
+                config.idle_timeout = std::time::Duration::from_secs(secs);
+            }
+            _ => usage(),
+        }
     }
-
-    pub fn read_err(&mut self) -> String {
-        let mut err_buf = self
-            .err_readable
-            .wait(self.err_buf.lock().unwrap())
-            .unwrap();
-        let res = err_buf.iter().cloned().collect();
-        err_buf.clear();
-        res
+    if let Some(role) = role {
+        config.policy = Some(load_policy(&role, profiles));
     }
-
-    pub fn read_buf_size(&mut self) -> usize {
-        let output_buf = self.output_buf.lock().unwrap();
-        output_buf.len()
+    if let Some(policy) = &config.policy {
+        info!("Consoles accept commands of role {}", policy.role());
     }
-
-    pub fn clear(&mut self) {
-        let mut output_buf = self.output_buf.lock().unwrap();
-        output_buf.clear();
+    match replay {
+        Some(transcript) => {
+            let exe = env::current_exe()?.to_string_lossy().to_string();
+            server::serve(config, move || {
+                Process::builder(&exe)
+                    .args(&["replay-console", &transcript])
+                    .from_env()
+            })
+        }
+        None => server::serve(config, || Process::builder("msfconsole").from_env()),
     }
 }
 
-impl Drop for Process {
-    fn drop(&mut self) {
-        // eprintln!("Sending Kill signal to process");
-        self.process.kill().expect("Failed to kill process");
-        // eprintln!("Process dropped");
+/// The policy for `--role`, with custom profiles from `--policy` or
+/// `PROCESS_RUNNER_POLICY`.
+fn load_policy(role: &str, profiles: Option<String>) -> Arc<Policy> {
+    let profiles = profiles.or_else(|| env::var("PROCESS_RUNNER_POLICY").ok());
+    match Policy::for_role(role, profiles.as_deref().map(std::path::Path::new)) {
+        Ok(policy) => Arc::new(policy),
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(2);
+        }
     }
 }
 
-#[derive(Serialize, Clone, Debug)]
-struct Parameter {
-    name: String,
-    default_value: Option<String>,
-    required: bool,
-    description: String,
-}
-impl Parameter {
-    pub fn new(
-        name: String,
-        default_value: Option<String>,
-        required: bool,
-        description: String,
-    ) -> Self {
-        Self {
-            name,
-            default_value,
-            required,
-            description,
+/// `daemon [--replay transcript] [--catalog file] [--catalog-max-age secs]
+/// [--pool-size n] [--role name] [--policy file]`: answers JSON-RPC requests
+/// on stdin. With `--replay`,
+/// consoles replay a transcript instead of running msfconsole.
+fn daemon(args: &[String]) -> std::io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let usage = || -> ! {
+        eprintln!("Usage: daemon [--replay transcript] [--catalog file] [--catalog-max-age secs] [--pool-size n] [--role name] [--policy file]");
+        std::process::exit(2);
+    };
+    let mut config = DaemonConfig::default();
+    let mut replay = None;
+    let (mut role, mut profiles) = (None, None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--replay" => replay = Some(value.clone()),
+            "--catalog" => config.catalog = value.into(),
+            "--catalog-max-age" => {
+                let secs = value.parse().unwrap_or_else(|_| usage());
+                config.catalog_max_age = std::time::Duration::from_secs(secs);
+            }
+            "--pool-size" => config.pool_size = value.parse().unwrap_or_else(|_| usage()),
+            "--role" => role = Some(value.clone()),
+            "--policy" => profiles = Some(value.clone()),
+            _ => usage(),
         }
     }
+    if let Some(role) = role {
+        config.policy = Some(load_policy(&role, profiles));
+    }
+    let daemon = match replay {
+        Some(transcript) => {
+            let exe = env::current_exe()?.to_string_lossy().to_string();
+            Daemon::new(config, move || {
+                Process::builder(&exe)
+                    .args(&["replay-console", &transcript])
+                    .from_env()
+            })
+        }
+        None => Daemon::new(config, || Process::builder("msfconsole").from_env()),
+    };
+    Arc::new(daemon).run(std::io::stdin().lock(), std::io::stdout())
 }
 
-#[derive(Serialize, Clone)]
-struct ExploitDetails {
-    name: String,
-    disclosure_date: String,
-    rank: String,
-    check: bool,
-    description: String,
-}
-#[derive(Serialize, Clone)]
-struct Exploit {
-    name: String,
-    payload: String,
-    payload_options: Option<Vec<Parameter>>,
-    options: Option<Vec<Parameter>>,
-    target: Option<Vec<String>>,
-}
-struct MSFProcess {
-    process: Process,
-    reader: thread::JoinHandle<()>,
-    err_reader: thread::JoinHandle<()>,
-    output: Vec<String>,
-    err: Vec<String>,
-    exploits: Vec<Exploit>,
-}
-
-impl MSFProcess {
-    pub fn new() -> Self {
-        let mut process = Process::new("msfconsole", None);
-        let reader = process.start_reader();
-        let err_reader = process.start_error_reader();
-
-        let mut read_line = process.read();
-        let mut read_line_err = process.read_err();
-        thread::sleep(std::time::Duration::from_secs(1));
-        let _ = process.write("ping");
-        while !read_line_err.contains("ping: usage error:") {
-            read_line = process.read();
-            read_line_err = process.read_err();
-            debug!("{}", read_line);
+/// `validate [--catalog file] <module> [NAME=VALUE...]`: checks option
+/// values against the catalog and prints the problems as JSON. Exits with
+/// status 1 when there are any.
+fn validate_options(args: &[String]) -> std::io::Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: validate [--catalog file] <module> [NAME=VALUE...]");
+        std::process::exit(2);
+    };
+    let mut catalog_path = "exploits_options.json".to_string();
+    let mut module = None;
+    let mut values = HashMap::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--catalog" {
+            catalog_path = args.next().unwrap_or_else(|| usage()).clone();
+        } else if module.is_none() {
+            module = Some(arg.clone());
+        } else {
+            let (name, value) = arg.split_once('=').unwrap_or_else(|| usage());
+            values.insert(name.to_string(), value.to_string());
         }
-        debug!("MSF started\n{}", read_line);
-        Self {
-            process,
-            reader,
-            err_reader,
-            output: Vec::new(),
-            err: Vec::new(),
-            exploits: Vec::new(),
+    }
+    let module = module.unwrap_or_else(|| usage());
+    let catalog = Catalog::load(&catalog_path)?;
+    let validation = match validate::validate_module(&catalog, &module, &values) {
+        Ok(validation) => validation,
+        Err(e) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
         }
+    };
+    serde_json::to_writer_pretty(std::io::stdout(), &validation)?;
+    println!();
+    if !validation.valid {
+        std::process::exit(1);
     }
+    Ok(())
+}
 
-    pub fn run_command(&mut self, command: &str) {
-        // info!("Running command: {}", command);
-        self.process.clear();
-        let _ = self.process.write(command);
-        thread::sleep(std::time::Duration::from_millis(10));
-        let _ = self.process.write("ping");
-        let mut read_line;
-        let mut read_line_err = "".to_string();
-        let mut lines_vec = Vec::new();
-        while !read_line_err.contains("ping: usage error:") {
-            read_line = self.process.read() + " ";
-            read_line_err = self.process.read_err();
-            lines_vec.push(read_line.clone());
-            // eprintln!("RUN COMMAND: {}\n\n", read_line);
+/// `commands [--catalog file] [--payload name] [--target t] [--action
+/// run|exploit|check] [--rc file] <module> [NAME=VALUE...]`: prints the
+/// console commands for a module configuration after checking it against
+/// the catalog, or writes them as a resource script with `--rc`.
+fn build_commands(args: &[String]) -> std::io::Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: commands [--catalog file] [--payload name] [--target t] [--action run|exploit|check] [--rc file] <module> [NAME=VALUE...]");
+        std::process::exit(2);
+    };
+    let mut catalog_path = "exploits_options.json".to_string();
+    let mut payload = None;
+    let mut target = None;
+    let mut action = None;
+    let mut rc = None;
+    let mut module = None;
+    let mut values = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage()).clone();
+        match arg.as_str() {
+            "--catalog" => catalog_path = value(),
+            "--payload" => payload = Some(value()),
+            "--target" => target = Some(value()),
+            "--action" => {
+                action = Some(match value().as_str() {
+                    "run" => Action::Run,
+                    "exploit" => Action::Exploit,
+                    "check" => Action::Check,
+                    _ => usage(),
+                })
+            }
+            "--rc" => rc = Some(value()),
+            _ if module.is_none() => module = Some(arg.clone()),
+            _ => {
+                let (name, value) = arg.split_once('=').unwrap_or_else(|| usage());
+                values.push((name.to_string(), value.to_string()));
+            }
         }
-        self.output = lines_vec;
     }
-
-    pub fn clear(&mut self) {
-        self.process.clear();
-        self.output.clear();
+    let mut builder = CommandBuilder::new(&module.unwrap_or_else(|| usage()))
+        .options(values.iter().map(|(n, v)| (n.as_str(), v.as_str())));
+    if let Some(payload) = &payload {
+        builder = builder.payload(payload);
     }
-
-    fn extract_exploit_names(input: &str) -> Vec<String> {
-        let re = Regex::new(r"exploit/([a-zA-Z0-9_/]+)").unwrap();
-
-        let exploit_names: Vec<String> = re
-            .captures_iter(input)
-            .filter_map(|cap| {
-                cap.get(1)
-                    .map(|m| "exploit/".to_owned() + &m.as_str().to_string())
-            })
-            .collect();
-
-        exploit_names
+    if let Some(target) = &target {
+        builder = builder.target(target);
     }
-
-    pub fn get_exploits(&mut self) -> Vec<Exploit> {
-        if self.exploits.len() > 0 {
-            return self.exploits.clone();
-        }
-        self.run_command("show exploits");
-
-        let lines_vec = self.output.clone();
-        let line = lines_vec.join("\n");
-
-        let exploit_names = Self::extract_exploit_names(line.as_str());
-        let exploits: Vec<Exploit> = exploit_names
-            .into_iter()
-            .map(|name| Exploit {
-                name,
-                options: None,
-                target: None,
-                payload_options: None,
-                payload: "".to_string(),
-            })
-            .collect();
-        self.clear();
-
-        exploits
+    if let Some(action) = action {
+        builder = builder.action(action);
     }
-
-    fn extract_exploit_details(input: &str) -> Vec<ExploitDetails> {
-        let sep = " -----------\n";
-        let mut start_index_opt = input.find(sep);
-        if start_index_opt.is_none() {
-            let sep = " ----------- ";
-            start_index_opt = input.find(sep);
-            println!("No exploit details found.{:#?}", input);
-            if start_index_opt.is_none() {
-                return Vec::new();
-            }
+    let catalog = Catalog::load(&catalog_path)?;
+    let commands = builder.build(&catalog).and_then(|commands| match &rc {
+        Some(_) => builder.resource_script(),
+        None => Ok(commands.join("\n")),
+    });
+    match (commands, rc) {
+        (Ok(script), Some(path)) => std::fs::write(path, script),
+        (Ok(commands), None) => {
+            println!("{}", commands);
+            Ok(())
         }
-        let start_index = start_index_opt.unwrap();
-        let truncated_input = &input[start_index + sep.len()..];
-        let sections = truncated_input
-            .split("\n")
-            .map(|line| {
-                line.split("  ")
-                    .map(|w| w.trim().to_string())
-                    .filter(|w| w != "")
-                    .collect::<Vec<String>>()
-            })
-            .filter(|w| w.len() > 0)
-            .collect::<Vec<Vec<String>>>();
-
-        let mut details: Vec<ExploitDetails> = Vec::new();
-        for exploit in sections {
-            if exploit.len() < 5 {
-                let last_index = details.len() - 1;
-                println!("i: {}", last_index);
-                details[last_index].description.push_str(&exploit[0]);
-                continue;
-            }
-            details.push(ExploitDetails {
-                name: exploit[1].clone(),
-                disclosure_date: exploit[2].clone(),
-                rank: exploit[3].clone(),
-                check: exploit[4] == "Yes",
-                description: exploit[5].clone(),
-            });
+        (Err(e), _) => {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
         }
-        details
     }
-    pub fn get_exploits_details(&mut self) -> Vec<ExploitDetails> {
-        self.run_command("show exploits");
-        let lines_vec = self.output.clone();
-        let line = lines_vec.join("\n");
+}
 
-        MSFProcess::extract_exploit_details(&line)
+/// `check --targets a,b [--targets-file file] [--modules file]... [--filter
+/// regex] [--option NAME=VALUE]... [--out file] [--replay transcript]`: runs
+/// `check` with every module that supports it against every target and
+/// writes a JSON report. Modules come from `exploits.json` by default.
+fn check_targets(args: &[String]) -> std::io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let usage = || -> ! {
+        eprintln!("Usage: check --targets a,b [--targets-file file] [--modules file]... [--filter regex] [--option NAME=VALUE]... [--out file] [--replay transcript]");
+        std::process::exit(2);
+    };
+    let mut targets: Vec<String> = Vec::new();
+    let mut module_files = Vec::new();
+    let mut filter = None;
+    let mut options = Vec::new();
+    let mut out = None;
+    let mut replay = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--targets" => targets.extend(value.split(',').map(|t| t.trim().to_string())),
+            "--targets-file" => {
+                let text = std::fs::read_to_string(value)?;
+                targets.extend(text.lines().map(|t| t.trim().to_string()));
+            }
+            "--modules" => module_files.push(value.clone()),
+            "--filter" => filter = Some(Regex::new(value).unwrap_or_else(|_| usage())),
+            "--option" => {
+                let (name, value) = value.split_once('=').unwrap_or_else(|| usage());
+                options.push((name.to_string(), value.to_string()));
+            }
+            "--out" => out = Some(value.clone()),
+            "--replay" => replay = Some(value.clone()),
+            _ => usage(),
+        }
     }
-    pub fn get_auxiliary_details(&mut self) -> Vec<ExploitDetails> {
-        self.run_command("show auxiliary");
-        let lines_vec = self.output.clone();
-        let line = lines_vec.join("\n");
-
-        MSFProcess::extract_exploit_details(&line)
+    targets.retain(|t| !t.is_empty() && !t.starts_with('#'));
+    if targets.is_empty() {
+        usage();
     }
-    pub fn get_payload_details(&mut self) -> Vec<ExploitDetails> {
-        self.run_command("show payloads");
-        let lines_vec = self.output.clone();
-        let line = lines_vec.join("\n");
-
-        MSFProcess::extract_exploit_details(&line)
+    if module_files.is_empty() {
+        module_files.push("exploits.json".to_string());
     }
-    fn parse_option(input: Vec<String>) -> Option<Vec<Parameter>> {
-        let mut res = Vec::new();
-        let mut input = VecDeque::from(input);
-
-        while !input.is_empty() {
-            let mut options = Vec::new();
-
-            for i in 0..4 {
-                if let Some(popped) = input.pop_front() {
-                    if popped == "|-line-|" && i == 2 {
-                        options.insert(1, "".to_string());
-                    }
-                    options.push(popped);
-                } else {
-                    // Handle case where there are not enough items left in the input
-                    options.push("".to_string()); // Or handle this case differently
-                }
-            }
-
-            if options.len() < 4 {
-                // Ensure we have at least 4 elements for `Parameter::new`
-                options.resize(4, "".to_string());
-            }
-
-            let parameter = Parameter::new(
-                options[0].clone(),
-                if options[1] != "" {
-                    Some(options[1].clone())
-                } else {
-                    None
-                },
-                options[2] == "yes",
-                options[3].clone(),
-            );
-            res.push(parameter);
+    let mut modules: Vec<ExploitDetails> = Vec::new();
+    for path in &module_files {
+        let file = std::fs::File::open(path)?;
+        modules.extend(serde_json::from_reader::<_, Vec<ExploitDetails>>(
+            std::io::BufReader::new(file),
+        )?);
+    }
+    let selected = check::select_modules(&modules, filter.as_ref());
+    info!(
+        "Checking {} targets with {} modules",
+        targets.len(),
+        selected.len()
+    );
 
-            // Skip any `|-line-|` at the front of `input`
-            if let Some(front) = input.front() {
-                if front == "|-line-|" {
-                    input.pop_front();
-                }
-            }
+    let builder = match replay {
+        Some(transcript) => {
+            let exe = env::current_exe()?.to_string_lossy().to_string();
+            Process::builder(&exe)
+                .args(&["replay-console", &transcript])
+                .from_env()
+        }
+        None => Process::builder("msfconsole").from_env(),
+    };
+    let mut msf = MSFProcess::try_with_builder(builder)?;
+    let report = check::run_checks(&mut msf, &selected, &targets, &options);
+    match out {
+        Some(path) => serde_json::to_writer_pretty(std::fs::File::create(path)?, &report)?,
+        None => {
+            serde_json::to_writer_pretty(std::io::stdout(), &report)?;
+            println!();
         }
-
-        Some(res)
     }
+    Ok(())
+}
 
-    fn extract_options_and_payloads(
-        input: &str,
-    ) -> Result<(String, Option<Vec<Parameter>>, Vec<String>), String> {
-        // eprintln!("Extracting from: {:#?}", input);
-        let payload_str;
-        let module_option_sub;
-        let payload_option_sub;
-        let exploit_targets_sub;
-        let payload_search_str = "Payload options (";
-        let exploit_search_str = "Exploit target:";
-        let exploit_search_end_str = "\n\n\n\n";
-        let exploit_end_idx = input.find(exploit_search_end_str);
-        if exploit_end_idx.is_none() {
-            let exploit_search_end_str = "\n\n\n";
-            let exploit_end_idx = input.find(exploit_search_end_str);
-            if exploit_end_idx.is_none() {
-                let msg = format!(
-                    "{}:No exploit target end found.\nInput:{:#?}",
-                    "Error".red(),
-                    input
-                );
-                warn!("{}", msg);
-                return Err(msg);
-            }
+/// `extract <modules dir> [--out dir] [--canonical]`: builds
+/// `exploits.json`, `auxiliaries.json` and `exploits_options.json` from
+/// module sources without starting msfconsole, plus `modules_info.json`
+/// with everything read from each source.
+fn extract_sources(args: &[String]) -> std::io::Result<()> {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    let usage = || -> ! {
+        eprintln!("Usage: extract <modules dir> [--out dir] [--canonical]");
+        std::process::exit(2);
+    };
+    let modules_dir = match args.first() {
+        Some(dir) if !dir.starts_with("--") => std::path::PathBuf::from(dir),
+        _ => usage(),
+    };
+    let mut out = std::path::PathBuf::from(".");
+    let mut canonical = canonical::enabled();
+    let mut args = args[1..].iter();
+    while let Some(arg) = args.next() {
+        if arg == "--canonical" {
+            canonical = true;
+            continue;
         }
-        let exploit_end_idx = exploit_end_idx.unwrap();
-        let payload_idx_res = input.find(payload_search_str);
-
-        if payload_idx_res.is_none() {
-            let exp_idx = input.find(exploit_search_str);
-            if exp_idx.is_none() {
-                let msg = format!("{}:No exploit found.\nInput:{:#?}", "Error".red(), input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            let exp_idx = exp_idx.unwrap();
-
-            payload_str = "";
-            payload_option_sub = "";
-            if input.len() <= exp_idx {
-                let msg = format!("{}:Out of bounds. Looking for module options substring. \nInput len:{} idx:{}\n {:#?}", "Error".red(), input.len(), exp_idx, input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            module_option_sub = &input[..exp_idx];
-            if input.len() <= exploit_end_idx {
-                let msg = format!("{}:Out of bounds. Looking for exploit targets substring. \nInput len:{} idx:{}\n {:#?}", "Error".red(), input.len(), exploit_end_idx, input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            exploit_targets_sub = &input[exp_idx..exploit_end_idx];
-        } else {
-            let payload_idx = payload_idx_res.unwrap();
-            let payload_end_idx = input[payload_idx..].find("):");
-            if payload_end_idx.is_none() {
-                let msg = format!("{}:No payload found.\nInput:{:#?}", "Error".red(), input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            let payload_end_idx = payload_end_idx.unwrap();
-            let exp_idx = input[payload_idx..].find(exploit_search_str);
-            if exp_idx.is_none() {
-                let msg = format!("{}:No exploit found.\nInput:{:#?}", "Error".red(), input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            let exp_idx = exp_idx.unwrap();
-
-            if payload_idx >= input.len() {
-                let msg = format!("{}:Out of bounds. Looking for payload substring. \nInput len:{} idx:{}\n {:#?}", "Error".red(), input.len(), payload_idx, input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            if payload_idx + payload_end_idx >= input.len() {
-                let msg =  format!("{}:Out of bounds. Looking for payload end substring. \nInput len:{} idx:{}\n {:#?}", "Error".red(), input.len(), payload_idx + payload_end_idx, input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-            if exploit_end_idx >= input.len() {
-                let msg = format!("{}:Out of bounds. Looking for exploit end substring. \nInput len:{} idx:{}\n {:#?}", "Error".red(), input.len(), exploit_end_idx, input);
-                warn!("{}", msg);
-                return Err(msg);
-            }
-
-            // module options come first from start till payload name
-            module_option_sub = &input[..payload_idx];
-
-            // then payload name until "):"
-            payload_str =
-                &input[payload_idx + payload_search_str.len()..payload_idx + payload_end_idx];
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--out" => out = std::path::PathBuf::from(value),
+            _ => usage(),
+        }
+    }
 
-            // then payload options string till exploi target start
-            payload_option_sub = &input[payload_idx + payload_end_idx..payload_idx + exp_idx];
+    let start = Instant::now();
+    let modules = extract::extract_tree(&modules_dir);
+    let (exploits, others): (Vec<_>, Vec<_>) =
+        modules.iter().partition(|m| m.name.starts_with("exploit/"));
+    let auxiliaries: Vec<ExploitDetails> = others
+        .iter()
+        .filter(|m| m.name.starts_with("auxiliary/"))
+        .map(|m| m.details())
+        .collect();
+    let details: Vec<ExploitDetails> = exploits.iter().map(|m| m.details()).collect();
+    let entries = exploits
+        .iter()
+        .map(|m| (m.name.clone(), CatalogEntry::from(m.exploit())));
+    let (details, auxiliaries) = if canonical {
+        (canonical::details(details), canonical::details(auxiliaries))
+    } else {
+        (details, auxiliaries)
+    };
+
+    std::fs::create_dir_all(&out)?;
+    write_json(&out.join("exploits.json"), &details, canonical)?;
+    write_json(&out.join("auxiliaries.json"), &auxiliaries, canonical)?;
+    if canonical {
+        let options = canonical::catalog(entries);
+        write_json(&out.join("exploits_options.json"), &options, canonical)?;
+    } else {
+        let options: HashMap<String, CatalogEntry> = entries.collect();
+        write_json(&out.join("exploits_options.json"), &options, canonical)?;
+    }
+    write_json(&out.join("modules_info.json"), &modules, canonical)?;
+
+    // no console to ask for the framework version
+    let mut manifest = Manifest::new(None);
+    manifest.count("exploits", details.len());
+    manifest.count("auxiliary", auxiliaries.len());
+    for name in [
+        "exploits.json",
+        "auxiliaries.json",
+        "exploits_options.json",
+        "modules_info.json",
+    ] {
+        manifest.add_file(&out, name)?;
+    }
+    manifest.write(&out)?;
+    info!(
+        "Extracted {} exploits and {} auxiliary modules in {} ms",
+        details.len(),
+        auxiliaries.len(),
+        start.elapsed().as_millis()
+    );
+    Ok(())
+}
 
-            // then exploit targets
-            exploit_targets_sub =
-                &input[payload_idx + exp_idx + exploit_search_str.len()..exploit_end_idx];
+/// `compare <left dir> <right dir> [--out report.json]`: compares the
+/// catalogs in two directories, e.g. a scrape and `extract --out`, prints a
+/// summary table and writes the full report as JSON.
+fn compare_catalogs(args: &[String]) -> std::io::Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: compare <left dir> <right dir> [--out report.json]");
+        std::process::exit(2);
+    };
+    let (left, right) = match args {
+        [left, right, ..] if !left.starts_with("--") && !right.starts_with("--") => (left, right),
+        _ => usage(),
+    };
+    let mut out = None;
+    let mut args = args[2..].iter();
+    while let Some(arg) = args.next() {
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--out" => out = Some(value.clone()),
+            _ => usage(),
         }
+    }
+    let load = |dir: &str| {
+        compare::CatalogSource::load(std::path::Path::new(dir)).unwrap_or_else(|e| {
+            eprintln!("{}: {}", "Error".red(), e);
+            std::process::exit(1);
+        })
+    };
+    let comparison = compare::compare(&load(left), &load(right));
+    println!("{}", compare::summary_table(&comparison));
+    if let Some(path) = out {
+        serde_json::to_writer_pretty(std::fs::File::create(path)?, &comparison)?;
+    }
+    Ok(())
+}
 
-        let sep = " -----------\n";
-        let mut non_module_options_expliot = false;
-        let mut module_options_start = module_option_sub.find(sep);
-        if module_options_start.is_none() {
-            let sep = " ----------- ";
-            module_options_start = module_option_sub.find(sep);
-            if module_options_start.is_none() {
-                non_module_options_expliot = true;
-            }
-        }
-        let non_payload_options_exploit = payload_option_sub.is_empty();
-        let mut payload_options_start = payload_option_sub.find(sep);
-        if !non_payload_options_exploit {
-            if payload_options_start.is_none() {
-                let sep = " ----------- ";
-                payload_options_start = payload_option_sub.find(sep);
-                if module_options_start.is_none() {
-                    let msg = format!(
-                        "{}:No payload options found.\nmodule_option_sub:{:#?}\npayload_option_sub:{:#?}",
-                        "Error".red(),
-                        module_option_sub,
-                        payload_option_sub
-                    );
-                    warn!("{}", msg);
-                    return Err(msg);
-                }
-            }
-            let payload_options_start = payload_options_start.unwrap();
-            let payload_options_unparsed = &payload_option_sub[payload_options_start + sep.len()..];
+/// `tree [--modules file]... [--depth n] [--collapse] [--out file]`: groups
+/// modules by the segments of their names for the radial menu and writes
+/// the tree as JSON.
+fn module_tree(args: &[String]) -> std::io::Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: tree [--modules file]... [--depth n] [--collapse] [--out file]");
+        std::process::exit(2);
+    };
+    let mut options = tree::TreeOptions::default();
+    let mut module_files = Vec::new();
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--collapse" {
+            options.collapse = true;
+            continue;
         }
-        let sep = " ----\n";
-        let mut exploits_start = exploit_targets_sub.find(sep);
-        if exploits_start.is_none() {
-            let sep = " ---- \n";
-            exploits_start = exploit_targets_sub.find(sep);
-            if exploits_start.is_none() {
-                let msg = format!(
-                    "{}:No exploit targets found.\nmodule_option_sub:{:#?}",
-                    "Error".red(),
-                    module_option_sub
-                );
-                warn!("{}", msg);
-                return Err(msg);
-            }
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--modules" => module_files.push(std::path::PathBuf::from(value)),
+            "--depth" => options.max_depth = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--out" => out = Some(value.clone()),
+            _ => usage(),
         }
-        let exploits_start = exploits_start.unwrap();
-        let exploit_target_unparsed = &exploit_targets_sub[exploits_start + sep.len()..];
-
-        let module_options;
-        if !non_module_options_expliot {
-            let module_options_start = module_options_start.unwrap();
-            let module_options_unparsed = &module_option_sub[module_options_start + sep.len()..];
-            let mod_lines: Vec<String> = module_options_unparsed
-                .split("\n")
-                .map(|w| w.replace(". ", "."))
-                .filter(|w| w.to_string() != "")
-                .collect();
-            let newline = mod_lines.join("  |-line-|  ");
-            let mut module_options_vec: Vec<String> =
-                newline.split("  ").map(|w| w.trim().to_string()).collect();
-
-            // fixing for multi line sections
-            let mut line_section_count = 0;
-            let mut section_indexes = [0, 0, 0, 0];
-            let mut previous_section_indexes = [0, 0, 0, 0];
-            let mut last_line_index = 0;
-            let mut sections_to_remove = Vec::new();
-            for i in 0..module_options_vec.len() {
-                if module_options_vec[i] == "|-line-|" {
-                    if line_section_count == 1 || line_section_count == 2 {
-                        // Has only second section
-                        if module_options_vec[i - 1] == "" {
-                            // Add to 2nd section
-                            let previous_string = module_options_vec[previous_section_indexes[1]]
-                                .to_string()
-                                .clone();
-                            let current_string = module_options_vec[section_indexes[0]].clone();
-                            let combined_string = previous_string + &current_string;
-                            module_options_vec[previous_section_indexes[1]] = combined_string;
-                        }
-                        // Has both sections
-                        else if section_indexes[1] != 0 {
-                            // Add to 2nd section
-                            let previous_string = module_options_vec[previous_section_indexes[1]]
-                                .to_string()
-                                .clone();
-                            let current_string = module_options_vec[section_indexes[0]].clone();
-                            let combined_string = previous_string + &current_string;
-                            module_options_vec[previous_section_indexes[1]] = combined_string;
-
-                            // Add to 4th section
-                            let previous_string = module_options_vec[previous_section_indexes[3]]
-                                .to_string()
-                                .clone();
-                            let current_string = module_options_vec[section_indexes[1]].clone();
-                            let combined_string = previous_string + &current_string;
-                            module_options_vec[previous_section_indexes[3]] = combined_string;
-                        }
-                        // Has only 4th section
-                        else {
-                            // Add to 4th section
-                            let previous_string = module_options_vec[previous_section_indexes[3]]
-                                .to_string()
-                                .clone();
-                            let current_string = module_options_vec[section_indexes[0]].clone();
-                            let combined_string = previous_string + &current_string;
-                            module_options_vec[previous_section_indexes[3]] = combined_string;
-                        }
-                        sections_to_remove.push((last_line_index, i));
-                        last_line_index = i;
-                    } else {
-                        previous_section_indexes = section_indexes;
-                    }
-                    line_section_count = 0;
-                    section_indexes = [0, 0, 0, 0];
-                } else if module_options_vec[i] != "" {
-                    section_indexes[line_section_count] = i;
-                    line_section_count += 1;
-                }
-            }
-
-            // remove sections
-            let mut offseted_size: i32 = 0;
-            for i in sections_to_remove {
-                let start_index = (i.0 as i32 + offseted_size) as usize;
-                let end_index = (i.1 as i32 + offseted_size) as usize;
-                module_options_vec.drain(start_index..end_index + 1);
-                offseted_size -= i.1 as i32 - i.0 as i32;
-            }
+    }
+    let modules = read_module_lists(module_files)?;
+    let root = tree::build(&modules, &options);
+    write_output(out, &root)
+}
 
-            let module_options_vec: Vec<String> = module_options_vec
-                .iter()
-                .map(|w| w.to_string())
-                .filter(|w| w != "")
-                .collect();
-            let module_options_o = MSFProcess::parse_option(module_options_vec.clone());
-            if module_options_o.is_none() {
-                return Err(format!(
-                    "{}:No options found.\n\nmodule_option_sub:{:#?}\n\nmodule_options_vec:{:#?}\n\nmodule_options_o:{:#?}",
-                    "Error".red(),
-                    module_option_sub,
-                    module_options_vec,
-                    module_options_o
-                ));
-            }
-            module_options = module_options_o;
-        } else {
-            module_options = None;
+/// `modules [--modules file]... [--min-rank rank] [--max-rank rank] [--check
+/// yes|no|unknown] [--since date] [--until date] [--sort name|rank|date]
+/// [--reverse] [--out file]`: lists the module rows that match, e.g.
+/// `--min-rank great --check yes --sort date --reverse` for the newest
+/// reliable modules that can check a target. Dates are `YYYY-MM-DD` and
+/// inclusive.
+fn list_modules(args: &[String]) -> std::io::Result<()> {
+    let usage = || -> ! {
+        eprintln!("Usage: modules [--modules file]... [--min-rank rank] [--max-rank rank] [--check yes|no|unknown] [--since date] [--until date] [--sort name|rank|date] [--reverse] [--out file]");
+        std::process::exit(2);
+    };
+    let date = |value: &str| {
+        chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap_or_else(|_| usage())
+    };
+    let mut query = query::ModuleQuery::default();
+    let mut module_files = Vec::new();
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--reverse" {
+            query.reverse = true;
+            continue;
+        }
+        let value = args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--modules" => module_files.push(std::path::PathBuf::from(value)),
+            "--min-rank" => query.min_rank = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--max-rank" => query.max_rank = Some(value.parse().unwrap_or_else(|_| usage())),
+            "--check" => {
+                query.check = Some(query::parse_check_filter(value).unwrap_or_else(|_| usage()))
+            }
+            "--since" => query.disclosed_since = Some(date(value)),
+            "--until" => query.disclosed_until = Some(date(value)),
+            "--sort" => query.sort = value.parse().unwrap_or_else(|_| usage()),
+            "--out" => out = Some(value.clone()),
+            _ => usage(),
         }
+    }
+    let modules = query.apply(read_module_lists(module_files)?);
+    write_output(out, &modules)
+}
 
-        let exploit_target: Vec<String> = exploit_target_unparsed
-            .split("  ")
-            .map(|w| w.trim().to_string())
-            .filter(|w| w != "")
+/// Module rows from `files`, or from `exploits.json`, `payloads.json` and
+/// `auxiliaries.json`, those that exist, when none are given.
+fn read_module_lists(mut files: Vec<std::path::PathBuf>) -> std::io::Result<Vec<ExploitDetails>> {
+    if files.is_empty() {
+        files = ["exploits.json", "payloads.json", "auxiliaries.json"]
+            .iter()
+            .map(std::path::PathBuf::from)
+            .filter(|path| path.exists())
             .collect();
-
-        Ok((payload_str.to_string(), module_options, exploit_target))
     }
+    let mut modules: Vec<ExploitDetails> = Vec::new();
+    for path in &files {
+        let file = std::fs::File::open(path)?;
+        modules.extend(serde_json::from_reader::<_, Vec<ExploitDetails>>(
+            std::io::BufReader::new(file),
+        )?);
+    }
+    Ok(modules)
+}
 
-    fn get_sections(input: Vec<String>) -> Vec<Vec<String>> {
-        let mut sections = vec![];
-        let mut current_section = Vec::new();
-        let mut section_open = false;
-
-        let mut lines = vec![];
-        let mut previous_line: Option<String> = None;
-        for i in 0..input.len() {
-            let line = &input[i];
-            let sublines: Vec<String> = line
-                .split("\n")
-                .map(|x| {
-                    let sp: Vec<String> = x
-                        .split("  ")
-                        .filter(|y| y.to_string() != "")
-                        .map(|x| x.to_string())
-                        .collect();
-                    if sp.len() == 1 {}
-                    sp.join("<--->").to_string()
-                })
-                .collect();
-            lines.extend(sublines);
+/// Writes `value` as pretty JSON to `path`, or to stdout.
+fn write_output<T: serde::Serialize>(path: Option<String>, value: &T) -> std::io::Result<()> {
+    match path {
+        Some(path) => serde_json::to_writer_pretty(std::fs::File::create(path)?, value)?,
+        None => {
+            serde_json::to_writer_pretty(std::io::stdout(), value)?;
+            println!();
         }
-        println!("lines: {}", lines.join("\n"));
-        let mut empty_count = 0;
-        for i in 0..lines.len() {
-            let line = &lines[i];
-            if section_open {
-                if line == "" {
-                    empty_count += 1;
-                    continue;
-                }
-                if empty_count == 2 {
-                    sections.push(current_section.clone());
-                    section_open = false;
-                    current_section.clear();
-                    empty_count = 0;
-                    continue;
-                }
-                current_section.push(line.to_string());
-                empty_count = 0;
-            } else {
-                if line.contains("----") {
-                    section_open = true;
-                    current_section = Vec::new();
-                    let section_title = &lines[i - 3];
-                    current_section.push(section_title.to_string());
-                }
-                continue;
-            }
-        }
-        if section_open {
-            // remove last two elements
-            current_section.pop();
-            current_section.pop();
-            sections.push(current_section.clone());
-        }
-        sections
     }
-    pub fn add_options(
-        &mut self,
-        exploit: &mut Exploit,
-        retries: Option<usize>,
-    ) -> Result<(), String> {
-        let use_command = format!("use {}", exploit.name);
-        self.run_command(&use_command);
-        self.clear();
-
-        let retries = match retries {
-            Some(retries) => retries,
-            None => 3,
-        };
+    Ok(())
+}
 
-        let mut try_i = 0;
-        loop {
-            let show_options = "show options";
-            self.run_command(&show_options);
-            let output = self.output.clone();
+fn write_json<T: serde::Serialize>(
+    path: &std::path::Path,
+    value: &T,
+    canonical: bool,
+) -> std::io::Result<()> {
+    if canonical {
+        canonical::write_json(path, value)?;
+    } else {
+        serde_json::to_writer_pretty(std::fs::File::create(path)?, value)?;
+    }
+    info!("Done writing to {}", path.display());
+    Ok(())
+}
 
-            let sections = MSFProcess::get_sections(output);
-            let mut exploit_options = vec![];
-            let mut payload_options = vec![];
-            let mut target = vec![];
-            for (i, v) in sections.iter().enumerate() {
-                // info!("sections: {:#?}", v);
-                if v[0].to_lowercase().contains(&"module".to_lowercase()) {
-                    let remain = &v[1..];
-                    let mut previous_line: Option<String> = None;
-                    let mut lines: Vec<String> = vec![];
-                    for (i, subline) in remain.iter().enumerate() {
-                        if subline.split("<--->").count() <= 2 {
-                            // error!("Found single line:{}", subline);
-                            // If there's a previous line, append the current string to it.
-                            if !previous_line.is_none() {
-                                lines.pop();
-                                lines.push(previous_line.clone().unwrap() + subline);
-                            } else {
-                                // If no previous line, treat it as a standalone line.
-                                lines.push(subline.to_string());
-                            }
-                        } else {
-                            // Add the line as is when it's not a single string.
-                            lines.push(subline.to_string());
-                            previous_line = Some(subline.to_string());
-                        }
-                    }
-                    exploit_options = lines
-                        .iter()
-                        .map(|x| {
-                            x.split("<--->")
-                                .map(|x| x.to_string())
-                                .filter(|x| x != "")
-                                .collect::<Vec<String>>()
-                        })
-                        .map(|line| {
-                            if line.len() == 3 {
-                                Parameter::new(
-                                    line[0].clone(),
-                                    None,
-                                    line[1] == "yes",
-                                    line[2].clone(),
-                                )
-                            } else {
-                                if line.len() != 4 {
-                                    error!("{:#?}", line);
-                                }
-                                Parameter::new(
-                                    line[0].clone(),
-                                    Some(line[1].clone()),
-                                    line[2] == "yes",
-                                    line[3].clone(),
-                                )
-                            }
-                        })
-                        .collect::<Vec<Parameter>>();
-                } else if v[0].to_lowercase().contains(&"payload".to_lowercase()) {
-                    let payload_name = v[0].clone();
-                    exploit.payload = payload_name
-                        .replace("Payload options (", "")
-                        .replace("):", "");
-                    let remain = &v[1..];
-                    remain
-                        .iter()
-                        .map(|x| {
-                            x.split("<--->")
-                                .map(|x| x.to_string())
-                                .collect::<Vec<String>>()
-                        })
-                        .for_each(|line| {
-                            if line.len() == 3 {
-                                payload_options.push(Parameter::new(
-                                    line[0].clone(),
-                                    None,
-                                    line[1] == "yes",
-                                    line[2].clone(),
-                                ))
-                            } else if line.len() == 4 {
-                                payload_options.push(Parameter::new(
-                                    line[0].clone(),
-                                    Some(line[1].clone()),
-                                    line[2] == "yes",
-                                    line[3].clone(),
-                                ));
-                            }
-                        })
-                } else if v[0].to_lowercase().contains(&"target".to_lowercase()) {
-                    let remain = &v[1..];
-                    remain.iter().for_each(|x| {
-                        target.extend(
-                            x.split("<--->")
-                                .map(|x| x.to_string())
-                                .collect::<Vec<String>>(),
-                        )
-                    });
-                }
-            }
-            if exploit_options.len() > 0 {
-                exploit.options = Some(exploit_options);
-            }
-            if payload_options.len() > 0 {
-                exploit.payload_options = Some(payload_options);
-            }
-            if target.len() > 0 {
-                exploit.target = Some(target);
-            }
-            break;
+/// `replay-console <transcript.jsonl>`: behaves like the recorded
+/// msfconsole, answering commands on stdin with the recorded output.
+fn replay_console(args: &[String]) -> std::io::Result<()> {
+    let path = match args.first() {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: replay-console <transcript.jsonl>");
+            std::process::exit(2);
         }
+    };
+    let records = transcript::read_transcript(std::path::Path::new(path))?;
+    replay::serve_replay(
+        ReplayConsole::new(records),
+        std::io::stdin().lock(),
+        std::io::stdout().lock(),
+        std::io::stderr().lock(),
+    )
+}
 
-        let back = "back";
-        self.run_command(&back);
-        self.clear();
-
-        Ok(())
+fn main() -> std::io::Result<()> {
+    let args = env::args().collect::<Vec<String>>();
+    match args.get(1).map(String::as_str) {
+        Some("replay") => return replay(&args[2..]),
+        Some("ansi") => return convert_ansi(&args[2..]),
+        Some("events") => return print_events(&args[2..]),
+        Some("serve") => return serve(&args[2..]),
+        Some("daemon") => return daemon(&args[2..]),
+        Some("replay-console") => return replay_console(&args[2..]),
+        Some("validate") => return validate_options(&args[2..]),
+        Some("commands") => return build_commands(&args[2..]),
+        Some("check") => return check_targets(&args[2..]),
+        Some("extract") => return extract_sources(&args[2..]),
+        Some("compare") => return compare_catalogs(&args[2..]),
+        Some("tree") => return module_tree(&args[2..]),
+        Some("modules") => return list_modules(&args[2..]),
+        _ => {}
     }
-}
-impl Drop for MSFProcess {
-    fn drop(&mut self) {
-        // eprintln!("Dropping MSFProcess");
-        info!("Dropping MSFProcess");
-        info!("Dropped MSFProcess");
+
+    let usage = || -> ! {
+        eprintln!("Usage: create-options-json [threads processes] [--advanced] [--strict-counts] [--canonical]");
+        std::process::exit(2);
+    };
+    // each flag can also be turned on with its environment variable
+    let flag = |name: &str, var: &str| {
+        args[1..].iter().any(|a| a == name) || env::var(var).is_ok_and(|v| v == "1")
+    };
+    if let Some(unknown) = args[1..].iter().find(|a| {
+        a.starts_with("--")
+            && !["--advanced", "--strict-counts", "--canonical"].contains(&a.as_str())
+    }) {
+        eprintln!("Unknown option: {}", unknown);
+        usage();
     }
-}
+    // a second pass per module, with `show advanced`
+    let advanced = flag("--advanced", "PROCESS_RUNNER_ADVANCED");
+    // fail when the banner's module counts are not all found
+    let strict = flag("--strict-counts", "PROCESS_RUNNER_STRICT_COUNTS");
+    let canonical = args[1..].iter().any(|a| a == "--canonical") || canonical::enabled();
+    let positional: Vec<&String> = args[1..].iter().filter(|a| !a.starts_with("--")).collect();
 
-fn main() -> std::io::Result<()> {
     // create args number of threads with an msf process of their own to run a portion of the exploits
     // using the offset of the exploits array depending on the number of threads (first argument) for
     // each process of msf
-    let args = env::args().collect::<Vec<String>>();
     let mut num_threads_per_process = 1;
     // get number of processor as process count
     let mut num_process = num_cpus::get() / 2;
-    if args.len() > 2 {
-        num_threads_per_process = args[1].parse().unwrap();
-        num_process = args[2].parse().unwrap();
+    match positional.as_slice() {
+        [] => {}
+        [threads, processes] => {
+            num_threads_per_process = threads.parse().unwrap_or_else(|_| usage());
+            num_process = processes.parse().unwrap_or_else(|_| usage());
+        }
+        _ => usage(),
     }
     let logger =
         env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).build();
@@ -874,32 +685,75 @@ fn main() -> std::io::Result<()> {
     let exploits;
     let payloads;
     let auxiliary;
+    let mut msf_version = None;
     {
         let mut msf = MSFProcess::new();
         info!("Getting exploits");
         msf.clear();
+        let mut tables = Vec::new();
         exploits = msf.get_exploits_details();
+        tables.push(("exploits", msf.output().join("\n"), exploits.clone()));
         payloads = msf.get_payload_details();
+        tables.push(("payloads", msf.output().join("\n"), payloads.clone()));
         auxiliary = msf.get_auxiliary_details();
+        tables.push(("auxiliary", msf.output().join("\n"), auxiliary.clone()));
+
+        // compare with the banner's counts so lost rows do not go unnoticed
+        match msf.get_banner() {
+            Some(found) => {
+                msf_version = found.version.clone();
+                info!(
+                    "Metasploit {}",
+                    found.version.as_deref().unwrap_or("(unknown version)")
+                );
+                let results: Vec<_> = tables
+                    .iter()
+                    .map(|(module_type, output, parsed)| {
+                        banner::check_completeness(
+                            module_type,
+                            found.count(module_type),
+                            output,
+                            parsed,
+                        )
+                    })
+                    .collect();
+                if !banner::report(&results, strict) && strict {
+                    error!("Incomplete scrape, see the rows above");
+                    std::process::exit(1);
+                }
+            }
+            None => warn!("No module counts in the banner; cannot check completeness"),
+        }
     }
 
+    let (exploits, payloads, auxiliary) = if canonical {
+        (
+            canonical::details(exploits),
+            canonical::details(payloads),
+            canonical::details(auxiliary),
+        )
+    } else {
+        (exploits, payloads, auxiliary)
+    };
+
+    let mut manifest = Manifest::new(msf_version);
+    manifest.count("exploits", exploits.len());
+    manifest.count("payloads", payloads.len());
+    manifest.count("auxiliary", auxiliary.len());
+
     let exp_len = exploits.len();
     info!("Writing {} exploits to exploits.json", exp_len);
-    let file = std::fs::File::create("exploits.json").unwrap();
-    serde_json::to_writer_pretty(file, &exploits).unwrap();
-    info!("Done writing to exploits.json");
+    write_json(std::path::Path::new("exploits.json"), &exploits, canonical)?;
 
-    let payload_len = payloads.len();
-    info!("Writing {} payloads to payloads.json", payload_len);
-    let file = std::fs::File::create("payloads.json").unwrap();
-    serde_json::to_writer_pretty(file, &payloads).unwrap();
-    info!("Done writing to payloads.json");
+    info!("Writing {} payloads to payloads.json", payloads.len());
+    write_json(std::path::Path::new("payloads.json"), &payloads, canonical)?;
 
-    let auxiliary_len = auxiliary.len();
-    info!("Writing {} auxiliary to auxiliaries.json", auxiliary_len);
-    let file = std::fs::File::create("auxiliaries.json").unwrap();
-    serde_json::to_writer_pretty(file, &auxiliary).unwrap();
-    info!("Done writing to auxiliaries.json");
+    info!("Writing {} auxiliary to auxiliaries.json", auxiliary.len());
+    write_json(
+        std::path::Path::new("auxiliaries.json"),
+        &auxiliary,
+        canonical,
+    )?;
 
     let exploits_per_process = exp_len / num_process;
     let exploits_per_thread = exploits_per_process / num_threads_per_process;
@@ -929,13 +783,12 @@ fn main() -> std::io::Result<()> {
         let output_exploits_outer = Arc::clone(&output_exploits);
         let running_proc = Arc::clone(&running_process);
 
-        let thread_bar;
-        if proc == num_process - 1 {
+        let thread_bar = if proc == num_process - 1 {
             let num = exp_len - process_start;
-            thread_bar = multi_progress.add(ProgressBar::new(num as u64))
+            multi_progress.add(ProgressBar::new(num as u64))
         } else {
-            thread_bar = multi_progress.add(ProgressBar::new(exploits_per_thread as u64))
-        }
+            multi_progress.add(ProgressBar::new(exploits_per_thread as u64))
+        };
         thread_bar.set_style(
             ProgressStyle::default_bar()
                 .template(
@@ -970,10 +823,9 @@ fn main() -> std::io::Result<()> {
 
                     let mut msf = msf_clone.lock().unwrap();
                     let mut exploits = msf.get_exploits();
-                    for i in thread_start..thread_end {
-                        let mut exploit = &mut exploits[i];
-                        let res = msf.add_options(&mut exploit, Some(5));
-                        if res.is_err() {
+                    for exploit in &mut exploits[thread_start..thread_end] {
+                        let res = msf.add_options(exploit, Some(5));
+                        if let Err(err) = res {
                             error!(
                                 "{} {} proc {}: adding options to exploit: {}",
                                 "Error thread".red(),
@@ -981,9 +833,14 @@ fn main() -> std::io::Result<()> {
                                 proc,
                                 exploit.name
                             );
-                            error!("{}", res.unwrap_err());
+                            error!("{}", err);
                             continue;
                         }
+                        if advanced {
+                            if let Err(err) = msf.add_advanced_options(exploit, Some(5)) {
+                                warn!("{}: advanced options: {}", exploit.name, err);
+                            }
+                        }
                         thread_bar_clone.inc(1);
                     }
                     let mut output_exploits = output_exploits_clone.lock().unwrap();
@@ -992,7 +849,7 @@ fn main() -> std::io::Result<()> {
                 threads.push(thread);
             }
             for thread in threads {
-                let _ = thread.join().unwrap();
+                thread.join().unwrap();
             }
             thread_bar.finish_with_message(format!("Process {} Complete", proc));
             process_bar.inc(1);
@@ -1001,7 +858,6 @@ fn main() -> std::io::Result<()> {
                 // remove the current proc from list
                 let index = lck.iter().position(|x| *x == proc).unwrap();
                 lck.remove(index);
-                // eprintln!("Remaining running process {:#?}", lck);
             }
         });
         process_threads.push(process_thread);
@@ -1009,10 +865,8 @@ fn main() -> std::io::Result<()> {
 
     // join threads
     let process_count = process_threads.len();
-    // eprintln!("Started and now waiting for {} processes", process_count);
     for process in process_threads {
-        let _ = process.join().unwrap();
-        // eprintln!("Process joined");
+        process.join().unwrap();
     }
 
     info!("Done waiting for {} processes", process_count);
@@ -1023,10 +877,52 @@ fn main() -> std::io::Result<()> {
     info!("Done Getting Options in {} seconds", duration.as_secs());
 
     info!("Done adding options");
-    let exploits = output_exploits.lock().unwrap().clone();
+    let mut exploits = output_exploits.lock().unwrap().clone();
     let exp_len = exploits.len() as u64;
+
+    // defaults like LHOST come from this host; keep the catalog portable
+    let host = HostEnvironment::detect();
+    exploits.iter_mut().for_each(|e| host.normalize_exploit(e));
     info!("Exploits: {}", exp_len);
 
+    // scrape each default payload's options once, for exploits to refer to
+    let mut payload_names: Vec<String> = exploits
+        .iter()
+        .filter(|e| e.payload_options.is_some())
+        .map(|e| e.payload.clone())
+        .collect();
+    payload_names.sort();
+    payload_names.dedup();
+    info!("Getting options of {} payloads", payload_names.len());
+    let mut payload_catalog = PayloadCatalog::new();
+    {
+        let mut msf = MSFProcess::new();
+        for name in payload_names {
+            match msf.payload_options(&name) {
+                Ok(mut options) => {
+                    if advanced {
+                        match msf.advanced_options(&format!("payload/{}", name), None) {
+                            Ok((module, _)) => msf::merge_options(&mut options, module),
+                            Err(e) => warn!("{}: advanced options: {}", name, e),
+                        }
+                    }
+                    options.iter_mut().for_each(|p| host.normalize(p));
+                    payload_catalog.insert(name, options);
+                }
+                Err(e) => warn!("{}; keeping its options inline", e),
+            }
+        }
+    }
+    if canonical {
+        payload_catalog = canonical::payloads(payload_catalog);
+    }
+    write_json(
+        std::path::Path::new(PAYLOADS_FILE),
+        &payload_catalog,
+        canonical,
+    )?;
+    manifest.count("payload_schemas", payload_catalog.len());
+
     // create new bar to write concurrently to the file while showing progress
     let process_bar = Arc::new(multi_progress.add(ProgressBar::new(exp_len)));
     process_bar.set_style(
@@ -1038,33 +934,28 @@ fn main() -> std::io::Result<()> {
     info!("Writing to exploits_options.json");
     let start = Instant::now();
 
-    let exploits_map: HashMap<String, HashMap<String, serde_json::Value>> = exploits
-        .into_iter()
-        .map(|exploit| {
-            let mut exploit_data = HashMap::new();
-            exploit_data.insert(
-                "payload".to_string(),
-                serde_json::to_value(&exploit.payload).unwrap(),
-            );
-            exploit_data.insert(
-                "options".to_string(),
-                serde_json::to_value(&exploit.options).unwrap(),
-            );
-            exploit_data.insert(
-                "target".to_string(),
-                serde_json::to_value(&exploit.target).unwrap(),
-            );
-            (exploit.name, exploit_data)
-        })
-        .collect();
-
-    let write_thread = thread::spawn(move || {
-        // write to file
-        let file = std::fs::File::create("exploits_options.json").unwrap();
-        serde_json::to_writer_pretty(file, &exploits_map).unwrap();
-        info!("Done writing to exploits_options.json");
+    let entries = exploits.into_iter().map(|exploit| {
+        let name = exploit.name.clone();
+        let mut entry = CatalogEntry::from(exploit);
+        if canonical {
+            entry = canonical::entry(entry);
+        }
+        if let Some(schema) = payload_catalog.get(&entry.payload) {
+            entry.reference_payload(schema);
+        }
+        (name, entry)
     });
 
+    let path = std::path::Path::new("exploits_options.json");
+    let write_thread = if canonical {
+        // sorted by module name instead of thread completion order
+        let exploits_map = canonical::catalog(entries);
+        thread::spawn(move || write_json(path, &exploits_map, true))
+    } else {
+        let exploits_map: HashMap<String, CatalogEntry> = entries.collect();
+        thread::spawn(move || write_json(path, &exploits_map, false))
+    };
+
     let exp_len_arc = Arc::new(exp_len);
     let exp_len_clone = Arc::clone(&exp_len_arc);
     let process_bar = Arc::clone(&process_bar);
@@ -1081,16 +972,30 @@ fn main() -> std::io::Result<()> {
             // Increase speed more aggressively as progress approaches total.
             // The 50 and 2 adjust the curve of the slowdown.  Experiment with these.
             if speed < 600 {
-                speed = (speed as f64 + (1.125 * (progress as f64 / total as f64).powf(1.10))) as u64;
+                speed =
+                    (speed as f64 + (1.125 * (progress as f64 / total as f64).powf(1.10))) as u64;
             }
         }
     });
 
-    let _ = progress_thread.join().unwrap();
-    let _ = write_thread.join().unwrap();
+    progress_thread.join().unwrap();
+    write_thread.join().unwrap()?;
 
     process_bar.finish_with_message("Done writing to file!");
 
+    let cwd = std::path::Path::new(".");
+    for name in [
+        "exploits.json",
+        "payloads.json",
+        "auxiliaries.json",
+        "exploits_options.json",
+        PAYLOADS_FILE,
+    ] {
+        manifest.add_file(cwd, name)?;
+    }
+    manifest.write(cwd)?;
+    info!("Done writing to {}", MANIFEST_FILE);
+
     let end = Instant::now();
     let duration = end - start;
     info!("Done writing to file in {} seconds", duration.as_secs());
@@ -0,0 +1,109 @@
+//! `manifest.json`, written next to the generated JSON files, records what
+//! produced them: the Metasploit and runner versions, host, time, command
+//! line, module counts and a hash of each file. The files themselves keep
+//! their format.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::host;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileEntry {
+    /// Relative to the manifest's directory.
+    pub path: String,
+    pub sha1: String,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Manifest {
+    /// The framework version msfconsole reported, e.g. `6.4.43-dev-`.
+    pub msf_version: Option<String>,
+    pub generated: DateTime<Utc>,
+    pub runner_version: String,
+    pub host: String,
+    /// The runner's arguments, without the executable.
+    pub command: Vec<String>,
+    /// Modules of each type that were written, e.g. `exploits`.
+    pub counts: BTreeMap<String, usize>,
+    pub files: Vec<FileEntry>,
+}
+
+impl Manifest {
+    pub fn new(msf_version: Option<String>) -> Self {
+        Self {
+            msf_version,
+            generated: Utc::now(),
+            runner_version: env!("CARGO_PKG_VERSION").to_string(),
+            host: host::hostname(),
+            command: std::env::args().skip(1).collect(),
+            counts: BTreeMap::new(),
+            files: Vec::new(),
+        }
+    }
+
+    pub fn count(&mut self, module_type: &str, count: usize) {
+        self.counts.insert(module_type.to_string(), count);
+    }
+
+    /// Hashes `name` in `dir`, which must already be written.
+    pub fn add_file(&mut self, dir: &Path, name: &str) -> std::io::Result<()> {
+        let path = dir.join(name);
+        self.files.retain(|f| f.path != name);
+        self.files.push(FileEntry {
+            path: name.to_string(),
+            sha1: file_sha1(&path)?,
+            bytes: std::fs::metadata(&path)?.len(),
+        });
+        Ok(())
+    }
+
+    /// Writes `manifest.json` to `dir`.
+    pub fn write(&self, dir: &Path) -> std::io::Result<()> {
+        let file = File::create(dir.join(MANIFEST_FILE))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    /// The manifest in `dir`, if it has one.
+    pub fn load(dir: &Path) -> std::io::Result<Self> {
+        let file = File::open(dir.join(MANIFEST_FILE))?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+
+    /// Files whose contents no longer match their hash, e.g. because one
+    /// was regenerated without the others.
+    pub fn changed_files(&self, dir: &Path) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|f| file_sha1(&dir.join(&f.path)).ok().as_ref() != Some(&f.sha1))
+            .map(|f| f.path.clone())
+            .collect()
+    }
+}
+
+pub fn file_sha1(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
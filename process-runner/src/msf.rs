@@ -0,0 +1,802 @@
+use chrono::NaiveDate;
+use log::{debug, error, info, warn};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::sync::Arc;
+use std::thread;
+
+use crate::banner::Banner;
+use crate::buffer::{BufferLimits, BufferStats, LineBuffer};
+use crate::events::{self, ConsoleEvent};
+use crate::kind::{self, OptionKind};
+use crate::policy::Policy;
+use crate::process::Process;
+use crate::rank::{self, Rank};
+use crate::scope::ScopeGuard;
+use crate::spawn::ProcessBuilder;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Parameter {
+    pub name: String,
+    pub default_value: Option<String>,
+    pub required: bool,
+    pub description: String,
+    /// Inferred type; missing in catalogs written before it was added.
+    #[serde(default)]
+    pub kind: Option<OptionKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<String>>,
+    /// The scraped default when `default_value` is a placeholder such as
+    /// `${LOCAL_IP}` for a value that came from the scraping host.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_default: Option<String>,
+    /// Listed by `show advanced` rather than `show options`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub advanced: bool,
+}
+impl Parameter {
+    pub fn new(
+        name: String,
+        default_value: Option<String>,
+        required: bool,
+        description: String,
+    ) -> Self {
+        let (kind, enum_values) = kind::infer(&name, default_value.as_deref(), &description);
+        Self {
+            name,
+            default_value,
+            required,
+            description,
+            kind: Some(kind),
+            enum_values,
+            original_default: None,
+            advanced: false,
+        }
+    }
+
+    /// The stored kind, or the inferred one for older catalogs.
+    pub fn kind(&self) -> OptionKind {
+        self.kind.unwrap_or_else(|| self.infer().0)
+    }
+
+    /// Accepted values of an enum option.
+    pub fn accepted_values(&self) -> Option<Vec<String>> {
+        match self.kind {
+            Some(_) => self.enum_values.clone(),
+            None => self.infer().1,
+        }
+    }
+
+    /// Fills in the kind of an option loaded from an older catalog.
+    pub fn infer_kind(&mut self) {
+        if self.kind.is_none() {
+            let (kind, enum_values) = self.infer();
+            self.kind = Some(kind);
+            self.enum_values = enum_values;
+        }
+    }
+
+    fn infer(&self) -> (OptionKind, Option<Vec<String>>) {
+        kind::infer(&self.name, self.default_value.as_deref(), &self.description)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExploitDetails {
+    pub name: String,
+    /// `None` where msfconsole shows `.`, which is also what gets written.
+    #[serde(
+        default,
+        deserialize_with = "lenient_date",
+        serialize_with = "date_or_dot"
+    )]
+    pub disclosure_date: Option<NaiveDate>,
+    /// `None` when the rank column held something else, i.e. the row was
+    /// split wrongly. Written as an empty string.
+    #[serde(
+        default,
+        deserialize_with = "rank::lenient",
+        serialize_with = "rank_or_empty"
+    )]
+    pub rank: Option<Rank>,
+    /// Whether the module implements `check`, `None` when not known.
+    /// Written as a boolean when known and as an empty string otherwise,
+    /// so an unknown check reads back as `None` rather than `false`.
+    #[serde(
+        default,
+        deserialize_with = "lenient_check",
+        serialize_with = "check_or_empty"
+    )]
+    pub check: Option<bool>,
+    pub description: String,
+}
+
+/// `Yes`/`No` from the Check column, or `true`/`false`.
+pub fn parse_check(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "yes" | "true" => Some(true),
+        "no" | "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// Catalogs from older scrapes have `.` for modules without a date.
+fn lenient_date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+    let date = Option::<String>::deserialize(deserializer)?;
+    Ok(date.and_then(|d| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok()))
+}
+
+/// Writes `.` for a missing date, as earlier versions did, so existing
+/// readers of module lists keep working.
+fn date_or_dot<S: Serializer>(date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error> {
+    match date {
+        Some(date) => serializer.collect_str(date),
+        None => serializer.serialize_str("."),
+    }
+}
+
+/// Keeps the rank a string for the same readers.
+fn rank_or_empty<S: Serializer>(rank: &Option<Rank>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(rank.map_or("", Rank::as_str))
+}
+
+/// Keeps a known check a boolean for the same readers; like the rank,
+/// an unknown one is an empty string.
+fn check_or_empty<S: Serializer>(check: &Option<bool>, serializer: S) -> Result<S::Ok, S::Error> {
+    match check {
+        Some(check) => serializer.serialize_bool(*check),
+        None => serializer.serialize_str(""),
+    }
+}
+
+/// Accepts booleans as well as the Check column's text.
+fn lenient_check<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Check {
+        Bool(bool),
+        Text(String),
+    }
+    Ok(match Option::<Check>::deserialize(deserializer)? {
+        Some(Check::Bool(check)) => Some(check),
+        Some(Check::Text(text)) => parse_check(&text),
+        None => None,
+    })
+}
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Exploit {
+    pub name: String,
+    pub payload: String,
+    pub payload_options: Option<Vec<Parameter>>,
+    pub options: Option<Vec<Parameter>>,
+    pub target: Option<Vec<String>>,
+}
+
+/// Overflow counters for one `run_command` call.
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct CommandResult {
+    pub stdout: BufferStats,
+    pub stderr: BufferStats,
+    /// Why the scope guard kept the command from being sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refused: Option<String>,
+}
+
+/// What a `run_command` call produced, as handed to an output hook.
+pub struct CommandOutput<'a> {
+    pub command: &'a str,
+    pub output: &'a [String],
+    pub raw_output: &'a [String],
+    pub result: &'a CommandResult,
+}
+
+/// Called after every command, e.g. to stream output to a web client.
+pub type OutputHook = Box<dyn FnMut(&CommandOutput) + Send>;
+
+pub struct MSFProcess {
+    process: Process,
+    output: Vec<String>,
+    raw_output: Vec<String>,
+    limits: BufferLimits,
+    exploits: Vec<Exploit>,
+    on_output: Option<OutputHook>,
+    scope_guard: Option<Arc<ScopeGuard>>,
+    policy: Option<Arc<Policy>>,
+}
+
+impl MSFProcess {
+    pub fn new() -> Self {
+        Self::with_builder(Process::builder("msfconsole").from_env())
+    }
+
+    /// Starts msfconsole from `builder`. Its buffer limits also cap the
+    /// output collected for each command.
+    pub fn with_builder(builder: ProcessBuilder) -> Self {
+        Self::try_with_builder(builder).expect("Failed to spawn process")
+    }
+
+    /// Like `with_builder`, but returns spawn errors (e.g. msfconsole not
+    /// on `PATH`) instead of panicking.
+    pub fn try_with_builder(builder: ProcessBuilder) -> std::io::Result<Self> {
+        let limits = builder.get_buffer_limits().clone();
+        let scope_guard = builder.get_scope_guard().cloned();
+        let policy = builder.get_policy().cloned();
+        let mut process = builder.spawn()?;
+        process.start_reader();
+        process.start_error_reader();
+
+        let mut read_line = process.read();
+        let mut read_line_err = process.read_err();
+        thread::sleep(std::time::Duration::from_secs(1));
+        let _ = process.write("ping");
+        while !read_line_err.contains("ping: usage error:") {
+            read_line = process.read();
+            read_line_err = process.read_err();
+            debug!("{}", read_line);
+        }
+        debug!("MSF started\n{}", read_line);
+        process.take_stats();
+        Ok(Self {
+            process,
+            output: Vec::new(),
+            raw_output: Vec::new(),
+            limits,
+            exploits: Vec::new(),
+            on_output: None,
+            scope_guard,
+            policy,
+        })
+    }
+
+    pub fn set_output_hook(&mut self, hook: OutputHook) {
+        self.on_output = Some(hook);
+    }
+
+    /// Sends `command` and collects its output. Commands with line breaks
+    /// and those the policy or scope guard refuses are not sent; their
+    /// output is the refusal as an error line.
+    pub fn run_command(&mut self, command: &str) -> CommandResult {
+        // msfconsole runs each line as its own command, so the policy and
+        // the scope guard would only see the first one
+        if command.contains(['\n', '\r']) {
+            let reason = format!("{:?} contains a line break", command);
+            if let Some(guard) = &self.scope_guard {
+                guard.record(command, &reason);
+            }
+            return self.refuse(command, "console", reason);
+        }
+        if let Some(Err(reason)) = self.policy.as_ref().map(|p| p.check(command)) {
+            return self.refuse(command, "policy", reason);
+        }
+        if let Some(Err(reason)) = self.scope_guard.as_ref().map(|g| g.check(command)) {
+            return self.refuse(command, "scope guard", reason);
+        }
+        self.process.clear();
+        let _ = self.process.write(command);
+        thread::sleep(std::time::Duration::from_millis(10));
+        let _ = self.process.write("ping");
+        let mut read_line;
+        let mut read_line_err = "".to_string();
+        let mut lines = LineBuffer::new("command", self.limits.clone());
+        while !read_line_err.contains("ping: usage error:") {
+            read_line = self.process.read() + " ";
+            read_line_err = self.process.read_err();
+            lines.push(read_line);
+        }
+        self.output = lines.drain();
+        self.raw_output = self.process.take_raw();
+        // drop the echo of the ping used to detect the end of output
+        self.raw_output.retain(|line| !line.contains("exec: ping"));
+
+        let (mut stdout, stderr) = self.process.take_stats();
+        stdout.merge(lines.take_stats());
+        if stdout.overflowed() || stderr.overflowed() {
+            warn!(
+                "Output of '{}' overflowed: {} bytes dropped, {} bytes spilled",
+                command,
+                stdout.dropped_bytes + stderr.dropped_bytes,
+                stdout.spilled_bytes + stderr.spilled_bytes
+            );
+        }
+        let result = CommandResult {
+            stdout,
+            stderr,
+            refused: None,
+        };
+        if let Some(hook) = self.on_output.as_mut() {
+            hook(&CommandOutput {
+                command,
+                output: &self.output,
+                raw_output: &self.raw_output,
+                result: &result,
+            });
+        }
+        result
+    }
+
+    fn refuse(&mut self, command: &str, by: &str, reason: String) -> CommandResult {
+        self.output = vec![format!("[-] Refused by {}: {}", by, reason)];
+        self.raw_output = self.output.clone();
+        let result = CommandResult {
+            refused: Some(reason),
+            ..Default::default()
+        };
+        if let Some(hook) = self.on_output.as_mut() {
+            hook(&CommandOutput {
+                command,
+                output: &self.output,
+                raw_output: &self.raw_output,
+                result: &result,
+            });
+        }
+        result
+    }
+
+    pub fn clear(&mut self) {
+        self.process.clear();
+        self.output.clear();
+        self.raw_output.clear();
+    }
+
+    /// Stripped stdout of the last command.
+    pub fn output(&self) -> &[String] {
+        &self.output
+    }
+
+    /// Status events (`[*]`, `[+]`, `[-]`, `[!]` lines) in the output of the
+    /// last command.
+    pub fn events(&self) -> Vec<ConsoleEvent> {
+        events::parse_command_output(&self.output)
+    }
+
+    /// Stdout of the last command with ANSI colours intact. Only filled when
+    /// the console was built with `keep_ansi`; convert with `ansi::to_spans`
+    /// or `ansi::to_html`.
+    pub fn raw_output(&self) -> &[String] {
+        &self.raw_output
+    }
+
+    fn extract_exploit_names(input: &str) -> Vec<String> {
+        let re = Regex::new(r"exploit/([a-zA-Z0-9_/]+)").unwrap();
+
+        let exploit_names: Vec<String> = re
+            .captures_iter(input)
+            .filter_map(|cap| cap.get(1).map(|m| "exploit/".to_owned() + m.as_str()))
+            .collect();
+
+        exploit_names
+    }
+
+    pub fn get_exploits(&mut self) -> Vec<Exploit> {
+        if !self.exploits.is_empty() {
+            return self.exploits.clone();
+        }
+        self.run_command("show exploits");
+
+        let line = self.output.join("\n");
+
+        let exploit_names = Self::extract_exploit_names(line.as_str());
+        let exploits: Vec<Exploit> = exploit_names
+            .into_iter()
+            .map(|name| Exploit {
+                name,
+                options: None,
+                target: None,
+                payload_options: None,
+                payload: "".to_string(),
+            })
+            .collect();
+        self.clear();
+
+        exploits
+    }
+
+    /// Parses a `show exploits`/`show payloads`/`show auxiliary` table.
+    pub fn extract_exploit_details(input: &str) -> Vec<ExploitDetails> {
+        let sep = " -----------\n";
+        let mut start_index_opt = input.find(sep);
+        if start_index_opt.is_none() {
+            let sep = " ----------- ";
+            start_index_opt = input.find(sep);
+            debug!("No exploit details found.{:#?}", input);
+            if start_index_opt.is_none() {
+                return Vec::new();
+            }
+        }
+        let start_index = start_index_opt.unwrap();
+        // the rows start on the line after the separator
+        let truncated_input = input[start_index..]
+            .split_once('\n')
+            .map_or("", |(_, rows)| rows);
+        // the table ends at the first blank line; the hint and the echo of
+        // the ping after it are not rows
+        let sections = truncated_input
+            .split("\n")
+            .take_while(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split("  ")
+                    .map(|w| w.trim().to_string())
+                    .filter(|w| !w.is_empty())
+                    .collect::<Vec<String>>()
+            })
+            .filter(|w| !w.is_empty())
+            .collect::<Vec<Vec<String>>>();
+
+        let mut details: Vec<ExploitDetails> = Vec::new();
+        for exploit in sections {
+            if exploit.len() < 5 {
+                // a wrapped description
+                if let Some(last) = details.last_mut() {
+                    last.description.push_str(&exploit[0]);
+                }
+                continue;
+            }
+            details.push(ExploitDetails {
+                name: exploit[1].clone(),
+                disclosure_date: NaiveDate::parse_from_str(&exploit[2], "%Y-%m-%d").ok(),
+                rank: exploit[3].parse().ok(),
+                check: parse_check(&exploit[4]),
+                description: exploit[5].clone(),
+            });
+        }
+        details
+    }
+    pub fn get_exploits_details(&mut self) -> Vec<ExploitDetails> {
+        self.run_command("show exploits");
+        let line = self.output.join("\n");
+
+        MSFProcess::extract_exploit_details(&line)
+    }
+    pub fn get_auxiliary_details(&mut self) -> Vec<ExploitDetails> {
+        self.run_command("show auxiliary");
+        let line = self.output.join("\n");
+
+        MSFProcess::extract_exploit_details(&line)
+    }
+    pub fn get_payload_details(&mut self) -> Vec<ExploitDetails> {
+        self.run_command("show payloads");
+        let line = self.output.join("\n");
+
+        MSFProcess::extract_exploit_details(&line)
+    }
+    /// Module counts and framework version from the `banner` command, with
+    /// the version from `version` if the banner has none.
+    pub fn get_banner(&mut self) -> Option<Banner> {
+        self.run_command("banner");
+        let mut banner = Banner::parse(&self.output.join("\n"));
+        if banner.as_ref().is_none_or(|b| b.version.is_none()) {
+            self.run_command("version");
+            if let Some(version) = Banner::parse(&self.output.join("\n")).and_then(|b| b.version) {
+                banner.get_or_insert_with(Banner::default).version = Some(version);
+            }
+        }
+        banner
+    }
+    /// Splits console output into tables, each as its title followed by
+    /// its rows with the columns joined by `<--->`. A table starts at its
+    /// `----` underline and ends at two or more blank lines. Cells are
+    /// trimmed, since `run_command` appends a space to every line it reads.
+    /// A table still open at the end loses the `exec: ping` echo that
+    /// marked the end of the output.
+    fn get_sections(input: Vec<String>) -> Vec<Vec<String>> {
+        let mut sections = vec![];
+        let mut current_section = Vec::new();
+        let mut section_open = false;
+
+        let mut lines = vec![];
+        for line in &input {
+            let sublines: Vec<String> = line
+                .split("\n")
+                .map(|x| {
+                    let sp: Vec<String> = x
+                        .split("  ")
+                        .map(str::trim)
+                        .filter(|y| !y.is_empty())
+                        .map(|x| x.to_string())
+                        .collect();
+                    sp.join("<--->").to_string()
+                })
+                .collect();
+            lines.extend(sublines);
+        }
+        debug!("lines: {}", lines.join("\n"));
+        let mut empty_count = 0;
+        for i in 0..lines.len() {
+            let line = &lines[i];
+            if section_open {
+                if line.is_empty() {
+                    empty_count += 1;
+                    continue;
+                }
+                if empty_count >= 2 {
+                    sections.push(current_section.clone());
+                    section_open = false;
+                    current_section.clear();
+                    empty_count = 0;
+                    continue;
+                }
+                current_section.push(line.to_string());
+                empty_count = 0;
+            } else {
+                if line.contains("----") {
+                    section_open = true;
+                    current_section = Vec::new();
+                    // the title, a blank line and the header come first;
+                    // a table without them gets an empty title
+                    let section_title = i.checked_sub(3).map_or("", |t| lines[t].as_str());
+                    current_section.push(section_title.to_string());
+                }
+                continue;
+            }
+        }
+        if section_open {
+            // drop the prompt and echo of the ping that ended the output
+            current_section.retain(|line| !line.contains("exec: ping"));
+            sections.push(current_section.clone());
+        }
+        sections
+    }
+    /// Parses `show options` output into `exploit`'s options, payload,
+    /// payload options and targets.
+    pub fn parse_show_options(exploit: &mut Exploit, output: &str) {
+        let sections = Self::get_sections(vec![output.to_string()]);
+        Self::apply_sections(exploit, &sections);
+    }
+
+    fn apply_sections(exploit: &mut Exploit, sections: &[Vec<String>]) {
+        let mut exploit_options = vec![];
+        let mut payload_options = vec![];
+        let mut target = vec![];
+        for v in sections.iter() {
+            if v[0].to_lowercase().contains(&"module".to_lowercase()) {
+                let remain = &v[1..];
+                let mut previous_line: Option<String> = None;
+                let mut lines: Vec<String> = vec![];
+                for subline in remain.iter() {
+                    if subline.split("<--->").count() <= 2 {
+                        // If there's a previous line, append the current string to it.
+                        if previous_line.is_some() {
+                            lines.pop();
+                            lines.push(previous_line.clone().unwrap() + subline);
+                        } else {
+                            // If no previous line, treat it as a standalone line.
+                            lines.push(subline.to_string());
+                        }
+                    } else {
+                        // Add the line as is when it's not a single string.
+                        lines.push(subline.to_string());
+                        previous_line = Some(subline.to_string());
+                    }
+                }
+                exploit_options = lines
+                    .iter()
+                    .map(|x| {
+                        x.split("<--->")
+                            .map(|x| x.to_string())
+                            .filter(|x| !x.is_empty())
+                            .collect::<Vec<String>>()
+                    })
+                    .map(|line| {
+                        if line.len() == 3 {
+                            Parameter::new(line[0].clone(), None, line[1] == "yes", line[2].clone())
+                        } else {
+                            if line.len() != 4 {
+                                error!("{:#?}", line);
+                            }
+                            Parameter::new(
+                                line[0].clone(),
+                                Some(line[1].clone()),
+                                line[2] == "yes",
+                                line[3].clone(),
+                            )
+                        }
+                    })
+                    .collect::<Vec<Parameter>>();
+            } else if v[0].to_lowercase().contains(&"payload".to_lowercase()) {
+                let payload_name = v[0].clone();
+                exploit.payload = payload_name
+                    .replace("Payload options (", "")
+                    .replace("):", "");
+                let remain = &v[1..];
+                remain
+                    .iter()
+                    .map(|x| {
+                        x.split("<--->")
+                            .map(|x| x.to_string())
+                            .collect::<Vec<String>>()
+                    })
+                    .for_each(|line| {
+                        if line.len() == 3 {
+                            payload_options.push(Parameter::new(
+                                line[0].clone(),
+                                None,
+                                line[1] == "yes",
+                                line[2].clone(),
+                            ))
+                        } else if line.len() == 4 {
+                            payload_options.push(Parameter::new(
+                                line[0].clone(),
+                                Some(line[1].clone()),
+                                line[2] == "yes",
+                                line[3].clone(),
+                            ));
+                        }
+                    })
+            } else if v[0].to_lowercase().contains(&"target".to_lowercase()) {
+                let remain = &v[1..];
+                remain.iter().for_each(|x| {
+                    target.extend(
+                        x.split("<--->")
+                            .map(|x| x.to_string())
+                            .collect::<Vec<String>>(),
+                    )
+                });
+            }
+        }
+        if !exploit_options.is_empty() {
+            exploit.options = Some(exploit_options);
+        }
+        if !payload_options.is_empty() {
+            exploit.payload_options = Some(payload_options);
+        }
+        if !target.is_empty() {
+            exploit.target = Some(target);
+        }
+    }
+
+    pub fn add_options(
+        &mut self,
+        exploit: &mut Exploit,
+        retries: Option<usize>,
+    ) -> Result<(), String> {
+        let use_command = format!("use {}", exploit.name);
+        self.run_command(&use_command);
+        self.clear();
+
+        let retries = retries.unwrap_or(3);
+
+        // retry when the output came back without any table in it
+        let mut sections = Vec::new();
+        for _ in 0..retries.max(1) {
+            self.run_command("show options");
+            let output = std::mem::take(&mut self.output);
+            sections = MSFProcess::get_sections(output);
+            if !sections.is_empty() {
+                break;
+            }
+        }
+        Self::apply_sections(exploit, &sections);
+
+        let back = "back";
+        self.run_command(back);
+        self.clear();
+
+        Ok(())
+    }
+
+    /// Looks up options, payload options and targets for any module by name.
+    pub fn module_options(&mut self, name: &str) -> Result<Exploit, String> {
+        if !is_module_name(name) {
+            return Err(format!("Invalid module name: {:?}", name));
+        }
+        let mut exploit = Exploit {
+            name: name.to_string(),
+            payload: "".to_string(),
+            payload_options: None,
+            options: None,
+            target: None,
+        };
+        self.add_options(&mut exploit, None)?;
+        if exploit.options.is_none()
+            && exploit.payload_options.is_none()
+            && exploit.target.is_none()
+        {
+            return Err(format!("No options found for module {}", name));
+        }
+        Ok(exploit)
+    }
+
+    /// Module and payload options from `show advanced` for `module`,
+    /// flagged as advanced.
+    pub fn advanced_options(
+        &mut self,
+        module: &str,
+        retries: Option<usize>,
+    ) -> Result<(Vec<Parameter>, Vec<Parameter>), String> {
+        if !is_module_name(module) {
+            return Err(format!("Invalid module name: {:?}", module));
+        }
+        self.run_command(&format!("use {}", module));
+        self.clear();
+        let mut sections = Vec::new();
+        for _ in 0..retries.unwrap_or(3).max(1) {
+            self.run_command("show advanced");
+            sections = MSFProcess::get_sections(std::mem::take(&mut self.output));
+            if !sections.is_empty() {
+                break;
+            }
+        }
+        self.run_command("back");
+        self.clear();
+        Ok(Self::advanced_from_sections(&sections))
+    }
+
+    /// Parses `show advanced` output into module and payload options,
+    /// flagged as advanced.
+    pub fn parse_show_advanced(output: &str) -> (Vec<Parameter>, Vec<Parameter>) {
+        Self::advanced_from_sections(&Self::get_sections(vec![output.to_string()]))
+    }
+
+    fn advanced_from_sections(sections: &[Vec<String>]) -> (Vec<Parameter>, Vec<Parameter>) {
+        // same tables as `show options`, titled "Module advanced options"
+        // and "Payload advanced options"
+        let mut advanced = Exploit {
+            name: String::new(),
+            payload: String::new(),
+            payload_options: None,
+            options: None,
+            target: None,
+        };
+        Self::apply_sections(&mut advanced, sections);
+        let flag = |options: Option<Vec<Parameter>>| {
+            let mut options = options.unwrap_or_default();
+            options.iter_mut().for_each(|p| p.advanced = true);
+            options
+        };
+        (flag(advanced.options), flag(advanced.payload_options))
+    }
+
+    /// Adds the module's advanced options to its options and, if it has
+    /// payload options, the payload's advanced options to those.
+    pub fn add_advanced_options(
+        &mut self,
+        exploit: &mut Exploit,
+        retries: Option<usize>,
+    ) -> Result<(), String> {
+        let (module, payload) = self.advanced_options(&exploit.name, retries)?;
+        if !module.is_empty() {
+            merge_options(exploit.options.get_or_insert_with(Vec::new), module);
+        }
+        if let Some(options) = exploit.payload_options.as_mut() {
+            merge_options(options, payload);
+        }
+        Ok(())
+    }
+
+    /// The options of `payload`, as `use payload/<name>` shows them.
+    pub fn payload_options(&mut self, payload: &str) -> Result<Vec<Parameter>, String> {
+        let module = self.module_options(&format!("payload/{}", payload))?;
+        module
+            .options
+            .ok_or_else(|| format!("No options found for payload {}", payload))
+    }
+}
+/// Appends the `advanced` options whose names `options` does not have yet.
+pub fn merge_options(options: &mut Vec<Parameter>, advanced: Vec<Parameter>) {
+    for option in advanced {
+        if !options.iter().any(|o| o.name == option.name) {
+            options.push(option);
+        }
+    }
+}
+
+/// Whether `name` looks like a module path and is safe to pass to `use`.
+pub fn is_module_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_/.-".contains(c))
+}
+
+impl Default for MSFProcess {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for MSFProcess {
+    fn drop(&mut self) {
+        info!("Dropping MSFProcess");
+        info!("Dropped MSFProcess");
+    }
+}
@@ -0,0 +1,212 @@
+//! Which console commands a caller may run. msfconsole runs unknown
+//! commands in a shell and has `irb`, `load` and `db_*` commands, so
+//! consoles reachable from the web UI only accept what the caller's role
+//! allows.
+//!
+//! A profile has allow and deny rules. A rule matches on the command's
+//! first word (`*` for any) and optionally a regex over the whole command.
+//! Deny rules win; commands no allow rule matches are refused.
+
+use log::error;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+#[derive(Deserialize, Clone, Debug)]
+struct RuleSpec {
+    verb: String,
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct ProfileSpec {
+    allow: Vec<RuleSpec>,
+    deny: Vec<RuleSpec>,
+}
+
+/// Custom profiles, e.g.
+/// `{"profiles": {"triage": {"allow": [{"verb": "show"}], "deny": []}}}`.
+/// A profile named like a built-in one replaces it.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct PolicyFile {
+    profiles: HashMap<String, ProfileSpec>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Rule {
+    verb: String,
+    pattern: Option<Regex>,
+}
+
+impl Rule {
+    fn new(verb: &str, pattern: Option<&str>) -> Result<Self, String> {
+        let pattern = pattern
+            .map(|p| Regex::new(p).map_err(|e| format!("Invalid pattern {:?}: {}", p, e)))
+            .transpose()?;
+        Ok(Self {
+            verb: verb.to_lowercase(),
+            pattern,
+        })
+    }
+
+    fn matches(&self, verb: &str, command: &str) -> bool {
+        (self.verb == "*" || self.verb == verb)
+            && self.pattern.as_ref().is_none_or(|p| p.is_match(command))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub allow: Vec<Rule>,
+    pub deny: Vec<Rule>,
+}
+
+impl Profile {
+    fn from_spec(spec: &ProfileSpec) -> Result<Self, String> {
+        let rules = |specs: &[RuleSpec]| {
+            specs
+                .iter()
+                .map(|r| Rule::new(&r.verb, r.pattern.as_deref()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allow: rules(&spec.allow)?,
+            deny: rules(&spec.deny)?,
+        })
+    }
+
+    fn verbs(verbs: &[&str]) -> Vec<Rule> {
+        verbs.iter().map(|v| Rule::new(v, None).unwrap()).collect()
+    }
+
+    /// Searching and reading module details, without changing anything.
+    pub fn read_only() -> Self {
+        let mut allow = Self::verbs(&[
+            "help", "?", "search", "info", "show", "use", "back", "options", "advanced", "targets",
+            "version", "banner",
+        ]);
+        // listing only; `-i`, `-k` and friends act on sessions and jobs
+        allow.push(Rule::new("sessions", Some(r"^sessions(\s+-l)?\s*$")).unwrap());
+        allow.push(Rule::new("jobs", Some(r"^jobs(\s+-l)?\s*$")).unwrap());
+        Self {
+            allow,
+            deny: Vec::new(),
+        }
+    }
+
+    /// Read-only plus configuring and running scanners and `check`. Only
+    /// auxiliary scanner and gather modules can be loaded, so `run` cannot
+    /// launch an exploit; other modules can still be read with `info`.
+    pub fn scanning() -> Self {
+        let mut allow: Vec<Rule> = Self::read_only()
+            .allow
+            .into_iter()
+            .filter(|r| r.verb != "use")
+            .collect();
+        allow.push(Rule::new("use", Some(r"^use\s+auxiliary/(scanner|gather)/")).unwrap());
+        allow.extend(Self::verbs(&[
+            "set", "setg", "unset", "unsetg", "run", "check", "jobs",
+        ]));
+        Self {
+            allow,
+            deny: Vec::new(),
+        }
+    }
+
+    pub fn full() -> Self {
+        Self {
+            allow: Self::verbs(&["*"]),
+            deny: Vec::new(),
+        }
+    }
+
+    pub fn built_in(role: &str) -> Option<Self> {
+        match role {
+            "read-only" => Some(Self::read_only()),
+            "scanning" => Some(Self::scanning()),
+            "full" => Some(Self::full()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Policy {
+    role: String,
+    profile: Profile,
+}
+
+impl Policy {
+    pub fn new(role: &str, profile: Profile) -> Self {
+        Self {
+            role: role.to_string(),
+            profile,
+        }
+    }
+
+    /// The profile for `role`: from the custom profiles file if it has one,
+    /// else `read-only`, `scanning` or `full`.
+    pub fn for_role(role: &str, profiles: Option<&Path>) -> Result<Self, String> {
+        if let Some(path) = profiles {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            let file: PolicyFile = serde_json::from_str(&text)
+                .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+            if let Some(spec) = file.profiles.get(role) {
+                return Ok(Self::new(role, Profile::from_spec(spec)?));
+            }
+        }
+        Profile::built_in(role)
+            .map(|profile| Self::new(role, profile))
+            .ok_or_else(|| format!("Unknown role: {}", role))
+    }
+
+    /// The policy for `PROCESS_RUNNER_ROLE`, with custom profiles from
+    /// `PROCESS_RUNNER_POLICY`. Unset means no policy. A role that cannot be
+    /// loaded gives an empty profile, so every command is refused.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let role = std::env::var("PROCESS_RUNNER_ROLE").ok()?;
+        let profiles = std::env::var("PROCESS_RUNNER_POLICY").ok();
+        let policy =
+            Self::for_role(&role, profiles.as_deref().map(Path::new)).unwrap_or_else(|e| {
+                error!("{}; refusing all commands", e);
+                Self::new(&role, Profile::default())
+            });
+        Some(Arc::new(policy))
+    }
+
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    /// Checks each `;`-separated command in `line`. Lines with line breaks
+    /// are refused: msfconsole would run each line as its own command, but
+    /// only the first verb would be checked.
+    pub fn check(&self, line: &str) -> Result<(), String> {
+        if line.contains(['\n', '\r']) {
+            return Err(format!("{:?} contains a line break", line));
+        }
+        for command in line.split(';').map(str::trim).filter(|c| !c.is_empty()) {
+            let verb = command
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_lowercase();
+            if self.profile.deny.iter().any(|r| r.matches(&verb, command)) {
+                return Err(format!("{:?} is denied for role {}", command, self.role));
+            }
+            if !self.profile.allow.iter().any(|r| r.matches(&verb, command)) {
+                return Err(format!(
+                    "{:?} is not allowed for role {}",
+                    command, self.role
+                ));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::console::Console;
+use crate::msf::MSFProcess;
+use crate::spawn::ProcessBuilder;
+
+type BuilderFn = Box<dyn Fn() -> ProcessBuilder + Send + Sync>;
+
+/// A fixed number of consoles shared between callers. Consoles are started
+/// on first use and replaced when they fail or stop; jobs are handed out
+/// round-robin.
+pub struct ConsolePool {
+    name: String,
+    builder: BuilderFn,
+    consoles: Mutex<Vec<Option<Arc<Console>>>>,
+    next: AtomicUsize,
+}
+
+impl ConsolePool {
+    pub fn new(
+        name: &str,
+        size: usize,
+        builder: impl Fn() -> ProcessBuilder + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            builder: Box::new(builder),
+            consoles: Mutex::new(vec![None; size.max(1)]),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// A pool of `size` msfconsoles configured from the environment.
+    pub fn from_env(size: usize) -> Self {
+        Self::new("pool", size, || {
+            crate::process::Process::builder("msfconsole").from_env()
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.consoles.lock().unwrap().len()
+    }
+
+    /// The next console in turn, starting a new one if needed.
+    pub fn get(&self) -> Arc<Console> {
+        let mut consoles = self.consoles.lock().unwrap();
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % consoles.len();
+        match &consoles[index] {
+            Some(console) if console.is_running() => Arc::clone(console),
+            _ => {
+                let name = format!("{}-{}", self.name, index);
+                let console = Console::spawn(&name, (self.builder)(), None);
+                consoles[index] = Some(Arc::clone(&console));
+                console
+            }
+        }
+    }
+
+    /// Runs `job` on the next console and waits for its result.
+    pub fn call<R, F>(&self, job: F) -> Result<R, String>
+    where
+        R: Send + 'static,
+        F: FnOnce(&mut MSFProcess) -> R + Send + 'static,
+    {
+        self.get().call(job)
+    }
+
+    pub fn stop(&self) {
+        for console in self.consoles.lock().unwrap().iter_mut() {
+            if let Some(console) = console.take() {
+                console.stop();
+            }
+        }
+    }
+
+    /// Stops every console and waits for them to exit.
+    pub fn shutdown(&self) {
+        let consoles: Vec<Arc<Console>> = self
+            .consoles
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter_map(Option::take)
+            .collect();
+        for console in &consoles {
+            console.stop();
+        }
+        for console in &consoles {
+            console.wait();
+        }
+    }
+}
+
+impl Drop for ConsolePool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
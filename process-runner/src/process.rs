@@ -0,0 +1,135 @@
+use log::error;
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+use std::thread;
+
+use crate::buffer::{BufferStats, StreamBuffer};
+use crate::spawn::ProcessBuilder;
+use crate::transcript::{Stream, Transcript};
+
+pub struct Process {
+    pub(crate) process: std::process::Child,
+    pub(crate) stdin: std::process::ChildStdin,
+    pub(crate) output_buf: Arc<StreamBuffer>,
+    pub(crate) err_buf: Arc<StreamBuffer>,
+    pub(crate) transcript: Option<Arc<Transcript>>,
+    /// Stdout lines with their ANSI escapes intact, when enabled with
+    /// `ProcessBuilder::keep_ansi`.
+    pub(crate) raw_buf: Option<Arc<StreamBuffer>>,
+}
+
+impl Process {
+    pub fn builder(command: &str) -> ProcessBuilder {
+        ProcessBuilder::new(command)
+    }
+
+    pub fn write(&mut self, data: &str) -> std::io::Result<()> {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(Stream::Stdin, data);
+        }
+        writeln!(self.stdin, "{}", data).expect("Failed to write to stdin");
+        self.stdin.flush().expect("Failed to flush stdin");
+        Ok(())
+    }
+
+    pub fn start_reader(&mut self) -> thread::JoinHandle<()> {
+        let out = self.process.stdout.take().expect("Failed to get stdout");
+        let output_buf = Arc::clone(&self.output_buf);
+        let raw_buf = self.raw_buf.clone();
+        let transcript = self.transcript.clone();
+        thread::spawn(move || {
+            let reader = BufReader::new(out);
+            for line in reader.lines() {
+                let ln = match line {
+                    Ok(output) => output,
+                    Err(e) => {
+                        error!("Error reading output: {}", e);
+                        return;
+                    }
+                };
+                if let Some(raw_buf) = &raw_buf {
+                    raw_buf.push(ln.clone());
+                }
+                let ln: Vec<String> = ln
+                    .split("\n")
+                    .map(|s| String::from_utf8(strip_ansi_escapes::strip(s)).unwrap())
+                    .collect();
+                let ln = ln.join("\n");
+                if let Some(transcript) = &transcript {
+                    transcript.record(Stream::Stdout, &ln);
+                }
+                output_buf.push(ln);
+            }
+        })
+    }
+
+    pub fn start_error_reader(&mut self) -> thread::JoinHandle<()> {
+        let stderr = self.process.stderr.take().expect("Failed to get stderr");
+        let err_buf = Arc::clone(&self.err_buf);
+        let transcript = self.transcript.clone();
+        thread::spawn(move || {
+            let reader = BufReader::new(stderr);
+            for line in reader.lines() {
+                let ln = match line {
+                    Ok(output) => output,
+                    Err(e) => {
+                        error!("Error reading output: {}", e);
+                        return;
+                    }
+                };
+                let ln_vec: Vec<u8> = strip_ansi_escapes::strip(&ln);
+                let striped_line = String::from_utf8(ln_vec).unwrap();
+                if let Some(transcript) = &transcript {
+                    transcript.record(Stream::Stderr, &striped_line);
+                }
+                err_buf.push(striped_line);
+            }
+        })
+    }
+
+    pub fn read(&mut self) -> String {
+        self.output_buf.wait_drain().join("\n")
+    }
+
+    pub fn read_err(&mut self) -> String {
+        self.err_buf.wait_drain().concat()
+    }
+
+    /// Takes the raw stdout lines read so far. Empty unless the process was
+    /// built with `keep_ansi`.
+    pub fn take_raw(&mut self) -> Vec<String> {
+        match &self.raw_buf {
+            Some(raw_buf) => raw_buf.try_drain(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn read_buf_size(&mut self) -> usize {
+        self.output_buf.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.output_buf.clear();
+        if let Some(raw_buf) = &self.raw_buf {
+            raw_buf.clear();
+        }
+    }
+
+    pub fn transcript(&self) -> Option<&Arc<Transcript>> {
+        self.transcript.as_ref()
+    }
+
+    /// Overflow counters for stdout and stderr since the last call.
+    pub fn take_stats(&mut self) -> (BufferStats, BufferStats) {
+        (self.output_buf.take_stats(), self.err_buf.take_stats())
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        if let Some(transcript) = &self.transcript {
+            transcript.record(Stream::Meta, "killed");
+        }
+        self.process.kill().expect("Failed to kill process");
+    }
+}
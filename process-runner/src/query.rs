@@ -0,0 +1,91 @@
+//! Filtering and sorting module rows by rank, disclosure date and check
+//! support, for the `modules` command.
+
+use chrono::NaiveDate;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::msf::{self, ExploitDetails};
+use crate::rank::Rank;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Rank,
+    Date,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(SortKey::Name),
+            "rank" => Ok(SortKey::Rank),
+            "date" => Ok(SortKey::Date),
+            _ => Err(format!("Unknown sort key: {:?}", s)),
+        }
+    }
+}
+
+/// `yes`, `no` or `unknown`, for matching the tri-state check column.
+pub fn parse_check_filter(text: &str) -> Result<Option<bool>, String> {
+    match text {
+        "unknown" => Ok(None),
+        text => msf::parse_check(text)
+            .map(Some)
+            .ok_or_else(|| format!("Expected yes, no or unknown, got {:?}", text)),
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ModuleQuery {
+    /// Leaves out modules ranked lower, and those without a known rank.
+    pub min_rank: Option<Rank>,
+    pub max_rank: Option<Rank>,
+    /// The check value to keep, `Some(None)` for modules where it is not
+    /// known.
+    pub check: Option<Option<bool>>,
+    /// Inclusive; leaves out modules without a disclosure date.
+    pub disclosed_since: Option<NaiveDate>,
+    /// Inclusive; leaves out modules without a disclosure date.
+    pub disclosed_until: Option<NaiveDate>,
+    pub sort: SortKey,
+    pub reverse: bool,
+}
+
+impl ModuleQuery {
+    pub fn matches(&self, module: &ExploitDetails) -> bool {
+        let rank = |bound: Option<Rank>, keep: fn(Rank, Rank) -> bool| {
+            bound.is_none_or(|bound| module.rank.is_some_and(|rank| keep(rank, bound)))
+        };
+        let date = |bound: Option<NaiveDate>, keep: fn(NaiveDate, NaiveDate) -> bool| {
+            bound.is_none_or(|bound| module.disclosure_date.is_some_and(|d| keep(d, bound)))
+        };
+        rank(self.min_rank, |rank, min| rank >= min)
+            && rank(self.max_rank, |rank, max| rank <= max)
+            && self.check.is_none_or(|check| module.check == check)
+            && date(self.disclosed_since, |d, since| d >= since)
+            && date(self.disclosed_until, |d, until| d <= until)
+    }
+
+    /// The matching modules, sorted. Ties, and modules without the sort
+    /// field, which come first, are ordered by name.
+    pub fn apply(&self, modules: Vec<ExploitDetails>) -> Vec<ExploitDetails> {
+        let mut modules: Vec<ExploitDetails> =
+            modules.into_iter().filter(|m| self.matches(m)).collect();
+        let key = |a: &ExploitDetails, b: &ExploitDetails| -> Ordering {
+            match self.sort {
+                SortKey::Name => Ordering::Equal,
+                SortKey::Rank => a.rank.cmp(&b.rank),
+                SortKey::Date => a.disclosure_date.cmp(&b.disclosure_date),
+            }
+        };
+        modules.sort_by(|a, b| key(a, b).then_with(|| a.name.cmp(&b.name)));
+        if self.reverse {
+            modules.reverse();
+        }
+        modules
+    }
+}
@@ -0,0 +1,73 @@
+//! Module ranks, from least to most reliable, as `show exploits` lists them
+//! and module sources set them (`Rank = ExcellentRanking`).
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Rank {
+    Manual,
+    Low,
+    Average,
+    /// What modules without a `Rank` get.
+    #[default]
+    Normal,
+    Good,
+    Great,
+    Excellent,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 7] = [
+        Rank::Manual,
+        Rank::Low,
+        Rank::Average,
+        Rank::Normal,
+        Rank::Good,
+        Rank::Great,
+        Rank::Excellent,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rank::Manual => "manual",
+            Rank::Low => "low",
+            Rank::Average => "average",
+            Rank::Normal => "normal",
+            Rank::Good => "good",
+            Rank::Great => "great",
+            Rank::Excellent => "excellent",
+        }
+    }
+}
+
+impl FromStr for Rank {
+    type Err = String;
+
+    /// Parses `average`, `Average` or `AverageRanking`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim();
+        let name = name.strip_suffix("Ranking").unwrap_or(name);
+        Rank::ALL
+            .into_iter()
+            .find(|rank| rank.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown rank: {:?}", s))
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Deserializes a rank string, with `None` for anything that is not a rank
+/// rather than an error, so rows split wrongly by older scrapes still load.
+pub fn lenient<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Rank>, D::Error> {
+    let rank = Option::<String>::deserialize(deserializer)?;
+    Ok(rank.and_then(|r| r.parse().ok()))
+}
@@ -0,0 +1,118 @@
+use std::io::{BufRead, Write};
+
+use crate::transcript::{Stream, TranscriptRecord};
+
+/// Stands in for msfconsole by answering commands from a recorded
+/// transcript. Each command is matched to the next stdin record with the
+/// same text, wrapping around so repeated commands replay again, and the
+/// output recorded after it is played back.
+pub struct ReplayConsole {
+    records: Vec<TranscriptRecord>,
+    pos: usize,
+}
+
+impl ReplayConsole {
+    pub fn new(records: Vec<TranscriptRecord>) -> Self {
+        Self { records, pos: 0 }
+    }
+
+    /// Output recorded before the first command, i.e. the banner.
+    pub fn startup(&mut self) -> Vec<TranscriptRecord> {
+        let start = self.output_from(0);
+        self.pos = start.len();
+        start
+    }
+
+    /// Output for `command`. Commands missing from the transcript get what
+    /// msfconsole prints for unknown commands; `ping` always answers, since
+    /// `MSFProcess` uses it to find the end of each command's output.
+    pub fn respond(&mut self, command: &str) -> Vec<TranscriptRecord> {
+        let is_command = |r: &TranscriptRecord| r.stream == Stream::Stdin && r.text == command;
+        let ahead = self.records[self.pos..]
+            .iter()
+            .position(is_command)
+            .map(|i| self.pos + i);
+        // wrapping around for a ping would rewind every later command
+        let found = match command {
+            "ping" => ahead,
+            _ => ahead.or_else(|| self.records.iter().position(is_command)),
+        };
+        if let Some(index) = found {
+            let output = self.output_from(index + 1);
+            self.pos = index + 1 + output.len();
+            return output;
+        }
+        if command == "ping" {
+            return vec![
+                Self::record(Stream::Stdout, "[*] exec: ping"),
+                Self::record(
+                    Stream::Stderr,
+                    "ping: usage error: Destination address required",
+                ),
+            ];
+        }
+        vec![Self::record(
+            Stream::Stdout,
+            &format!("[-] Unknown command: {}.", command),
+        )]
+    }
+
+    /// Output records from `start` up to the next command.
+    fn output_from(&self, start: usize) -> Vec<TranscriptRecord> {
+        self.records[start.min(self.records.len())..]
+            .iter()
+            .take_while(|r| r.stream != Stream::Stdin)
+            .filter(|r| r.stream != Stream::Meta)
+            .cloned()
+            .collect()
+    }
+
+    fn record(stream: Stream, text: &str) -> TranscriptRecord {
+        TranscriptRecord {
+            seq: 0,
+            mono_us: 0,
+            wall: chrono::Utc::now(),
+            stream,
+            text: text.to_string(),
+        }
+    }
+}
+
+fn play<O: Write, E: Write>(
+    records: &[TranscriptRecord],
+    out: &mut O,
+    err: &mut E,
+) -> std::io::Result<()> {
+    for record in records {
+        match record.stream {
+            Stream::Stderr => writeln!(err, "{}", record.text)?,
+            _ => writeln!(out, "{}", record.text)?,
+        }
+    }
+    out.flush()?;
+    err.flush()
+}
+
+/// Runs a replay console over the given streams until input ends.
+pub fn serve_replay<I: BufRead, O: Write, E: Write>(
+    mut console: ReplayConsole,
+    input: I,
+    mut out: O,
+    mut err: E,
+) -> std::io::Result<()> {
+    let startup = console.startup();
+    play(&startup, &mut out, &mut err)?;
+    for line in input.lines() {
+        let line = line?;
+        let command = line.trim();
+        if command.is_empty() {
+            continue;
+        }
+        if command == "exit" || command == "quit" {
+            break;
+        }
+        let output = console.respond(command);
+        play(&output, &mut out, &mut err)?;
+    }
+    Ok(())
+}
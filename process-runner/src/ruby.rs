@@ -0,0 +1,492 @@
+//! Just enough of a Ruby reader to pull literal arguments out of Metasploit
+//! module sources: strings (quoted, `%q`, `%w` and heredocs), numbers,
+//! symbols, arrays, hashes, constants and calls such as
+//! `OptString.new('NAME', [true, 'desc', 'default'])`. Anything else is read
+//! as `Other` and skipped, so unusual code does not stop the extraction.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RbValue {
+    Str(String),
+    Sym(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Nil,
+    Array(Vec<RbValue>),
+    Hash(Vec<(RbValue, RbValue)>),
+    /// A constant path such as `ARCH_X86` or `Msf::Module::Platform`.
+    Const(String),
+    /// `Name(args)` or `Name.new(args)`.
+    Call(String, Vec<RbValue>),
+    Other,
+}
+
+impl RbValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            RbValue::Str(s) | RbValue::Sym(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[RbValue]> {
+        match self {
+            RbValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// The value under a string or symbol key of a hash.
+    pub fn get(&self, key: &str) -> Option<&RbValue> {
+        match self {
+            RbValue::Hash(pairs) => pairs
+                .iter()
+                .find(|(k, _)| k.as_str() == Some(key))
+                .map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Strings, numbers and constants as text, e.g. for defaults.
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            RbValue::Str(s) | RbValue::Sym(s) | RbValue::Const(s) => Some(s.clone()),
+            RbValue::Int(i) => Some(i.to_string()),
+            RbValue::Float(f) => Some(f.to_string()),
+            RbValue::Bool(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+}
+
+pub struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    /// Heredoc bodies already read: reaching the newline at `.0` jumps to
+    /// `.1`, past the terminator.
+    heredoc_skip: Vec<(usize, usize)>,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src: src.as_bytes(),
+            pos: 0,
+            heredoc_skip: Vec::new(),
+        }
+    }
+
+    /// A parser positioned just after byte offset `pos`.
+    pub fn at(src: &'a str, pos: usize) -> Self {
+        let mut parser = Self::new(src);
+        parser.pos = pos;
+        parser
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<u8> {
+        self.src.get(self.pos + offset).copied()
+    }
+
+    /// The source from the current position, empty once past the end.
+    fn rest(&self) -> &[u8] {
+        self.src.get(self.pos..).unwrap_or_default()
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        String::from_utf8_lossy(&self.src[start..end]).to_string()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b'\n' => match self.heredoc_skip.iter().position(|(at, _)| *at == self.pos) {
+                    Some(i) => self.pos = self.heredoc_skip.remove(i).1,
+                    None => self.pos += 1,
+                },
+                b' ' | b'\t' | b'\r' | b'\\' => self.pos += 1,
+                b'#' => {
+                    while self.peek().is_some_and(|c| c != b'\n') {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_space();
+        if self.rest().starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Arguments of a call whose `(` was just consumed, up to its `)`.
+    /// Trailing `key => value` or `key: value` pairs become one hash.
+    pub fn call_args(&mut self) -> Vec<RbValue> {
+        self.sequence(b')', true)
+    }
+
+    /// Comma-separated values up to `close`, which is consumed.
+    fn sequence(&mut self, close: u8, bare_hash: bool) -> Vec<RbValue> {
+        let mut items = Vec::new();
+        let mut pairs = Vec::new();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                None => break,
+                Some(c) if c == close => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let start = self.pos;
+            if let Some(key) = self.label() {
+                pairs.push((RbValue::Sym(key), self.value()));
+            } else {
+                let value = self.value();
+                if bare_hash && self.eat("=>") {
+                    pairs.push((value, self.value()));
+                } else {
+                    items.push(value);
+                }
+            }
+            if self.pos == start {
+                // not something we can read; step over it
+                self.pos += 1;
+            }
+        }
+        if !pairs.is_empty() {
+            items.push(RbValue::Hash(pairs));
+        }
+        items
+    }
+
+    /// `key:` or `'key':` hash labels, not `::`.
+    fn label(&mut self) -> Option<String> {
+        self.skip_space();
+        let start = self.pos;
+        let key = match self.peek()? {
+            b'\'' | b'"' => match self.string()? {
+                RbValue::Str(s) => s,
+                _ => return None,
+            },
+            c if c.is_ascii_alphabetic() || c == b'_' => self.ident(),
+            _ => return None,
+        };
+        if self.peek() == Some(b':') && self.peek_at(1) != Some(b':') {
+            self.pos += 1;
+            Some(key)
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'?' || c == b'!')
+        {
+            self.pos += 1;
+        }
+        self.text(start, self.pos)
+    }
+
+    /// One value, with `+` concatenation of strings and method calls such
+    /// as `.freeze` applied to it skipped.
+    pub fn value(&mut self) -> RbValue {
+        let mut value = self.primary();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some(b'.') if self.peek_at(1) != Some(b'.') => {
+                    self.pos += 1;
+                    let method = self.ident();
+                    if self.peek() == Some(b'(') {
+                        self.pos += 1;
+                        let args = self.call_args();
+                        if method == "new" {
+                            if let RbValue::Const(name) = &value {
+                                value = RbValue::Call(name.clone(), args);
+                            }
+                        }
+                    }
+                }
+                Some(b'+') => {
+                    let save = self.pos;
+                    self.pos += 1;
+                    match (&value, self.primary()) {
+                        (RbValue::Str(a), RbValue::Str(b)) => value = RbValue::Str(a.clone() + &b),
+                        _ => {
+                            self.pos = save;
+                            break;
+                        }
+                    }
+                }
+                _ => break,
+            }
+        }
+        value
+    }
+
+    fn primary(&mut self) -> RbValue {
+        self.skip_space();
+        let Some(c) = self.peek() else {
+            return RbValue::Other;
+        };
+        match c {
+            b'\'' | b'"' => self.string().unwrap_or(RbValue::Other),
+            b'%' => self.percent_literal().unwrap_or(RbValue::Other),
+            b'<' if self.rest().starts_with(b"<<") => self.heredoc().unwrap_or(RbValue::Other),
+            b'[' => {
+                self.pos += 1;
+                RbValue::Array(self.sequence(b']', false))
+            }
+            b'{' => {
+                self.pos += 1;
+                match self.sequence(b'}', true).pop() {
+                    Some(hash @ RbValue::Hash(_)) => hash,
+                    _ => RbValue::Hash(Vec::new()),
+                }
+            }
+            b':' if self
+                .peek_at(1)
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == b'_') =>
+            {
+                self.pos += 1;
+                RbValue::Sym(self.ident())
+            }
+            b'-' | b'0'..=b'9' => self.number(),
+            b'(' => {
+                self.pos += 1;
+                self.call_args().into_iter().next().unwrap_or(RbValue::Nil)
+            }
+            c if c.is_ascii_uppercase() => {
+                let mut path = self.ident();
+                while self.rest().starts_with(b"::") {
+                    self.pos += 2;
+                    path.push_str("::");
+                    path.push_str(&self.ident());
+                }
+                if self.peek() == Some(b'(') {
+                    self.pos += 1;
+                    RbValue::Call(path, self.call_args())
+                } else {
+                    RbValue::Const(path)
+                }
+            }
+            c if c.is_ascii_lowercase() || c == b'_' => match self.ident().as_str() {
+                "true" => RbValue::Bool(true),
+                "false" => RbValue::Bool(false),
+                "nil" => RbValue::Nil,
+                name => {
+                    if self.peek() == Some(b'(') {
+                        self.pos += 1;
+                        RbValue::Call(name.to_string(), self.call_args())
+                    } else {
+                        RbValue::Other
+                    }
+                }
+            },
+            _ => RbValue::Other,
+        }
+    }
+
+    fn number(&mut self) -> RbValue {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_' || c == b'.')
+        {
+            if self.peek() == Some(b'.') && !self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+                break;
+            }
+            self.pos += 1;
+        }
+        let text = self.text(start, self.pos).replace('_', "");
+        if let Some(hex) = text.strip_prefix("0x") {
+            return i64::from_str_radix(hex, 16).map_or(RbValue::Other, RbValue::Int);
+        }
+        text.parse()
+            .map(RbValue::Int)
+            .or_else(|_| text.parse().map(RbValue::Float))
+            .unwrap_or(RbValue::Other)
+    }
+
+    fn string(&mut self) -> Option<RbValue> {
+        let quote = self.peek()?;
+        self.pos += 1;
+        let mut out = Vec::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                c if c == quote => {
+                    return Some(RbValue::Str(String::from_utf8_lossy(&out).to_string()))
+                }
+                b'\\' => {
+                    let escaped = self.peek()?;
+                    self.pos += 1;
+                    match (quote, escaped) {
+                        (b'"', b'n') => out.push(b'\n'),
+                        (b'"', b't') => out.push(b'\t'),
+                        (b'\'', c) if c != b'\'' && c != b'\\' => out.extend([b'\\', c]),
+                        (_, c) => out.push(c),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+        None
+    }
+
+    /// `%q(...)`, `%Q{...}`, `%(...)`, `%w[...]` and `%i[...]`.
+    fn percent_literal(&mut self) -> Option<RbValue> {
+        self.pos += 1;
+        let kind = match self.peek()? {
+            c if c.is_ascii_alphabetic() => {
+                self.pos += 1;
+                c
+            }
+            _ => b'Q',
+        };
+        let open = self.peek()?;
+        let close = match open {
+            b'(' => b')',
+            b'[' => b']',
+            b'{' => b'}',
+            b'<' => b'>',
+            c => c,
+        };
+        self.pos += 1;
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            if c == b'\\' {
+                self.pos += 2;
+                continue;
+            }
+            if c == open && open != close {
+                depth += 1;
+            } else if c == close {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            self.pos += 1;
+        }
+        if self.peek().is_none() {
+            // unterminated, e.g. a truncated file; a trailing `\` skipped
+            // past the end
+            self.pos = self.src.len();
+            return None;
+        }
+        let body = self.text(start, self.pos);
+        self.pos += 1;
+        Some(match kind {
+            b'w' | b'W' => RbValue::Array(
+                body.split_whitespace()
+                    .map(|w| RbValue::Str(w.to_string()))
+                    .collect(),
+            ),
+            b'i' | b'I' => RbValue::Array(
+                body.split_whitespace()
+                    .map(|w| RbValue::Sym(w.to_string()))
+                    .collect(),
+            ),
+            _ => RbValue::Str(body),
+        })
+    }
+
+    /// `<<~ID`, `<<-ID` and `<<ID`. The body starts on the next line; it is
+    /// read now and skipped when the lexer gets there.
+    fn heredoc(&mut self) -> Option<RbValue> {
+        self.pos += 2;
+        let squiggly = self.peek() == Some(b'~');
+        if matches!(self.peek(), Some(b'~' | b'-')) {
+            self.pos += 1;
+        }
+        let quoted = matches!(self.peek(), Some(b'\'' | b'"'));
+        if quoted {
+            self.pos += 1;
+        }
+        let id = self.ident();
+        if id.is_empty() {
+            return None;
+        }
+        if quoted {
+            self.pos += 1;
+        }
+        let newline = self
+            .rest()
+            .iter()
+            .position(|&c| c == b'\n')
+            .map(|i| self.pos + i)?;
+        let mut lines = Vec::new();
+        let mut at = newline + 1;
+        loop {
+            let end = self.src[at..]
+                .iter()
+                .position(|&c| c == b'\n')
+                .map_or(self.src.len(), |i| at + i);
+            let line = self.text(at, end);
+            at = (end + 1).min(self.src.len());
+            if line.trim() == id {
+                break;
+            }
+            lines.push(line);
+            if end == self.src.len() {
+                break;
+            }
+        }
+        if squiggly {
+            let indent = lines
+                .iter()
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| l.len() - l.trim_start().len())
+                .min()
+                .unwrap_or(0);
+            for line in &mut lines {
+                *line = line.get(indent..).unwrap_or("").to_string();
+            }
+        }
+        self.heredoc_skip.push((newline, at));
+        Some(RbValue::Str(lines.join("\n") + "\n"))
+    }
+}
+
+/// Arguments of every call to `method` in `src`, e.g. each
+/// `register_options([...])`.
+pub fn calls(src: &str, method: &str) -> Vec<Vec<RbValue>> {
+    let needle = format!("{}(", method);
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(i) = src[from..].find(&needle) {
+        let start = from + i;
+        from = start + needle.len();
+        // skip e.g. `deregister_options(` when looking for `register_options(`
+        let before = src[..start].chars().next_back();
+        if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            continue;
+        }
+        found.push(Parser::at(src, from).call_args());
+    }
+    found
+}
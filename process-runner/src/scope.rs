@@ -0,0 +1,373 @@
+//! Engagement scope: the networks, hostnames and ports a console may
+//! target. `MSFProcess::run_command` asks the guard before sending each
+//! command, so out-of-scope `set RHOSTS`, `set RPORT`, `run RHOSTS=...`,
+//! `run <target>` or `connect <host> <port>` never reach msfconsole.
+//! Commands whose targets cannot be checked, such as `db_nmap`, are refused.
+//! Every refusal is appended to an audit log.
+
+use ipnet::IpNet;
+use log::{error, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Options naming the hosts a module attacks.
+const HOST_OPTIONS: [&str; 2] = ["RHOSTS", "RHOST"];
+const PORT_OPTIONS: [&str; 1] = ["RPORT"];
+/// Commands that accept `NAME=value` options and targets for a single run.
+const RUN_COMMANDS: [&str; 5] = ["run", "exploit", "check", "rerun", "rexploit"];
+/// Run flags followed by a value that is not a target.
+const RUN_VALUE_FLAGS: [&str; 4] = ["-p", "-e", "-n", "-t"];
+/// `connect` flags followed by a value that is not the target.
+const CONNECT_VALUE_FLAGS: [&str; 6] = ["-P", "-S", "-c", "-i", "-p", "-w"];
+/// Commands that take targets the guard cannot read, like nmap target
+/// specifications. msfconsole runs unknown commands in a shell, so this
+/// includes the usual network tools. They are refused when given arguments.
+const UNCHECKED_COMMANDS: [&str; 12] = [
+    "db_nmap",
+    "nmap",
+    "ping",
+    "traceroute",
+    "nc",
+    "ncat",
+    "netcat",
+    "curl",
+    "wget",
+    "ssh",
+    "telnet",
+    "ftp",
+];
+
+/// The scope file, e.g.
+/// `{"networks": ["10.0.0.0/24"], "hostnames": ["*.lab.example"], "ports": ["1-1024", "8080"]}`.
+/// No ports means any port.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct ScopeFile {
+    networks: Vec<String>,
+    hostnames: Vec<String>,
+    ports: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Scope {
+    pub networks: Vec<IpNet>,
+    /// Exact names, or `*.example.com` for any subdomain.
+    pub hostnames: Vec<String>,
+    /// Inclusive ranges.
+    pub ports: Vec<(u16, u16)>,
+}
+
+impl Scope {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let file: ScopeFile = serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+        let networks = file
+            .networks
+            .iter()
+            .map(|n| {
+                n.parse::<IpNet>()
+                    .or_else(|_| n.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("Invalid network in scope: {:?}", n))
+            })
+            .collect::<Result<_, _>>()?;
+        let ports = file
+            .ports
+            .iter()
+            .map(|p| {
+                let (start, end) = p.split_once('-').unwrap_or((p, p));
+                match (start.trim().parse(), end.trim().parse()) {
+                    (Ok(start), Ok(end)) if start <= end => Ok((start, end)),
+                    _ => Err(format!("Invalid port range in scope: {:?}", p)),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            networks,
+            hostnames: file.hostnames.iter().map(|h| h.to_lowercase()).collect(),
+            ports,
+        })
+    }
+
+    fn contains_address(&self, address: IpAddr) -> bool {
+        self.networks.iter().any(|n| n.contains(&address))
+    }
+
+    fn contains_hostname(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('.').to_lowercase();
+        self.hostnames.iter().any(|h| match h.strip_prefix("*.") {
+            Some(domain) => name.ends_with(&format!(".{}", domain)),
+            None => *h == name,
+        })
+    }
+
+    pub fn contains_port(&self, port: u16) -> bool {
+        self.ports.is_empty()
+            || self
+                .ports
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&port))
+    }
+
+    /// Checks one RHOSTS entry: an address, CIDR block, address range or
+    /// hostname. Hostnames are matched by name, not resolved.
+    pub fn check_host(&self, spec: &str) -> Result<(), String> {
+        let refuse = || Err(format!("{} is out of scope", spec));
+        if spec.starts_with("file:") {
+            return Err(format!("{}: host files cannot be checked", spec));
+        }
+        if let Ok(net) = spec.parse::<IpNet>() {
+            if self.networks.iter().any(|n| n.contains(&net)) {
+                return Ok(());
+            }
+            return refuse();
+        }
+        if let Some((start, end)) = spec.split_once('-') {
+            if let Ok(start) = start.parse::<IpAddr>() {
+                let end = match (start, end.parse::<u8>()) {
+                    (IpAddr::V4(start), Ok(last)) => {
+                        let [a, b, c, _] = start.octets();
+                        IpAddr::from([a, b, c, last])
+                    }
+                    _ => end
+                        .parse::<IpAddr>()
+                        .map_err(|_| format!("{}: cannot read address range", spec))?,
+                };
+                // networks are contiguous, so both ends in one covers the range
+                if self
+                    .networks
+                    .iter()
+                    .any(|n| n.contains(&start) && n.contains(&end))
+                {
+                    return Ok(());
+                }
+                return refuse();
+            }
+        }
+        match spec.parse::<IpAddr>() {
+            Ok(address) if self.contains_address(address) => Ok(()),
+            Err(_) if self.contains_hostname(spec) => Ok(()),
+            _ => refuse(),
+        }
+    }
+
+    /// Checks a value of a target option, e.g. a whole RHOSTS list. URLs
+    /// in RHOSTS have their host and port checked.
+    pub fn check_option(&self, name: &str, value: &str) -> Result<(), String> {
+        let name = name.to_uppercase();
+        if HOST_OPTIONS.contains(&name.as_str()) {
+            for spec in value.split([' ', ',']).filter(|s| !s.is_empty()) {
+                match url_target(spec) {
+                    Some((host, port)) => {
+                        self.check_host(host)
+                            .map_err(|e| format!("{}: {}", name, e))?;
+                        if let Some(port) = port {
+                            self.check_option("RPORT", port)?;
+                        }
+                    }
+                    None => self
+                        .check_host(spec)
+                        .map_err(|e| format!("{}: {}", name, e))?,
+                }
+            }
+        } else if PORT_OPTIONS.contains(&name.as_str()) {
+            match value.parse::<u16>() {
+                Ok(port) if self.contains_port(port) => {}
+                _ => return Err(format!("{}: port {} is out of scope", name, value)),
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the arguments of a run command: `NAME=value` options, `-o`
+    /// option lists and positional targets, which msfconsole reads as RHOSTS.
+    fn check_run(&self, args: &str) -> Result<(), String> {
+        let mut args = args.split_whitespace();
+        while let Some(arg) = args.next() {
+            match arg {
+                "-o" => {
+                    for option in args.next().unwrap_or_default().split(',') {
+                        if let Some((name, value)) = option.split_once('=') {
+                            self.check_option(name, &unquote(value))?;
+                        }
+                    }
+                }
+                flag if RUN_VALUE_FLAGS.contains(&flag) => {
+                    args.next();
+                }
+                flag if flag.starts_with('-') => {}
+                // URLs may carry `=` in their query
+                target if url_target(target).is_some() => {
+                    self.check_option("RHOSTS", &unquote(target))?
+                }
+                option => match option.split_once('=') {
+                    Some((name, value)) => self.check_option(name, &unquote(value))?,
+                    None => self.check_option("RHOSTS", &unquote(option))?,
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks `connect [options] <host> <port>`.
+    fn check_connect(&self, args: &str) -> Result<(), String> {
+        let mut targets = Vec::new();
+        let mut args = args.split_whitespace();
+        while let Some(arg) = args.next() {
+            if CONNECT_VALUE_FLAGS.contains(&arg) {
+                args.next();
+            } else if !arg.starts_with('-') {
+                targets.push(unquote(arg));
+            }
+        }
+        match targets.as_slice() {
+            [host, port, ..] => {
+                self.check_option("RHOST", host)?;
+                self.check_option("RPORT", port)
+            }
+            [host] => self.check_option("RHOST", host),
+            [] => Ok(()),
+        }
+    }
+
+    /// Checks every target option a console line sets, including each
+    /// `;`-separated command. Lines with line breaks are refused, since
+    /// msfconsole would run each line as its own command.
+    pub fn check_command(&self, line: &str) -> Result<(), String> {
+        if line.contains(['\n', '\r']) {
+            return Err(format!("{:?} contains a line break", line));
+        }
+        for command in line.split(';') {
+            let command = command.trim();
+            let (verb, rest) = command
+                .split_once(char::is_whitespace)
+                .unwrap_or((command, ""));
+            match verb.to_lowercase().as_str() {
+                "set" | "setg" => {
+                    let rest = rest.trim();
+                    let (name, value) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+                    self.check_option(name, &unquote(value.trim()))?;
+                }
+                "connect" => self.check_connect(rest)?,
+                verb if RUN_COMMANDS.contains(&verb) => self.check_run(rest)?,
+                verb if UNCHECKED_COMMANDS.contains(&verb) && !rest.trim().is_empty() => {
+                    return Err(format!(
+                        "{}: targets cannot be checked against the scope",
+                        verb
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The host and port of a URL target such as `http://10.0.0.5:8080/path`.
+fn url_target(spec: &str) -> Option<(&str, Option<&str>)> {
+    let (_, rest) = spec.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+    if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, port) = bracketed.split_once(']')?;
+        return Some((host, port.strip_prefix(':')));
+    }
+    Some(match authority.split_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    })
+}
+
+/// Undoes the quoting `command::quote` applies.
+fn unquote(value: &str) -> String {
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value);
+    value.replace("\\\"", "\"").replace("\\\\", "\\")
+}
+
+/// A scope plus the audit log its refusals go to.
+#[derive(Debug)]
+pub struct ScopeGuard {
+    scope: Scope,
+    audit_path: PathBuf,
+    audit: Mutex<Option<File>>,
+}
+
+impl ScopeGuard {
+    pub fn new(scope: Scope, audit_path: impl Into<PathBuf>) -> Self {
+        Self {
+            scope,
+            audit_path: audit_path.into(),
+            audit: Mutex::new(None),
+        }
+    }
+
+    /// The guard configured by `PROCESS_RUNNER_SCOPE` (the scope file) and
+    /// `PROCESS_RUNNER_SCOPE_AUDIT` (default `scope_audit.jsonl`), if any.
+    /// A scope file that cannot be read gives an empty scope, so every
+    /// target is refused rather than allowed.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let path = std::env::var("PROCESS_RUNNER_SCOPE").ok()?;
+        let audit = std::env::var("PROCESS_RUNNER_SCOPE_AUDIT")
+            .unwrap_or_else(|_| "scope_audit.jsonl".to_string());
+        let scope = Scope::load(Path::new(&path)).unwrap_or_else(|e| {
+            error!("{}; refusing all targets", e);
+            Scope::default()
+        });
+        Some(Arc::new(Self::new(scope, audit)))
+    }
+
+    pub fn scope(&self) -> &Scope {
+        &self.scope
+    }
+
+    /// Checks `command`, recording it in the audit log if it is refused.
+    pub fn check(&self, command: &str) -> Result<(), String> {
+        let result = self.scope.check_command(command);
+        if let Err(reason) = &result {
+            self.record(command, reason);
+        }
+        result
+    }
+
+    /// Records a command refused on the guard's behalf in the audit log.
+    pub fn record(&self, command: &str, reason: &str) {
+        warn!("Refused {:?}: {}", command, reason);
+        self.audit(command, reason);
+    }
+
+    fn audit(&self, command: &str, reason: &str) {
+        let record = json!({
+            "time": chrono::Utc::now(),
+            "command": command,
+            "reason": reason,
+        });
+        let mut audit = self.audit.lock().unwrap();
+        if audit.is_none() {
+            match OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.audit_path)
+            {
+                Ok(file) => *audit = Some(file),
+                Err(e) => {
+                    error!("Failed to open {}: {}", self.audit_path.display(), e);
+                    return;
+                }
+            }
+        }
+        if let Some(file) = audit.as_mut() {
+            if let Err(e) = writeln!(file, "{}", record) {
+                error!("Failed to write {}: {}", self.audit_path.display(), e);
+            }
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! `serve` mode: the REST + WebSocket API that server.js and
+//! msfProcessManager.js used to provide, with one msfconsole per `source`.
+//!
+//! REST (default port 8081) keeps the old routes, and their status codes
+//! for missing sources and consoles. Breaking changes for existing clients:
+//! - `/radial-command` waits for its commands and answers 403 with the
+//!   reason when the console's policy or scope guard refuses one.
+//! - `/radial-options-data` and `/radial-modules-data` answer with the data
+//!   as JSON once it is scraped, instead of "Processing exploits..." or
+//!   "Success" followed by the data on the WebSocket. Nothing is inserted
+//!   into Parse; clients that need it stored must do so themselves.
+//!
+//! Console output goes to every WebSocket (default port 8082) connected
+//! with the same `?source=`, one JSON message per command instead of raw
+//! pty chunks:
+//! `{"type":"output","source","command","output","spans","events","result"}`.
+//! Status notes (e.g. inactivity shutdowns) are sent as
+//! `{"type":"status","source","message"}`.
+
+use log::{error, info, warn};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response};
+use tungstenite::handshake::server::{Request as WsRequest, Response as WsResponse};
+use tungstenite::Message;
+
+use crate::ansi;
+use crate::command::CommandBuilder;
+use crate::console::Console;
+use crate::events;
+use crate::lookup::OptionsLookup;
+use crate::msf::CommandOutput;
+use crate::policy::{Policy, Profile};
+use crate::pool::ConsolePool;
+use crate::spawn::ProcessBuilder;
+use crate::tree::{self, TreeOptions};
+use crate::validate;
+
+pub struct ServerConfig {
+    pub http_addr: String,
+    pub ws_addr: String,
+    /// Consoles with no commands for this long are stopped.
+    pub idle_timeout: Duration,
+    /// Generated options catalog used by `/module-options`.
+    pub catalog: PathBuf,
+    /// Older catalogs are ignored in favour of live lookups.
+    pub catalog_max_age: Duration,
+    /// Consoles kept for live lookups, separate from the per-source ones.
+    pub pool_size: usize,
+    /// Commands allowed on the per-source consoles; `None` allows all. The
+    /// API has no authentication, so the default is the `PROCESS_RUNNER_ROLE`
+    /// policy, or `read-only` without one.
+    pub policy: Option<Arc<Policy>>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            http_addr: "0.0.0.0:8081".to_string(),
+            ws_addr: "0.0.0.0:8082".to_string(),
+            idle_timeout: Duration::from_secs(10 * 60),
+            catalog: PathBuf::from("exploits_options.json"),
+            catalog_max_age: Duration::from_secs(24 * 60 * 60),
+            pool_size: 1,
+            policy: Policy::from_env()
+                .or_else(|| Some(Arc::new(Policy::new("read-only", Profile::read_only())))),
+        }
+    }
+}
+
+struct Client {
+    id: u64,
+    tx: Sender<String>,
+}
+
+type BuilderFn = Arc<dyn Fn() -> ProcessBuilder + Send + Sync>;
+
+struct ServerState {
+    builder: BuilderFn,
+    sessions: Mutex<HashMap<String, Arc<Console>>>,
+    clients: Mutex<HashMap<String, Vec<Client>>>,
+    next_client: AtomicU64,
+    lookup: OptionsLookup,
+    policy: Option<Arc<Policy>>,
+}
+
+impl ServerState {
+    fn new(config: &ServerConfig, builder: BuilderFn) -> Self {
+        let pool_builder = Arc::clone(&builder);
+        let pool = Arc::new(ConsolePool::new("pool", config.pool_size, move || {
+            pool_builder()
+        }));
+        Self {
+            builder,
+            sessions: Mutex::new(HashMap::new()),
+            clients: Mutex::new(HashMap::new()),
+            next_client: AtomicU64::new(0),
+            lookup: OptionsLookup::new(&config.catalog, config.catalog_max_age, pool),
+            policy: config.policy.clone(),
+        }
+    }
+
+    fn broadcast(&self, source: &str, message: String) {
+        let mut clients = self.clients.lock().unwrap();
+        match clients.get_mut(source) {
+            Some(list) => list.retain(|client| client.tx.send(message.clone()).is_ok()),
+            None => info!("Client with source {} not found", source),
+        }
+    }
+
+    /// Refuses commands the policy does not allow, before they are queued.
+    fn check_policy<'a>(
+        &self,
+        commands: impl IntoIterator<Item = &'a String>,
+    ) -> Result<(), HttpResponse> {
+        let Some(policy) = &self.policy else {
+            return Ok(());
+        };
+        for command in commands {
+            policy.check(command).map_err(|e| text(403, e))?;
+        }
+        Ok(())
+    }
+
+    fn session(&self, source: &str) -> Option<Arc<Console>> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .get(source)
+            .filter(|console| console.is_running())
+            .cloned()
+    }
+}
+
+#[derive(Deserialize, Default)]
+struct Body {
+    source: Option<String>,
+    command: Option<String>,
+    /// Module name for `/module-options` and `/validate-options`.
+    name: Option<String>,
+    /// Option values for `/validate-options`.
+    options: Option<HashMap<String, String>>,
+    #[serde(rename = "radialCommandData")]
+    radial_command_data: Option<RadialCommandData>,
+    /// Tree depth for `/radial-modules-data`.
+    depth: Option<usize>,
+    /// Merges single-child groups in the `/radial-modules-data` tree.
+    collapse: Option<bool>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct RadialCommandData {
+    selected_exploit: Option<String>,
+    selected_payload: Option<String>,
+    selected_auxiliary: Option<String>,
+    destination_ip_addr: Option<String>,
+    source_ip_addr: Option<String>,
+}
+
+impl RadialCommandData {
+    /// The radial menu sends "<index> <module path>"; keep the path.
+    fn path(selection: &Option<String>) -> Option<String> {
+        selection
+            .as_ref()
+            .and_then(|s| s.split(' ').nth(1))
+            .map(str::to_string)
+    }
+
+    fn builder(&self) -> Result<CommandBuilder, String> {
+        let exploit = Self::path(&self.selected_exploit);
+        let auxiliary = Self::path(&self.selected_auxiliary);
+        let mut builder = match exploit.or(auxiliary) {
+            Some(module) => CommandBuilder::new(&module),
+            None => return Err("No exploit or auxiliary module selected".to_string()),
+        };
+        if let Some(payload) = Self::path(&self.selected_payload) {
+            builder = builder.payload(&payload);
+        }
+        if let Some(rhost) = &self.destination_ip_addr {
+            builder = builder.option("RHOST", rhost);
+        }
+        if let Some(lhost) = &self.source_ip_addr {
+            builder = builder.option("LHOST", lhost);
+        }
+        Ok(builder)
+    }
+}
+
+type HttpResponse = Response<io::Cursor<Vec<u8>>>;
+
+fn text(status: u16, body: impl Into<String>) -> HttpResponse {
+    Response::from_string(body.into()).with_status_code(status)
+}
+
+fn json_response(status: u16, body: &impl serde::Serialize) -> HttpResponse {
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    match serde_json::to_string(body) {
+        Ok(body) => Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header),
+        Err(e) => text(500, e.to_string()),
+    }
+}
+
+/// Runs the REST and WebSocket servers until the process is killed.
+/// `builder` makes the command each console runs: msfconsole normally, or a
+/// replay console in tests.
+pub fn serve(
+    config: ServerConfig,
+    builder: impl Fn() -> ProcessBuilder + Send + Sync + 'static,
+) -> io::Result<()> {
+    let state = Arc::new(ServerState::new(&config, Arc::new(builder)));
+
+    let listener = TcpListener::bind(&config.ws_addr)?;
+    info!("WebSocket server listening on {}", config.ws_addr);
+    let ws_state = Arc::clone(&state);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let state = Arc::clone(&ws_state);
+                    thread::spawn(move || handle_websocket(&state, stream));
+                }
+                Err(e) => error!("WebSocket connection failed: {}", e),
+            }
+        }
+    });
+
+    let reaper_state = Arc::clone(&state);
+    let idle_timeout = config.idle_timeout;
+    thread::spawn(move || reap_idle(&reaper_state, idle_timeout));
+
+    let server = tiny_http::Server::http(&config.http_addr)
+        .map_err(|e| io::Error::new(ErrorKind::AddrNotAvailable, e))?;
+    info!("Server running on {}", config.http_addr);
+    for request in server.incoming_requests() {
+        let state = Arc::clone(&state);
+        thread::spawn(move || handle_request(&state, request));
+    }
+    Ok(())
+}
+
+fn handle_request(state: &Arc<ServerState>, mut request: Request) {
+    let method = request.method().clone();
+    let url = request.url().to_string();
+    let body: Body = if method == Method::Post {
+        match serde_json::from_reader(request.as_reader()) {
+            Ok(body) => body,
+            Err(e) => {
+                let _ = request.respond(text(400, format!("Invalid JSON body: {}", e)));
+                return;
+            }
+        }
+    } else {
+        Body::default()
+    };
+
+    let response = match (&method, url.as_str()) {
+        (Method::Post, "/start") => start(state, body),
+        (Method::Post, "/command") => command(state, body),
+        (Method::Post, "/stop") => stop(state, body),
+        (Method::Post, "/radial-command") => radial_command(state, body),
+        (Method::Post, "/radial-options-data") => radial_options_data(state, body),
+        (Method::Post, "/radial-modules-data") => radial_modules_data(state, body),
+        (Method::Post, "/module-options") => module_options(state, body),
+        (Method::Post, "/validate-options") => validate_options(state, body),
+        (Method::Get, "/processes") => processes(state),
+        _ => text(404, format!("Cannot {} {}", method, url)),
+    };
+    if let Err(e) = request.respond(response) {
+        warn!("Failed to respond to {} {}: {}", method, url, e);
+    }
+}
+
+fn start(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let source = body.source.unwrap_or_default();
+    if body.command.as_deref() != Some("msfconsole") {
+        return text(400, "Please enter a correct command.");
+    }
+    let mut sessions = state.sessions.lock().unwrap();
+    if sessions.get(&source).is_some_and(|c| c.is_running()) {
+        return text(500, format!("{}: Metasploit is already running", source));
+    }
+
+    let hook_state = Arc::clone(state);
+    let hook_source = source.clone();
+    let hook = Box::new(move |out: &CommandOutput| {
+        hook_state.broadcast(&hook_source, output_message(&hook_source, out));
+    });
+    let mut builder = (state.builder)().keep_ansi(true);
+    if let Some(policy) = &state.policy {
+        builder = builder.policy(Arc::clone(policy));
+    }
+    sessions.insert(source.clone(), Console::spawn(&source, builder, Some(hook)));
+    text(200, format!("{}: Metasploit process starting...", source))
+}
+
+fn output_message(source: &str, out: &CommandOutput) -> String {
+    let output = out.output.join("\n");
+    json!({
+        "type": "output",
+        "source": source,
+        "command": out.command,
+        "spans": ansi::to_spans(&out.raw_output.join("\n")),
+        "events": events::parse_command_output(out.output),
+        "output": output,
+        "result": out.result,
+    })
+    .to_string()
+}
+
+fn command(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let (source, command) = match (body.source, body.command) {
+        (Some(source), Some(command)) => (source, command),
+        _ => return text(400, "No Source or Command"),
+    };
+    if command.contains(['\n', '\r']) {
+        return text(400, format!("Command contains a line break: {:?}", command));
+    }
+    if let Err(response) = state.check_policy([&command]) {
+        return response;
+    }
+    let console = match state.session(&source) {
+        Some(console) => console,
+        None => return text(400, format!("{}: Metasploit is not running", source)),
+    };
+    match console.submit(move |msf| {
+        msf.run_command(&command);
+    }) {
+        Ok(()) => text(200, "Success"),
+        Err(e) => text(500, e),
+    }
+}
+
+fn stop(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let source = body.source.unwrap_or_default();
+    let console = state.sessions.lock().unwrap().remove(&source);
+    match console {
+        Some(console) if console.is_running() => {
+            console.stop();
+            text(200, format!("{}: Metasploit stopped", source))
+        }
+        _ => text(400, format!("{}: Metasploit is not running", source)),
+    }
+}
+
+fn radial_command(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let (source, data) = match (body.source, body.radial_command_data) {
+        (Some(source), Some(data)) => (source, data),
+        _ => return text(400, "No Source or Command"),
+    };
+    let builder = match data.builder() {
+        Ok(builder) => builder,
+        Err(e) => return text(400, e),
+    };
+    // modules missing from a fresh catalog go through unchecked
+    if let Some(Ok(validation)) = state.lookup.catalog().map(|c| builder.validate(&c)) {
+        if !validation.valid {
+            return json_response(400, &validation);
+        }
+    }
+    let commands = match builder.commands() {
+        Ok(commands) => commands,
+        Err(e) => return text(400, e),
+    };
+    if let Err(response) = state.check_policy(&commands) {
+        return response;
+    }
+    let console = match state.session(&source) {
+        Some(console) => console,
+        None => return text(404, "Process not found for the source"),
+    };
+    // waits for the commands, so a refusal from the console's own policy or
+    // scope guard can be reported; the rest are not sent after one
+    let refused = console.call(move |msf| {
+        commands
+            .iter()
+            .find_map(|command| msf.run_command(command).refused)
+    });
+    match refused {
+        Ok(None) => text(200, "Success"),
+        Ok(Some(reason)) => text(403, reason),
+        Err(e) => text(500, e),
+    }
+}
+
+/// Options of the first exploit, as the radial menu's option form expects.
+/// server.js answered "Processing exploits..." and sent the data over the
+/// WebSocket and into Parse; this answers with the data itself.
+fn radial_options_data(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let source = match body.source {
+        Some(source) => source,
+        None => return text(400, "No Source provided"),
+    };
+    let console = match state.session(&source) {
+        Some(console) => console,
+        None => return text(404, "Process not found for the source"),
+    };
+    let result = console.call(|msf| {
+        let mut exploit = msf
+            .get_exploits()
+            .into_iter()
+            .next()
+            .ok_or("No exploits found")?;
+        msf.add_options(&mut exploit, None)?;
+        Ok::<_, String>(exploit)
+    });
+    match result.and_then(|r| r) {
+        Ok(exploit) => json_response(200, &exploit),
+        Err(e) => text(500, e),
+    }
+}
+
+/// Exploit, payload and auxiliary rows, and the tree grouping them for the
+/// radial menu. Like `/radial-options-data`, this answers with the data
+/// rather than "Success", and does not send it to Parse.
+fn radial_modules_data(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let source = match body.source {
+        Some(source) => source,
+        None => return text(400, "No Source or Command"),
+    };
+    let console = match state.session(&source) {
+        Some(console) => console,
+        None => return text(404, "Process not found for the source"),
+    };
+    let options = TreeOptions {
+        max_depth: body.depth,
+        collapse: body.collapse.unwrap_or(false),
+    };
+    let result = console.call(move |msf| {
+        let exploits = msf.get_exploits_details();
+        let payloads = msf.get_payload_details();
+        let auxiliary = msf.get_auxiliary_details();
+        let modules = exploits.iter().chain(&payloads).chain(&auxiliary);
+        json!({
+            "tree": tree::build(modules, &options),
+            "exploits": exploits,
+            "payloads": payloads,
+            "auxiliary": auxiliary,
+        })
+    });
+    match result {
+        Ok(modules) => json_response(200, &modules),
+        Err(e) => text(500, e),
+    }
+}
+
+/// Options, payload options and targets for any module, from the catalog
+/// when it is fresh and a pooled console otherwise.
+fn module_options(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let name = match body.name {
+        Some(name) => name,
+        None => return text(400, "No module name provided"),
+    };
+    match state.lookup.lookup(&name) {
+        Ok(options) => json_response(200, &options),
+        Err(e) => text(404, e),
+    }
+}
+
+/// Checks option values for a module against the catalog.
+fn validate_options(state: &Arc<ServerState>, body: Body) -> HttpResponse {
+    let name = match body.name {
+        Some(name) => name,
+        None => return text(400, "No module name provided"),
+    };
+    let catalog = match state.lookup.load_catalog() {
+        Some(catalog) => catalog,
+        None => return text(503, "No catalog available"),
+    };
+    match validate::validate_module(&catalog, &name, &body.options.unwrap_or_default()) {
+        Ok(validation) => json_response(200, &validation),
+        Err(e) => text(404, e),
+    }
+}
+
+fn processes(state: &Arc<ServerState>) -> HttpResponse {
+    let sessions = state.sessions.lock().unwrap();
+    let mut running: Vec<&String> = sessions
+        .iter()
+        .filter(|(_, console)| console.is_running())
+        .map(|(source, _)| source)
+        .collect();
+    running.sort();
+    json_response(
+        200,
+        &json!({
+            "Number of processes running": running.len(),
+            "Active Running Processes": running,
+        }),
+    )
+}
+
+fn reap_idle(state: &Arc<ServerState>, idle_timeout: Duration) {
+    let interval = idle_timeout
+        .min(Duration::from_secs(30))
+        .max(Duration::from_secs(1));
+    loop {
+        thread::sleep(interval);
+        let idle: Vec<(String, Arc<Console>)> = {
+            let mut sessions = state.sessions.lock().unwrap();
+            let idle: Vec<String> = sessions
+                .iter()
+                .filter(|(_, c)| !c.is_running() || c.idle_for() >= idle_timeout)
+                .map(|(source, _)| source.clone())
+                .collect();
+            idle.into_iter()
+                .filter_map(|source| sessions.remove_entry(&source))
+                .collect()
+        };
+        for (source, console) in idle {
+            if console.is_running() {
+                let message = format!("{}: Stopping Metasploit due to inactivity", source);
+                info!("{}", message);
+                console.stop();
+                let status = json!({"type": "status", "source": source, "message": message});
+                state.broadcast(&source, status.to_string());
+            }
+        }
+    }
+}
+
+fn handle_websocket(state: &Arc<ServerState>, stream: TcpStream) {
+    let mut source = "Unknown".to_string();
+    // the error type is fixed by tungstenite's handshake callback
+    #[allow(clippy::result_large_err)]
+    let callback = |req: &WsRequest, res: WsResponse| {
+        if let Some(query) = req.uri().query() {
+            for (key, value) in url_query(query) {
+                if key == "source" && !value.is_empty() {
+                    source = value;
+                }
+            }
+        }
+        Ok(res)
+    };
+    let mut socket = match tungstenite::accept_hdr(stream, callback) {
+        Ok(socket) => socket,
+        Err(e) => {
+            warn!("WebSocket handshake failed: {}", e);
+            return;
+        }
+    };
+    info!("Client connected to WebSocket from source: {}", source);
+
+    let (tx, rx) = mpsc::channel();
+    let id = state.next_client.fetch_add(1, Ordering::Relaxed);
+    state
+        .clients
+        .lock()
+        .unwrap()
+        .entry(source.clone())
+        .or_default()
+        .push(Client { id, tx });
+
+    // short read timeout so queued output goes out promptly
+    let _ = socket
+        .get_mut()
+        .set_read_timeout(Some(Duration::from_millis(100)));
+    'conn: loop {
+        match socket.read() {
+            Ok(Message::Close(_)) => break,
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => break,
+        }
+        while let Ok(message) = rx.try_recv() {
+            if socket.send(Message::text(message)).is_err() {
+                break 'conn;
+            }
+        }
+    }
+
+    if let Some(list) = state.clients.lock().unwrap().get_mut(&source) {
+        list.retain(|client| client.id != id);
+    }
+    info!("Client disconnected from source: {}", source);
+}
+
+/// Splits a query string into decoded key/value pairs.
+fn url_query(query: &str) -> Vec<(String, String)> {
+    query
+        .split('&')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((percent_decode(key)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = input.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
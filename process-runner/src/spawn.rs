@@ -0,0 +1,337 @@
+use chrono::Utc;
+use log::warn;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+use crate::buffer::{BufferLimits, OverflowPolicy, StreamBuffer};
+use crate::policy::Policy;
+use crate::process::Process;
+use crate::scope::ScopeGuard;
+use crate::transcript::{Stream, Transcript};
+
+/// rlimits applied to the child between fork and exec. `None` leaves the
+/// limit inherited from the runner.
+#[derive(Clone, Debug, Default)]
+pub struct ResourceLimits {
+    /// Address space in bytes (`RLIMIT_AS`).
+    pub memory_bytes: Option<u64>,
+    /// CPU time in seconds (`RLIMIT_CPU`).
+    pub cpu_seconds: Option<u64>,
+    /// Open file descriptors (`RLIMIT_NOFILE`).
+    pub open_files: Option<u64>,
+}
+
+/// Builds a `Process` with its own environment, working directory, rlimits
+/// and nice level.
+#[derive(Clone, Debug)]
+pub struct ProcessBuilder {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+    env_remove: Vec<String>,
+    env_clear: bool,
+    current_dir: Option<PathBuf>,
+    rlimits: ResourceLimits,
+    nice: Option<i32>,
+    buffer_limits: BufferLimits,
+    transcript_dir: Option<PathBuf>,
+    keep_ansi: bool,
+    scope_guard: Option<Arc<ScopeGuard>>,
+    policy: Option<Arc<Policy>>,
+}
+
+impl ProcessBuilder {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            env_remove: Vec::new(),
+            env_clear: false,
+            current_dir: None,
+            rlimits: ResourceLimits::default(),
+            nice: None,
+            buffer_limits: BufferLimits::default(),
+            transcript_dir: None,
+            keep_ansi: false,
+            scope_guard: None,
+            policy: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: &str) -> Self {
+        self.args.push(arg.to_string());
+        self
+    }
+
+    pub fn args(mut self, args: &[&str]) -> Self {
+        self.args.extend(args.iter().map(|a| a.to_string()));
+        self
+    }
+
+    /// Sets `key` in the child's environment, e.g. `MSF_DATABASE_CONFIG`,
+    /// `HOME` or `COLUMNS`.
+    pub fn env(mut self, key: &str, value: &str) -> Self {
+        self.env_remove.retain(|k| k != key);
+        self.env.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn env_remove(mut self, key: &str) -> Self {
+        self.env.remove(key);
+        self.env_remove.push(key.to_string());
+        self
+    }
+
+    /// Starts the child with an empty environment plus whatever is set
+    /// through `env`.
+    pub fn env_clear(mut self) -> Self {
+        self.env_clear = true;
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    pub fn memory_limit(mut self, bytes: u64) -> Self {
+        self.rlimits.memory_bytes = Some(bytes);
+        self
+    }
+
+    pub fn cpu_time_limit(mut self, seconds: u64) -> Self {
+        self.rlimits.cpu_seconds = Some(seconds);
+        self
+    }
+
+    pub fn open_files_limit(mut self, files: u64) -> Self {
+        self.rlimits.open_files = Some(files);
+        self
+    }
+
+    /// Nice level for the child, from -20 (highest priority) to 19.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    pub fn buffer_limits(mut self, limits: BufferLimits) -> Self {
+        self.buffer_limits = limits;
+        self
+    }
+
+    pub fn get_buffer_limits(&self) -> &BufferLimits {
+        &self.buffer_limits
+    }
+
+    /// Records every spawned process to its own JSON Lines transcript in
+    /// `dir`, named after the command, start time and pid.
+    pub fn transcript_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.transcript_dir = Some(dir.into());
+        self
+    }
+
+    /// Also keeps stdout with its ANSI colour codes, readable through
+    /// `Process::take_raw`. `read` still returns stripped text.
+    pub fn keep_ansi(mut self, keep: bool) -> Self {
+        self.keep_ansi = keep;
+        self
+    }
+
+    /// Checks every command an `MSFProcess` on this process sends against
+    /// an engagement scope.
+    pub fn scope_guard(mut self, guard: Arc<ScopeGuard>) -> Self {
+        self.scope_guard = Some(guard);
+        self
+    }
+
+    pub fn get_scope_guard(&self) -> Option<&Arc<ScopeGuard>> {
+        self.scope_guard.as_ref()
+    }
+
+    /// Refuses commands an `MSFProcess` on this process sends unless
+    /// `policy` allows them. Set on consoles that run caller commands, not
+    /// on those used for internal lookups.
+    pub fn policy(mut self, policy: Arc<Policy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    pub fn get_policy(&self) -> Option<&Arc<Policy>> {
+        self.policy.as_ref()
+    }
+
+    /// Applies the `PROCESS_RUNNER_*` variables of the runner itself:
+    /// `CWD`, `NICE`, `MEMORY_LIMIT` (bytes), `CPU_SECONDS`, `OPEN_FILES`,
+    /// `TRANSCRIPT_DIR`, plus the buffer settings read by `BufferLimits::with_env`
+    /// and the scope read by `ScopeGuard::from_env`. Anything whose variable
+    /// is not set keeps what the builder already has.
+    pub fn from_env(mut self) -> Self {
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            let value = std::env::var(name).ok()?;
+            match value.parse() {
+                Ok(v) => Some(v),
+                Err(_) => {
+                    warn!("Ignoring {}={}: not a number", name, value);
+                    None
+                }
+            }
+        }
+        if let Ok(dir) = std::env::var("PROCESS_RUNNER_CWD") {
+            self.current_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(nice) = var("PROCESS_RUNNER_NICE") {
+            self.nice = Some(nice);
+        }
+        if let Some(bytes) = var("PROCESS_RUNNER_MEMORY_LIMIT") {
+            self.rlimits.memory_bytes = Some(bytes);
+        }
+        if let Some(seconds) = var("PROCESS_RUNNER_CPU_SECONDS") {
+            self.rlimits.cpu_seconds = Some(seconds);
+        }
+        if let Some(files) = var("PROCESS_RUNNER_OPEN_FILES") {
+            self.rlimits.open_files = Some(files);
+        }
+        if let Ok(dir) = std::env::var("PROCESS_RUNNER_TRANSCRIPT_DIR") {
+            self.transcript_dir = Some(PathBuf::from(dir));
+        }
+        self.buffer_limits = self.buffer_limits.with_env();
+        if let Some(guard) = ScopeGuard::from_env() {
+            self.scope_guard = Some(guard);
+        }
+        self
+    }
+
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.command);
+        cmd.args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if self.env_clear {
+            cmd.env_clear();
+        }
+        for key in &self.env_remove {
+            cmd.env_remove(key);
+        }
+        cmd.envs(&self.env);
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
+        self.apply_limits(&mut cmd);
+        cmd
+    }
+
+    #[cfg(unix)]
+    fn apply_limits(&self, cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        let rlimits = self.rlimits.clone();
+        let nice = self.nice;
+        if rlimits.memory_bytes.is_none()
+            && rlimits.cpu_seconds.is_none()
+            && rlimits.open_files.is_none()
+            && nice.is_none()
+        {
+            return;
+        }
+        // Only async-signal-safe calls are allowed between fork and exec.
+        unsafe {
+            cmd.pre_exec(move || {
+                set_rlimit(libc::RLIMIT_AS, rlimits.memory_bytes)?;
+                set_rlimit(libc::RLIMIT_CPU, rlimits.cpu_seconds)?;
+                set_rlimit(libc::RLIMIT_NOFILE, rlimits.open_files)?;
+                if let Some(nice) = nice {
+                    if libc::setpriority(libc::PRIO_PROCESS, 0, nice) != 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn apply_limits(&self, _cmd: &mut Command) {
+        if self.nice.is_some()
+            || self.rlimits.memory_bytes.is_some()
+            || self.rlimits.cpu_seconds.is_some()
+            || self.rlimits.open_files.is_some()
+        {
+            warn!("Resource limits and nice are only supported on unix");
+        }
+    }
+
+    pub fn spawn(&self) -> io::Result<Process> {
+        let mut process = self.command().spawn()?;
+        let stdin = process.stdin.take().expect("Failed to get stdin");
+        let transcript = match &self.transcript_dir {
+            Some(dir) => {
+                let name = Path::new(&self.command)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| self.command.clone());
+                let path = dir.join(format!(
+                    "{}-{}-{}.jsonl",
+                    name,
+                    Utc::now().format("%Y%m%dT%H%M%SZ"),
+                    process.id()
+                ));
+                let transcript = Transcript::create(path)?;
+                let mut command_line = vec![self.command.clone()];
+                command_line.extend(self.args.iter().cloned());
+                transcript.record(
+                    Stream::Meta,
+                    &format!("started {} (pid {})", command_line.join(" "), process.id()),
+                );
+                Some(Arc::new(transcript))
+            }
+            None => None,
+        };
+
+        // Nobody may be draining the raw copy, so it must never block the
+        // reader that also feeds the stripped buffer.
+        let raw_buf = if self.keep_ansi {
+            let mut limits = self.buffer_limits.clone();
+            if let OverflowPolicy::Block = limits.policy {
+                limits.policy = OverflowPolicy::DropOldest;
+            }
+            Some(Arc::new(StreamBuffer::new("raw", limits)))
+        } else {
+            None
+        };
+
+        Ok(Process {
+            process,
+            stdin,
+            output_buf: Arc::new(StreamBuffer::new("stdout", self.buffer_limits.clone())),
+            err_buf: Arc::new(StreamBuffer::new("stderr", self.buffer_limits.clone())),
+            transcript,
+            raw_buf,
+        })
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+#[cfg(unix)]
+fn set_rlimit(resource: Resource, value: Option<u64>) -> io::Result<()> {
+    let Some(value) = value else {
+        return Ok(());
+    };
+    let limit = libc::rlimit {
+        rlim_cur: value as libc::rlim_t,
+        rlim_max: value as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
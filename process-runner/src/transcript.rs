@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    /// Notes from the runner itself, e.g. when the console was started.
+    Meta,
+    Stdin,
+    Stdout,
+    Stderr,
+}
+
+/// One line of a transcript file.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TranscriptRecord {
+    pub seq: u64,
+    /// Microseconds since the transcript was opened.
+    pub mono_us: u64,
+    pub wall: DateTime<Utc>,
+    pub stream: Stream,
+    pub text: String,
+}
+
+struct TranscriptState {
+    out: BufWriter<File>,
+    seq: u64,
+}
+
+/// Append-only JSON Lines log of everything sent to and received from one
+/// console. Shared between the writer and both reader threads.
+pub struct Transcript {
+    path: PathBuf,
+    start: Instant,
+    state: Mutex<TranscriptState>,
+}
+
+impl Transcript {
+    pub fn create(path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        let file = File::create(&path)?;
+        Ok(Self {
+            path,
+            start: Instant::now(),
+            state: Mutex::new(TranscriptState {
+                out: BufWriter::new(file),
+                seq: 0,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes and flushes one record. Failures are logged rather than
+    /// returned so a full disk can't take the console down with it.
+    pub fn record(&self, stream: Stream, text: &str) {
+        let mut state = self.state.lock().unwrap();
+        let record = TranscriptRecord {
+            seq: state.seq,
+            mono_us: self.start.elapsed().as_micros() as u64,
+            wall: Utc::now(),
+            stream,
+            text: text.to_string(),
+        };
+        state.seq += 1;
+        let res = serde_json::to_writer(&mut state.out, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|_| writeln!(state.out))
+            .and_then(|_| state.out.flush());
+        if let Err(e) = res {
+            error!("Failed to write transcript {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Reads a transcript back, ordered by sequence number.
+pub fn read_transcript(path: &Path) -> std::io::Result<Vec<TranscriptRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TranscriptRecord = serde_json::from_str(&line).map_err(|e| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{}:{}: {}", path.display(), i + 1, e),
+            )
+        })?;
+        records.push(record);
+    }
+    records.sort_by_key(|r| r.seq);
+    Ok(records)
+}
+
+/// Renders records as a console log: relative (or wall-clock) time, a marker
+/// for the stream, then the text. Commands are prefixed with `>`.
+pub fn render<W: Write>(
+    records: &[TranscriptRecord],
+    out: &mut W,
+    wall: bool,
+) -> std::io::Result<()> {
+    for record in records {
+        let time = if wall {
+            record.wall.format("%Y-%m-%d %H:%M:%S%.3f").to_string()
+        } else {
+            format!("+{:>10.3}s", record.mono_us as f64 / 1_000_000.0)
+        };
+        let line = match record.stream {
+            Stream::Meta => format!("# {}", record.text).dimmed().to_string(),
+            Stream::Stdin => format!("> {}", record.text).green().to_string(),
+            Stream::Stdout => format!("  {}", record.text),
+            Stream::Stderr => format!("! {}", record.text).red().to_string(),
+        };
+        writeln!(out, "[{}] {}", time, line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Modules grouped by the segments of their names (type, platform,
+//! service, module) for the radial menu, which would otherwise rebuild the
+//! grouping from `show exploits` text.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::msf::ExploitDetails;
+use crate::rank::Rank;
+
+#[derive(Clone, Debug, Default)]
+pub struct TreeOptions {
+    /// Levels below the root to include; deeper nodes are only counted.
+    pub max_depth: Option<usize>,
+    /// Merges a group below the module types with a single subgroup into
+    /// one node, named `windows/smb` rather than `windows` with a lone
+    /// `smb` child.
+    pub collapse: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TreeNode {
+    /// The segment, or segments joined with `/` when collapsed. Empty for
+    /// the root.
+    pub name: String,
+    /// The name prefix the node stands for, e.g. `exploit/windows/smb`.
+    pub path: String,
+    /// Modules at or below the node.
+    pub modules: usize,
+    /// Direct children, including those cut off by the depth limit.
+    pub child_count: usize,
+    /// Modules at or below the node by rank, leaving out modules whose
+    /// rank is not known.
+    pub ranks: BTreeMap<Rank, usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<TreeNode>,
+    /// Set on leaves, i.e. the module itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module: Option<ExploitDetails>,
+}
+
+#[derive(Default)]
+struct Branch<'a> {
+    children: BTreeMap<&'a str, Branch<'a>>,
+    module: Option<&'a ExploitDetails>,
+}
+
+impl Branch<'_> {
+    fn node(&self, name: &str, path: &str, depth: usize, options: &TreeOptions) -> TreeNode {
+        let (mut name, mut path) = (name.to_string(), path.to_string());
+        let mut branch = self;
+        // the root, module types and leaves keep their own nodes
+        while options.collapse && depth > 1 && branch.module.is_none() {
+            let mut children = branch.children.iter();
+            let (Some((segment, child)), None) = (children.next(), children.next()) else {
+                break;
+            };
+            if child.children.is_empty() {
+                break;
+            }
+            name = format!("{}/{}", name, segment);
+            path = format!("{}/{}", path, segment);
+            branch = child;
+        }
+
+        let mut node = TreeNode {
+            name,
+            path,
+            modules: 0,
+            child_count: branch.children.len(),
+            ranks: BTreeMap::new(),
+            children: Vec::new(),
+            module: branch.module.cloned(),
+        };
+        if let Some(module) = branch.module {
+            node.modules += 1;
+            if let Some(rank) = module.rank {
+                *node.ranks.entry(rank).or_default() += 1;
+            }
+        }
+        let expand = options.max_depth.is_none_or(|max| depth < max);
+        for (segment, child) in &branch.children {
+            let path = match node.path.as_str() {
+                "" => segment.to_string(),
+                prefix => format!("{}/{}", prefix, segment),
+            };
+            let child = child.node(segment, &path, depth + 1, options);
+            node.modules += child.modules;
+            for (rank, count) in &child.ranks {
+                *node.ranks.entry(*rank).or_default() += count;
+            }
+            if expand {
+                node.children.push(child);
+            }
+        }
+        node
+    }
+}
+
+/// The tree of `modules`, rooted at a node with an empty name whose
+/// children are the module types.
+pub fn build<'a>(
+    modules: impl IntoIterator<Item = &'a ExploitDetails>,
+    options: &TreeOptions,
+) -> TreeNode {
+    let mut root = Branch::default();
+    for module in modules {
+        let mut branch = &mut root;
+        for segment in module.name.split('/').filter(|s| !s.is_empty()) {
+            branch = branch.children.entry(segment).or_default();
+        }
+        branch.module = Some(module);
+    }
+    root.node("", "", 0, options)
+}
@@ -0,0 +1,296 @@
+//! Checks option values against the catalog before they are sent to
+//! msfconsole, so mistakes are reported up front instead of as a failed run.
+//! Kinds are mostly guessed from option text, so a value is only refused
+//! when its kind is certain; other mismatches are warnings.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::net::IpAddr;
+
+use crate::catalog::{Catalog, CatalogEntry};
+use crate::kind::OptionKind;
+use crate::msf::Parameter;
+
+/// Options msfconsole accepts for any module, so never reported as unknown.
+const GLOBAL_OPTIONS: [&str; 5] = ["PAYLOAD", "TARGET", "ACTION", "VERBOSE", "WORKSPACE"];
+
+/// msfconsole treats these as the same option.
+const ALIASES: [(&str, &str); 1] = [("RHOST", "RHOSTS")];
+
+/// Options whose kind is known from their name, as the framework defines
+/// them the same way in every module.
+const KNOWN_KINDS: [(&str, OptionKind); 10] = [
+    ("RHOSTS", OptionKind::AddressRange),
+    ("RPORT", OptionKind::Port),
+    ("LHOST", OptionKind::Address),
+    ("LPORT", OptionKind::Port),
+    ("SRVHOST", OptionKind::Address),
+    ("SRVPORT", OptionKind::Port),
+    ("CHOST", OptionKind::Address),
+    ("CPORT", OptionKind::Port),
+    ("ReverseListenerBindAddress", OptionKind::Address),
+    ("ReverseListenerBindPort", OptionKind::Port),
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    MissingRequired {
+        option: String,
+    },
+    UnknownOption {
+        option: String,
+    },
+    NotAccepted {
+        option: String,
+        value: String,
+        accepted: Vec<String>,
+    },
+    InvalidPort {
+        option: String,
+        value: String,
+    },
+    InvalidAddress {
+        option: String,
+        value: String,
+    },
+    InvalidBoolean {
+        option: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingRequired { option } => write!(f, "{} is required", option),
+            Problem::UnknownOption { option } => write!(f, "{} is not an option", option),
+            Problem::NotAccepted {
+                option,
+                value,
+                accepted,
+            } => write!(
+                f,
+                "{} must be one of {}, not {:?}",
+                option,
+                accepted.join(", "),
+                value
+            ),
+            Problem::InvalidPort { option, value } => {
+                write!(f, "{} is not a valid port: {:?}", option, value)
+            }
+            Problem::InvalidAddress { option, value } => {
+                write!(f, "{} is not a valid address: {:?}", option, value)
+            }
+            Problem::InvalidBoolean { option, value } => {
+                write!(f, "{} is not a valid boolean: {:?}", option, value)
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Validation {
+    pub module: String,
+    pub valid: bool,
+    pub problems: Vec<Problem>,
+    /// Values that do not fit a guessed kind; they do not make the values
+    /// invalid.
+    pub warnings: Vec<Problem>,
+}
+
+/// Validates `values` for module `name`; errors if the module is not in the
+/// catalog.
+pub fn validate_module(
+    catalog: &Catalog,
+    name: &str,
+    values: &HashMap<String, String>,
+) -> Result<Validation, String> {
+    let entry = catalog
+        .modules
+        .get(name)
+        .ok_or_else(|| format!("{} is not in the catalog", name))?;
+    let (problems, warnings) = validate(entry, values);
+    Ok(Validation {
+        module: name.to_string(),
+        valid: problems.is_empty(),
+        problems,
+        warnings,
+    })
+}
+
+/// Every problem with `values`, in option order, then the warnings. Option
+/// names are matched case-insensitively, as msfconsole does. Payload options
+/// are only checked when `values` keeps the module's default payload, since
+/// the catalog has nothing on other payloads.
+pub fn validate(
+    entry: &CatalogEntry,
+    values: &HashMap<String, String>,
+) -> (Vec<Problem>, Vec<Problem>) {
+    let values: HashMap<String, &String> = values
+        .iter()
+        .map(|(name, value)| (canonical(name), value))
+        .collect();
+    let default_payload = values
+        .get("PAYLOAD")
+        .is_none_or(|p| p.as_str() == entry.payload);
+    let mut parameters: Vec<&Parameter> = entry.options.iter().flatten().collect();
+    if default_payload {
+        parameters.extend(entry.payload_options.iter().flatten());
+    }
+
+    let (mut problems, mut warnings) = (Vec::new(), Vec::new());
+    for parameter in &parameters {
+        let value = values
+            .get(&canonical(&parameter.name))
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty());
+        match value {
+            Some(value) => match check_value(parameter, value) {
+                Some(problem) if is_certain(parameter) => problems.push(problem),
+                Some(warning) => warnings.push(warning),
+                None => {}
+            },
+            None if parameter.required
+                && parameter.default_value.as_deref().unwrap_or("").is_empty() =>
+            {
+                problems.push(Problem::MissingRequired {
+                    option: parameter.name.clone(),
+                })
+            }
+            None => {}
+        }
+    }
+
+    if default_payload {
+        let mut unknown: Vec<&String> = values
+            .keys()
+            .filter(|name| !GLOBAL_OPTIONS.contains(&name.as_str()))
+            .filter(|name| !parameters.iter().any(|p| canonical(&p.name) == **name))
+            .collect();
+        unknown.sort();
+        problems.extend(unknown.into_iter().map(|name| Problem::UnknownOption {
+            option: name.clone(),
+        }));
+    }
+    (problems, warnings)
+}
+
+/// Whether the kind of `parameter` is certain rather than guessed: an
+/// "Accepted:" list or a true/false default, which msfconsole only shows
+/// for OptEnum and OptBool, or a name in `KNOWN_KINDS`.
+fn is_certain(parameter: &Parameter) -> bool {
+    match parameter.kind() {
+        OptionKind::Enum => true,
+        OptionKind::Boolean => parameter
+            .default_value
+            .as_deref()
+            .is_some_and(|d| d.eq_ignore_ascii_case("true") || d.eq_ignore_ascii_case("false")),
+        kind => KNOWN_KINDS
+            .iter()
+            .any(|(name, known)| canonical(name) == canonical(&parameter.name) && *known == kind),
+    }
+}
+
+fn canonical(name: &str) -> String {
+    let name = name.to_uppercase();
+    ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map(|(_, option)| option.to_string())
+        .unwrap_or(name)
+}
+
+fn check_value(parameter: &Parameter, value: &str) -> Option<Problem> {
+    let option = parameter.name.clone();
+    let value_owned = value.to_string();
+    match parameter.kind() {
+        OptionKind::Enum => {
+            let accepted = parameter.accepted_values().unwrap_or_default();
+            if !accepted.iter().any(|a| a.eq_ignore_ascii_case(value)) {
+                return Some(Problem::NotAccepted {
+                    option,
+                    value: value_owned,
+                    accepted,
+                });
+            }
+        }
+        OptionKind::Port if value.parse::<u16>().is_err() => {
+            return Some(Problem::InvalidPort {
+                option,
+                value: value_owned,
+            })
+        }
+        OptionKind::AddressRange
+            if !value.starts_with("file:") && !value.split([' ', ',']).all(is_host_spec) =>
+        {
+            return Some(Problem::InvalidAddress {
+                option,
+                value: value_owned,
+            })
+        }
+        OptionKind::Address if value.parse::<IpAddr>().is_err() && !is_hostname(value) => {
+            return Some(Problem::InvalidAddress {
+                option,
+                value: value_owned,
+            })
+        }
+        OptionKind::Boolean if !is_boolean(value) => {
+            return Some(Problem::InvalidBoolean {
+                option,
+                value: value_owned,
+            })
+        }
+        _ => {}
+    }
+    None
+}
+
+/// The spellings msfconsole's OptBool accepts.
+fn is_boolean(value: &str) -> bool {
+    ["true", "false", "yes", "no", "y", "n", "t", "f", "1", "0"]
+        .iter()
+        .any(|b| b.eq_ignore_ascii_case(value))
+}
+
+/// One entry of an RHOSTS list: an address, a CIDR block, an address range
+/// ("10.0.0.1-10.0.0.9" or "10.0.0.1-9") or a hostname.
+fn is_host_spec(spec: &str) -> bool {
+    if spec.is_empty() {
+        return true;
+    }
+    if let Some((address, prefix)) = spec.split_once('/') {
+        let max = match address.parse::<IpAddr>() {
+            Ok(IpAddr::V4(_)) => 32,
+            Ok(IpAddr::V6(_)) => 128,
+            Err(_) => return false,
+        };
+        return prefix.parse::<u8>().is_ok_and(|p| p <= max);
+    }
+    if let Some((start, end)) = spec.split_once('-') {
+        if let Ok(start) = start.parse::<IpAddr>() {
+            return match end.parse::<IpAddr>() {
+                Ok(end) => start.is_ipv4() == end.is_ipv4() && start <= end,
+                Err(_) => start.is_ipv4() && end.parse::<u8>().is_ok(),
+            };
+        }
+    }
+    spec.parse::<IpAddr>().is_ok() || is_hostname(spec)
+}
+
+/// A DNS name or interface name. Dotted numbers are not hostnames, so
+/// "10.0.0.300" is rejected instead of being looked up.
+fn is_hostname(name: &str) -> bool {
+    let labels: Vec<&str> = name.trim_end_matches('.').split('.').collect();
+    name.len() <= 253
+        && !labels.iter().all(|l| l.chars().all(|c| c.is_ascii_digit()))
+        && labels.iter().all(|l| {
+            !l.is_empty()
+                && l.len() <= 63
+                && !l.starts_with('-')
+                && !l.ends_with('-')
+                && l.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
@@ -0,0 +1,73 @@
+use create_options_json::msf::{self, MSFProcess, Parameter};
+
+const SHOW_ADVANCED: &str = include_str!("fixtures/show_advanced.txt");
+
+#[test]
+fn parses_show_advanced_tables() {
+    let (module, payload) = MSFProcess::parse_show_advanced(SHOW_ADVANCED);
+    let names: Vec<&str> = module.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "CHOST",
+            "ConnectTimeout",
+            "Proxies",
+            "SSL",
+            "VERBOSE",
+            "WfsDelay"
+        ]
+    );
+    assert!(module.iter().chain(&payload).all(|p| p.advanced));
+    let timeout = &module[1];
+    assert_eq!(timeout.default_value.as_deref(), Some("10"));
+    assert!(timeout.required);
+    assert_eq!(module[0].default_value, None);
+
+    let names: Vec<&str> = payload.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["AutoVerifySession", "CommandShellCleanupCommand", "VERBOSE"]
+    );
+}
+
+#[test]
+fn advanced_flag_is_only_serialized_when_set() {
+    let (module, _) = MSFProcess::parse_show_advanced(SHOW_ADVANCED);
+    let ssl = serde_json::to_value(&module[3]).unwrap();
+    assert_eq!(ssl["name"], "SSL");
+    assert_eq!(ssl["advanced"], true);
+
+    let rport = Parameter::new(
+        "RPORT".to_string(),
+        Some("21".to_string()),
+        true,
+        "The target port (TCP)".to_string(),
+    );
+    let json = serde_json::to_value(&rport).unwrap();
+    assert!(json.get("advanced").is_none());
+    // catalogs written before the flag existed still load
+    let loaded: Parameter = serde_json::from_value(json).unwrap();
+    assert!(!loaded.advanced);
+}
+
+#[test]
+fn merging_keeps_basic_options_and_skips_duplicates() {
+    let (_, payload) = MSFProcess::parse_show_advanced(SHOW_ADVANCED);
+    let basic = |name: &str| Parameter::new(name.to_string(), None, false, String::new());
+    let mut options = vec![basic("LHOST"), basic("VERBOSE")];
+    msf::merge_options(&mut options, payload.clone());
+    // merging again, as both the exploit and the payload pass may, adds nothing
+    msf::merge_options(&mut options, payload);
+    let names: Vec<&str> = options.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "LHOST",
+            "VERBOSE",
+            "AutoVerifySession",
+            "CommandShellCleanupCommand"
+        ]
+    );
+    assert!(!options[1].advanced);
+    assert!(options[2].advanced);
+}
@@ -0,0 +1,147 @@
+use create_options_json::ansi::{to_html, to_spans, Span, Style};
+
+fn span(text: &str, style: Style) -> Span {
+    Span {
+        text: text.to_string(),
+        style,
+    }
+}
+
+fn fg(color: &str) -> Style {
+    Style {
+        fg: Some(color.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plain_text_is_one_span() {
+    assert_eq!(to_spans("msf6 > "), [span("msf6 > ", Style::default())]);
+    assert_eq!(to_spans(""), []);
+}
+
+#[test]
+fn sgr_reset_and_combined_codes() {
+    let bold_red = Style {
+        bold: true,
+        ..fg("#cd0000")
+    };
+    assert_eq!(
+        to_spans("\x1b[1;31m[-]\x1b[0m failed"),
+        [
+            span("[-]", bold_red.clone()),
+            span(" failed", Style::default())
+        ]
+    );
+    // `ESC [ m` resets like `ESC [ 0 m`
+    assert_eq!(
+        to_spans("\x1b[1;31m[-]\x1b[m failed"),
+        [span("[-]", bold_red), span(" failed", Style::default())]
+    );
+
+    let all = Style {
+        fg: Some("#00cd00".to_string()),
+        bg: Some("#0000ee".to_string()),
+        bold: true,
+        dim: true,
+        italic: true,
+        underline: true,
+        inverse: true,
+    };
+    assert_eq!(
+        to_spans("\x1b[1;2;3;4;7;32;44ma\x1b[22;23;24;27;39;49mb"),
+        [span("a", all), span("b", Style::default())]
+    );
+    assert_eq!(
+        to_spans("\x1b[93mbright\x1b[105mboth"),
+        [
+            span("bright", fg("#ffff00")),
+            span(
+                "both",
+                Style {
+                    bg: Some("#ff00ff".to_string()),
+                    ..fg("#ffff00")
+                }
+            ),
+        ]
+    );
+    // a code that changes nothing does not split the run
+    assert_eq!(to_spans("a\x1b[39mb"), [span("ab", Style::default())]);
+}
+
+#[test]
+fn indexed_and_true_colour() {
+    assert_eq!(to_spans("\x1b[38;5;9mx"), [span("x", fg("#ff0000"))]);
+    assert_eq!(to_spans("\x1b[38;5;196mx"), [span("x", fg("#ff0000"))]);
+    assert_eq!(to_spans("\x1b[38;5;67mx"), [span("x", fg("#5f87af"))]);
+    assert_eq!(to_spans("\x1b[38;5;244mx"), [span("x", fg("#808080"))]);
+    assert_eq!(to_spans("\x1b[38;2;1;2;255mx"), [span("x", fg("#0102ff"))]);
+    assert_eq!(
+        to_spans("\x1b[48;2;16;32;48;1mx"),
+        [span(
+            "x",
+            Style {
+                bg: Some("#102030".to_string()),
+                bold: true,
+                ..Default::default()
+            }
+        )]
+    );
+    // truncated extended colours clear the colour instead of panicking
+    assert_eq!(to_spans("\x1b[31;38;5mx"), [span("x", Style::default())]);
+    assert_eq!(to_spans("\x1b[38;2;1mx"), [span("x", Style::default())]);
+}
+
+#[test]
+fn strips_other_escape_sequences() {
+    // OSC titles and hyperlinks, ended by BEL or ESC \
+    assert_eq!(
+        to_spans("\x1b]0;msfconsole\x07a\x1b]8;;https://x.test\x1b\\b\x1b]8;;\x1b\\c"),
+        [span("abc", Style::default())]
+    );
+    // cursor movement and erasing
+    assert_eq!(
+        to_spans("\x1b[2K\x1b[1Gmsf6\x1b[?25h >"),
+        [span("msf6 >", Style::default())]
+    );
+    // charset selection from `tput sgr0`, and a lone two-byte escape
+    assert_eq!(
+        to_spans("\x1b[1mon\x1b(B\x1b[moff\x1b=\x1b"),
+        [
+            span(
+                "on",
+                Style {
+                    bold: true,
+                    ..Default::default()
+                }
+            ),
+            span("off", Style::default()),
+        ]
+    );
+}
+
+#[test]
+fn html_escapes_text() {
+    assert_eq!(
+        to_html("<a href=\"x\">Tom & Jerry's</a>"),
+        "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+    );
+    assert_eq!(
+        to_html("\x1b[1;31m<b>\x1b[0m & done"),
+        "<span style=\"color:#cd0000;font-weight:bold\">&lt;b&gt;</span> &amp; done"
+    );
+}
+
+#[test]
+fn html_styles() {
+    assert_eq!(
+        to_html("\x1b[2;3;4;38;2;0;0;0;48;5;15mx"),
+        "<span style=\"color:#000000;background-color:#ffffff;opacity:0.7;\
+         font-style:italic;text-decoration:underline\">x</span>"
+    );
+    // inverse swaps the colours
+    assert_eq!(
+        to_html("\x1b[7;31mx"),
+        "<span style=\"background-color:#cd0000\">x</span>"
+    );
+}
@@ -0,0 +1,78 @@
+use create_options_json::banner::{check_completeness, Banner};
+use create_options_json::msf::MSFProcess;
+
+/// A recorded msfconsole session: the startup banner, then `show exploits`.
+fn recorded_session() -> String {
+    let text = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/output.txt")).unwrap();
+    String::from_utf8(strip_ansi_escapes::strip(text)).unwrap()
+}
+
+#[test]
+fn parses_banner_counts_and_version() {
+    let banner = Banner::parse(&recorded_session()).unwrap();
+    assert_eq!(banner.version.as_deref(), Some("6.4.43-dev-"));
+    let counts: Vec<(&str, usize)> = [
+        "exploits",
+        "auxiliary",
+        "post",
+        "payloads",
+        "encoders",
+        "nops",
+        "evasion",
+    ]
+    .iter()
+    .map(|t| (*t, banner.count(t).unwrap()))
+    .collect();
+    assert_eq!(
+        counts,
+        [
+            ("exploits", 2482),
+            ("auxiliary", 1279),
+            ("post", 431),
+            ("payloads", 1463),
+            ("encoders", 49),
+            ("nops", 13),
+            ("evasion", 9),
+        ]
+    );
+
+    let version = Banner::parse("Framework: 6.4.43-dev-\nConsole  : 6.4.43-dev-").unwrap();
+    assert_eq!(version.version.as_deref(), Some("6.4.43-dev-"));
+    assert!(version.counts.is_empty());
+    assert_eq!(Banner::parse("msf6 > "), None);
+}
+
+#[test]
+fn recorded_exploit_table_is_complete() {
+    let session = recorded_session();
+    let banner = Banner::parse(&session).unwrap();
+    let exploits = MSFProcess::extract_exploit_details(&session);
+    let result = check_completeness("exploits", banner.count("exploits"), &session, &exploits);
+    assert_eq!(result.unparsed, Vec::<String>::new());
+    assert_eq!(result.found, 2482);
+    assert!(result.complete);
+}
+
+#[test]
+fn lists_rows_that_were_not_parsed() {
+    let session = recorded_session();
+    let mut exploits = MSFProcess::extract_exploit_details(&session);
+    // a row lost entirely and one whose columns were shifted
+    exploits.retain(|e| e.name != "exploit/aix/local/ibstat_path");
+    exploits[0].rank = None;
+    let result = check_completeness("exploits", Some(2482), &session, &exploits);
+    assert_eq!(result.found, 2480);
+    assert!(!result.complete);
+    let names: Vec<&str> = result
+        .unparsed
+        .iter()
+        .map(|row| row.split_whitespace().nth(1).unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        [
+            "exploit/aix/local/ibstat_path",
+            "exploit/aix/local/invscout_rpm_priv_esc",
+        ]
+    );
+}
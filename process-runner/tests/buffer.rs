@@ -0,0 +1,155 @@
+use create_options_json::buffer::{
+    BufferLimits, BufferStats, LineBuffer, OverflowPolicy, StreamBuffer,
+};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+fn lines(n: usize) -> impl Iterator<Item = String> {
+    (0..n).map(|i| format!("line {:02}", i))
+}
+
+#[test]
+fn parses_overflow_policies() {
+    assert!(matches!(
+        "drop-oldest".parse(),
+        Ok(OverflowPolicy::DropOldest)
+    ));
+    assert!(matches!("block".parse(), Ok(OverflowPolicy::Block)));
+    assert!(
+        matches!("spill".parse(), Ok(OverflowPolicy::Spill(dir)) if dir == std::env::temp_dir())
+    );
+    assert!(
+        matches!("spill:/var/tmp".parse(), Ok(OverflowPolicy::Spill(dir)) if dir.to_str() == Some("/var/tmp"))
+    );
+    assert!("drop-newest".parse::<OverflowPolicy>().is_err());
+}
+
+#[test]
+fn drop_oldest_keeps_the_newest_lines_under_the_cap() {
+    // each line is 7 bytes, so three fit in 21
+    let mut buffer = LineBuffer::new(
+        "stdout",
+        BufferLimits::bounded(21, OverflowPolicy::DropOldest),
+    );
+    lines(5).for_each(|l| buffer.push(l));
+    assert_eq!(buffer.len(), 3);
+    assert_eq!(
+        buffer.take_stats(),
+        BufferStats {
+            dropped_bytes: 14,
+            dropped_lines: 2,
+            ..Default::default()
+        }
+    );
+    assert_eq!(buffer.drain(), ["line 02", "line 03", "line 04"]);
+    assert!(buffer.is_empty());
+
+    // the counters reset once taken, and draining frees the whole cap
+    assert_eq!(buffer.take_stats(), BufferStats::default());
+    lines(3).for_each(|l| buffer.push(l));
+    assert!(!buffer.take_stats().overflowed());
+}
+
+#[test]
+fn drops_a_line_larger_than_the_cap() {
+    let mut buffer = LineBuffer::new(
+        "stdout",
+        BufferLimits::bounded(8, OverflowPolicy::DropOldest),
+    );
+    buffer.push("short".to_string());
+    buffer.push("far too long for the cap".to_string());
+    assert_eq!(buffer.drain(), Vec::<String>::new());
+    let stats = buffer.take_stats();
+    assert_eq!(stats.dropped_lines, 2);
+    assert_eq!(stats.dropped_bytes, 29);
+}
+
+#[test]
+fn unbounded_never_drops() {
+    let mut buffer = LineBuffer::new("stdout", BufferLimits::unbounded());
+    lines(1000).for_each(|l| buffer.push(l));
+    assert_eq!(buffer.len(), 1000);
+    assert_eq!(buffer.take_stats(), BufferStats::default());
+}
+
+#[test]
+fn spill_writes_evicted_lines_to_a_file() {
+    let dir = std::env::temp_dir().join(format!("buffer-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut buffer = LineBuffer::new(
+        "stdout",
+        BufferLimits::bounded(14, OverflowPolicy::Spill(dir.clone())),
+    );
+    lines(4).for_each(|l| buffer.push(l));
+    let first = buffer.take_stats();
+    assert_eq!(first.spilled_bytes, 14);
+    assert_eq!((first.dropped_bytes, first.dropped_lines), (0, 0));
+    let path = first.spill_path.clone().unwrap();
+    assert!(path.starts_with(&dir));
+
+    // later overflow goes to the same file, which keeps every evicted line
+    buffer.push("line 04".to_string());
+    let second = buffer.take_stats();
+    assert_eq!(second.spilled_bytes, 7);
+    assert_eq!(second.spill_path, Some(path.clone()));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "line 00\nline 01\nline 02\n"
+    );
+    assert_eq!(buffer.drain(), ["line 03", "line 04"]);
+
+    let mut total = first;
+    total.merge(second);
+    assert_eq!(total.spilled_bytes, 21);
+    assert!(total.overflowed());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn spill_falls_back_to_dropping_when_the_file_cannot_be_opened() {
+    let mut buffer = LineBuffer::new(
+        "stdout",
+        BufferLimits::bounded(7, OverflowPolicy::Spill("/nonexistent/dir".into())),
+    );
+    lines(2).for_each(|l| buffer.push(l));
+    let stats = buffer.take_stats();
+    assert_eq!((stats.spilled_bytes, stats.dropped_lines), (0, 1));
+    assert_eq!(stats.spill_path, None);
+}
+
+#[test]
+fn block_waits_for_the_reader_instead_of_dropping() {
+    let buffer = Arc::new(StreamBuffer::new(
+        "stdout",
+        BufferLimits::bounded(14, OverflowPolicy::Block),
+    ));
+    let writer = {
+        let buffer = Arc::clone(&buffer);
+        thread::spawn(move || lines(10).for_each(|l| buffer.push(l)))
+    };
+    // the writer stops at the cap until something is drained
+    thread::sleep(Duration::from_millis(100));
+    assert!(!writer.is_finished());
+    assert!(buffer.len() <= 2);
+
+    let mut read = Vec::new();
+    while read.len() < 10 {
+        read.extend(buffer.wait_drain());
+    }
+    writer.join().unwrap();
+    assert_eq!(read, lines(10).collect::<Vec<_>>());
+    assert_eq!(buffer.take_stats(), BufferStats::default());
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn stream_buffer_clear_and_try_drain() {
+    let buffer = StreamBuffer::new("stdout", BufferLimits::default());
+    assert_eq!(buffer.try_drain(), Vec::<String>::new());
+    lines(3).for_each(|l| buffer.push(l));
+    buffer.clear();
+    assert!(buffer.is_empty());
+    buffer.push("after".to_string());
+    assert_eq!(buffer.try_drain(), ["after"]);
+}
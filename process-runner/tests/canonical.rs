@@ -0,0 +1,164 @@
+use create_options_json::canonical;
+use create_options_json::catalog::CatalogEntry;
+use create_options_json::msf::{ExploitDetails, Parameter};
+use create_options_json::rank::Rank;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn details(name: &str, description: &str) -> ExploitDetails {
+    ExploitDetails {
+        name: name.to_string(),
+        disclosure_date: "2017-03-14".parse().ok(),
+        rank: Some(Rank::Average),
+        check: Some(true),
+        description: description.to_string(),
+    }
+}
+
+#[test]
+fn sorts_modules_and_normalizes_descriptions() {
+    let rows = canonical::details(vec![
+        details(
+            "exploit/windows/smb/psexec",
+            "Microsoft Windows  Authenticated",
+        ),
+        details(
+            "exploit/windows/smb/ms17_010_eternalblue",
+            "MS17-010 EternalBlue SMB Remote Windows Kernel\n  Pool Corruption ",
+        ),
+        details(
+            "exploit/windows/smb/psexec",
+            "Microsoft Windows Authenticated",
+        ),
+    ]);
+    let rows: Vec<(&str, &str)> = rows
+        .iter()
+        .map(|d| (d.name.as_str(), d.description.as_str()))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            (
+                "exploit/windows/smb/ms17_010_eternalblue",
+                "MS17-010 EternalBlue SMB Remote Windows Kernel Pool Corruption"
+            ),
+            (
+                "exploit/windows/smb/psexec",
+                "Microsoft Windows Authenticated"
+            ),
+        ]
+    );
+
+    let entry = CatalogEntry {
+        options: Some(vec![Parameter::new(
+            "SMBDomain".to_string(),
+            Some(" . ".to_string()),
+            false,
+            "The Windows domain to use for\n   authentication".to_string(),
+        )]),
+        ..Default::default()
+    };
+    let catalog = canonical::catalog([
+        ("exploit/windows/smb/psexec".to_string(), entry.clone()),
+        ("exploit/unix/ftp/vsftpd_234_backdoor".to_string(), entry),
+    ]);
+    let names: Vec<&String> = catalog.keys().collect();
+    assert_eq!(
+        names,
+        [
+            "exploit/unix/ftp/vsftpd_234_backdoor",
+            "exploit/windows/smb/psexec"
+        ]
+    );
+    let option = &catalog["exploit/windows/smb/psexec"]
+        .options
+        .as_ref()
+        .unwrap()[0];
+    assert_eq!(
+        option.description,
+        "The Windows domain to use for authentication"
+    );
+    assert_eq!(option.default_value.as_deref(), Some("."));
+}
+
+#[test]
+fn duplicate_rows_keep_the_same_row_in_any_order() {
+    let rows = || {
+        vec![
+            details("exploit/windows/smb/psexec", "Second"),
+            details("exploit/multi/http/struts2_content_type_ognl", "Struts"),
+            details("exploit/windows/smb/psexec", "First"),
+            details("exploit/windows/smb/psexec", "Second"),
+        ]
+    };
+    let mut reversed = rows();
+    reversed.reverse();
+    for rows in [rows(), reversed] {
+        let rows: Vec<(String, String)> = canonical::details(rows)
+            .into_iter()
+            .map(|d| (d.name, d.description))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (
+                    "exploit/multi/http/struts2_content_type_ognl".to_string(),
+                    "Struts".to_string()
+                ),
+                (
+                    "exploit/windows/smb/psexec".to_string(),
+                    "First".to_string()
+                ),
+            ]
+        );
+    }
+}
+
+fn extract(out: &Path, flag: bool) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_create-options-json"));
+    command
+        .arg("extract")
+        .arg(Path::new(FIXTURES).join("modules"))
+        .args(["--out".as_ref(), out.as_os_str()])
+        .env("RUST_LOG", "off");
+    if flag {
+        command.arg("--canonical");
+    } else {
+        command.env("PROCESS_RUNNER_CANONICAL", "1");
+    }
+    let status = command.status().expect("Failed to run extract");
+    assert!(status.success());
+}
+
+#[test]
+fn canonical_runs_are_byte_identical() {
+    let dir = |run: &str| -> PathBuf {
+        std::env::temp_dir().join(format!("canonical-{}-{}", run, std::process::id()))
+    };
+    let (first, second) = (dir("first"), dir("second"));
+    // --canonical and PROCESS_RUNNER_CANONICAL=1 give the same output
+    extract(&first, true);
+    extract(&second, false);
+    for name in ["exploits.json", "auxiliaries.json", "exploits_options.json"] {
+        let first = std::fs::read_to_string(first.join(name)).unwrap();
+        let second = std::fs::read_to_string(second.join(name)).unwrap();
+        assert_eq!(first, second, "{} differs between runs", name);
+        assert!(first.ends_with("}\n") || first.ends_with("]\n"));
+    }
+
+    let catalog = std::fs::read_to_string(first.join("exploits_options.json")).unwrap();
+    let positions: Vec<usize> = [
+        "exploit/multi/http/struts2_content_type_ognl",
+        "exploit/unix/ftp/vsftpd_234_backdoor",
+        "exploit/windows/smb/ms17_010_eternalblue",
+    ]
+    .iter()
+    .map(|name| catalog.find(&format!("\"{}\":", name)).unwrap())
+    .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+    std::fs::remove_dir_all(first).unwrap();
+    std::fs::remove_dir_all(second).unwrap();
+}
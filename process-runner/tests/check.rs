@@ -0,0 +1,103 @@
+use create_options_json::check::{parse_check, CheckStatus};
+use create_options_json::transcript::{read_transcript, Stream};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// The output recorded for each `check` in the transcript, in order.
+fn recorded_checks() -> Vec<String> {
+    let records = read_transcript(&Path::new(FIXTURES).join("check.jsonl")).unwrap();
+    let mut checks = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        if record.stream == Stream::Stdin && record.text == "check" {
+            let output: Vec<&str> = records[i + 1..]
+                .iter()
+                .take_while(|r| r.stream != Stream::Stdin)
+                .map(|r| r.text.as_str())
+                .collect();
+            checks.push(output.join("\n"));
+        }
+    }
+    checks
+}
+
+#[test]
+fn parses_recorded_check_output() {
+    let statuses: Vec<CheckStatus> = recorded_checks()
+        .iter()
+        .map(|output| parse_check(output).0)
+        .collect();
+    assert_eq!(
+        statuses,
+        [
+            CheckStatus::Vulnerable,
+            CheckStatus::Appears,
+            CheckStatus::Safe,
+            CheckStatus::Error,
+            CheckStatus::Unknown,
+            CheckStatus::Error,
+            CheckStatus::Unknown,
+        ]
+    );
+}
+
+#[test]
+fn verdict_wins_over_earlier_errors() {
+    // the scanner's "[-] Host does NOT appear vulnerable." precedes the verdict
+    let (status, message) = parse_check(&recorded_checks()[2]);
+    assert_eq!(status, CheckStatus::Safe);
+    assert_eq!(message, "10.0.0.6:445 - The target is not exploitable.");
+
+    assert_eq!(parse_check("").0, CheckStatus::Unknown);
+    assert_eq!(
+        parse_check("[-] Unknown command: chek.").1,
+        "Unknown command: chek."
+    );
+}
+
+#[test]
+fn check_mode_reports_per_target_and_module() {
+    let output = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .arg("check")
+        .args(["--replay", &format!("{}/check.jsonl", FIXTURES)])
+        .args(["--modules", &format!("{}/check_modules.json", FIXTURES)])
+        .args(["--filter", "/smb/"])
+        .args(["--targets", "10.0.0.5,10.0.0.6"])
+        .env("RUST_LOG", "off")
+        .output()
+        .expect("Failed to run check");
+    assert!(output.status.success());
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let results: Vec<(&str, &str, &str)> = report["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["target"].as_str().unwrap(),
+                r["module"].as_str().unwrap(),
+                r["status"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    // psexec has no check and struts2 is filtered out
+    assert_eq!(
+        results,
+        [
+            (
+                "10.0.0.5",
+                "exploit/windows/smb/ms17_010_eternalblue",
+                "vulnerable"
+            ),
+            ("10.0.0.5", "exploit/windows/smb/ms08_067_netapi", "appears"),
+            (
+                "10.0.0.6",
+                "exploit/windows/smb/ms17_010_eternalblue",
+                "safe"
+            ),
+            ("10.0.0.6", "exploit/windows/smb/ms08_067_netapi", "error"),
+        ]
+    );
+}
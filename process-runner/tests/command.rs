@@ -0,0 +1,99 @@
+use create_options_json::command::{quote, Action, CommandBuilder};
+
+#[test]
+fn quotes_values_msfconsole_would_split_or_strip() {
+    let cases = [
+        ("10.0.0.5", "10.0.0.5"),
+        (
+            "/usr/share/wordlists/rockyou.txt",
+            "/usr/share/wordlists/rockyou.txt",
+        ),
+        ("10.0.0.5 10.0.0.6", "\"10.0.0.5 10.0.0.6\""),
+        ("a\tb", "\"a\tb\""),
+        ("id; uname -a", "\"id; uname -a\""),
+        ("#admin", "\"#admin\""),
+        ("say \"hi\"", "\"say \\\"hi\\\"\""),
+        ("it's", "\"it's\""),
+        ("C:\\Windows\\Temp", "\"C:\\\\Windows\\\\Temp\""),
+        ("\\\"", "\"\\\\\\\"\""),
+        // a bare `set NAME` would show the option rather than clear it
+        ("", "\"\""),
+    ];
+    for (value, quoted) in cases {
+        assert_eq!(quote(value).unwrap(), quoted, "{:?}", value);
+    }
+}
+
+#[test]
+fn refuses_line_breaks() {
+    for value in ["a\nb", "a\rb", "\r\n"] {
+        assert!(quote(value).unwrap_err().contains("line break"));
+    }
+    let builder = CommandBuilder::new("exploit/unix/ftp/vsftpd_234_backdoor")
+        .option("RHOSTS", "10.0.0.5\nirb");
+    assert!(builder.commands().is_err());
+    let builder = CommandBuilder::new("exploit/unix/ftp/vsftpd_234_backdoor").target("0\nirb");
+    assert!(builder.commands().is_err());
+}
+
+#[test]
+fn builds_commands_in_order() {
+    let commands = CommandBuilder::new("exploit/windows/smb/ms17_010_eternalblue")
+        .payload("windows/x64/meterpreter/reverse_tcp")
+        .target("Windows 7")
+        .option("RHOSTS", "10.0.0.5")
+        .option("SMBPass", "p@ss word;#")
+        .option("rhosts", "10.0.0.6")
+        .commands()
+        .unwrap();
+    assert_eq!(
+        commands,
+        [
+            "use exploit/windows/smb/ms17_010_eternalblue",
+            "set PAYLOAD windows/x64/meterpreter/reverse_tcp",
+            "set TARGET \"Windows 7\"",
+            "set RHOSTS 10.0.0.6",
+            "set SMBPass \"p@ss word;#\"",
+            "exploit",
+        ]
+    );
+    let commands = CommandBuilder::new("auxiliary/scanner/portscan/tcp")
+        .commands()
+        .unwrap();
+    assert_eq!(commands.last().unwrap(), "run");
+}
+
+#[test]
+fn refuses_invalid_names() {
+    assert!(CommandBuilder::new("exploit/x; irb").commands().is_err());
+    assert!(CommandBuilder::new("exploit/unix/ftp/vsftpd_234_backdoor")
+        .payload("cmd/unix/interact; irb")
+        .commands()
+        .is_err());
+    for name in ["", "RHOSTS 10.0.0.1;", "A-B"] {
+        let builder = CommandBuilder::new("exploit/unix/ftp/vsftpd_234_backdoor").option(name, "x");
+        assert!(builder.commands().is_err(), "{:?}", name);
+    }
+}
+
+#[test]
+fn writes_resource_script() {
+    let script = CommandBuilder::new("auxiliary/scanner/portscan/tcp")
+        .option("RHOSTS", "10.0.0.0/24")
+        .option("PORTS", "22, 80, 443")
+        .action(Action::Check)
+        .resource_script()
+        .unwrap();
+    assert_eq!(
+        script,
+        "# auxiliary/scanner/portscan/tcp\n\
+         use auxiliary/scanner/portscan/tcp\n\
+         set RHOSTS 10.0.0.0/24\n\
+         set PORTS \"22, 80, 443\"\n\
+         check\n"
+    );
+    assert!(CommandBuilder::new("auxiliary/scanner/portscan/tcp")
+        .option("RHOSTS", "a\nb")
+        .resource_script()
+        .is_err());
+}
@@ -0,0 +1,118 @@
+use create_options_json::catalog::CatalogEntry;
+use create_options_json::compare::{compare, summary_table, CatalogSource, Discrepancy, Side};
+use create_options_json::extract::extract_tree;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn scraped() -> CatalogSource {
+    CatalogSource::load(&Path::new(FIXTURES).join("scraped")).unwrap()
+}
+
+fn extracted() -> CatalogSource {
+    let modules = extract_tree(&Path::new(FIXTURES).join("modules"));
+    let exploits = modules.iter().filter(|m| m.name.starts_with("exploit/"));
+    CatalogSource {
+        label: "extracted".to_string(),
+        msf_version: None,
+        entries: exploits
+            .clone()
+            .map(|m| (m.name.clone(), CatalogEntry::from(m.exploit())))
+            .collect(),
+        details: exploits.map(|m| (m.name.clone(), m.details())).collect(),
+    }
+}
+
+#[test]
+fn flags_discrepancies_between_catalogs() {
+    let comparison = compare(&scraped(), &extracted());
+    assert_eq!(comparison.compared, 3);
+    assert_eq!(comparison.only_left, ["exploit/windows/smb/psexec"]);
+    assert!(comparison.only_right.is_empty());
+
+    // vsftpd agrees; eternalblue's payload options are only on one side
+    let modules: Vec<&str> = comparison
+        .modules
+        .iter()
+        .map(|m| m.module.as_str())
+        .collect();
+    assert_eq!(
+        modules,
+        [
+            "exploit/multi/http/struts2_content_type_ognl",
+            "exploit/windows/smb/ms17_010_eternalblue",
+        ]
+    );
+
+    let struts = &comparison.modules[0];
+    assert!(struts.discrepancies.contains(&Discrepancy::Required {
+        option: "TIMEOUT".to_string(),
+        left: true,
+        right: false,
+    }));
+    assert!(!struts.dropped_rows_suspected);
+
+    // the scrape lost the rows after SMBDomain's wrapped description
+    let eternalblue = &comparison.modules[1];
+    assert_eq!(
+        eternalblue.discrepancies,
+        [
+            Discrepancy::TruncatedDescription {
+                option: "SMBDomain".to_string(),
+                truncated_on: Side::Left,
+            },
+            Discrepancy::MissingOption {
+                option: "SMBPass".to_string(),
+                missing_from: Side::Left,
+            },
+            Discrepancy::MissingOption {
+                option: "SMBUser".to_string(),
+                missing_from: Side::Left,
+            },
+        ]
+    );
+    assert!(eternalblue.dropped_rows_suspected);
+    assert_eq!(comparison.totals["missing"], 4);
+
+    let table = summary_table(&comparison);
+    let lines: Vec<&str> = table.lines().collect();
+    assert!(lines[0].starts_with("Module"));
+    assert!(lines[2].ends_with("dropped rows?"));
+    assert!(lines[3].starts_with("Total"));
+}
+
+#[test]
+fn compare_writes_table_and_json_report() {
+    let dir = std::env::temp_dir().join(format!("compare-test-{}", std::process::id()));
+    let binary = env!("CARGO_BIN_EXE_create-options-json");
+    let status = Command::new(binary)
+        .arg("extract")
+        .arg(Path::new(FIXTURES).join("modules"))
+        .args(["--out".as_ref(), dir.as_os_str()])
+        .env("RUST_LOG", "off")
+        .status()
+        .expect("Failed to run extract");
+    assert!(status.success());
+
+    let report = dir.join("report.json");
+    let output = Command::new(binary)
+        .arg("compare")
+        .arg(Path::new(FIXTURES).join("scraped"))
+        .arg(&dir)
+        .args(["--out".as_ref(), report.as_os_str()])
+        .output()
+        .expect("Failed to run compare");
+    assert!(output.status.success());
+    let table = String::from_utf8(output.stdout).unwrap();
+    assert!(table.contains("3 modules compared, 2 with discrepancies"));
+
+    let report: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(report["modules"][1]["dropped_rows_suspected"], true);
+    assert_eq!(
+        report["modules"][1]["discrepancies"][0]["discrepancy"],
+        "truncated_description"
+    );
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,290 @@
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// The daemon running against the replay backend.
+struct Daemon {
+    child: Child,
+    stdin: ChildStdin,
+    responses: Receiver<Value>,
+    next_id: u64,
+}
+
+impl Daemon {
+    fn start(extra_args: &[&str]) -> Self {
+        Self::start_with_env(extra_args, &[])
+    }
+
+    fn start_with_env(extra_args: &[&str], env: &[(&str, &Path)]) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+            .arg("daemon")
+            .args(["--replay", &format!("{}/msfconsole.jsonl", FIXTURES)])
+            .args(["--catalog", &format!("{}/catalog.json", FIXTURES)])
+            .args(extra_args)
+            .envs(env.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start daemon");
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+        let (tx, responses) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = line.unwrap();
+                tx.send(serde_json::from_str(&line).unwrap()).unwrap();
+            }
+        });
+        Self {
+            child,
+            stdin,
+            responses,
+            next_id: 1,
+        }
+    }
+
+    fn send_line(&mut self, line: &str) -> Value {
+        writeln!(self.stdin, "{}", line).unwrap();
+        self.stdin.flush().unwrap();
+        self.responses
+            .recv_timeout(Duration::from_secs(30))
+            .expect("No response from daemon")
+    }
+
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params});
+        let response = self.send_line(&request.to_string());
+        assert_eq!(response["id"], id);
+        response
+    }
+
+    fn result(&mut self, method: &str, params: Value) -> Value {
+        let response = self.call(method, params);
+        assert!(response["error"].is_null(), "{}", response);
+        response["result"].clone()
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn catalog_list_filters_by_prefix() {
+    let mut daemon = Daemon::start(&[]);
+    let result = daemon.result("catalog.list", json!({"prefix": "exploit/"}));
+    assert_eq!(
+        result["modules"],
+        json!([
+            "exploit/multi/http/struts2_content_type_ognl",
+            "exploit/windows/smb/ms17_010_eternalblue"
+        ])
+    );
+    assert_eq!(result["fresh"], true);
+}
+
+#[test]
+fn module_options_prefers_fresh_catalog() {
+    let mut daemon = Daemon::start(&[]);
+    let result = daemon.result(
+        "module.options",
+        json!({"name": "exploit/windows/smb/ms17_010_eternalblue"}),
+    );
+    assert_eq!(result["source"], "catalog");
+    assert_eq!(result["payload"], "windows/x64/meterpreter/reverse_tcp");
+    assert_eq!(result["payload_options"][1]["default_value"], "4444");
+    assert_eq!(result["payload_options"][1]["kind"], "port");
+    assert_eq!(result["payload_options"][2]["kind"], "enum");
+    assert_eq!(
+        result["payload_options"][2]["enum_values"],
+        json!(["", "seh", "thread", "process", "none"])
+    );
+}
+
+#[test]
+fn module_options_falls_back_to_console() {
+    let mut daemon = Daemon::start(&[]);
+    let result = daemon.result(
+        "module.options",
+        json!({"name": "exploit/unix/ftp/vsftpd_234_backdoor"}),
+    );
+    assert_eq!(result["source"], "live");
+    assert_eq!(result["payload"], "cmd/unix/interact");
+    assert_eq!(result["target"], json!(["0", "Automatic"]));
+    let options = result["options"].as_array().unwrap();
+    let names: Vec<&str> = options
+        .iter()
+        .map(|o| o["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["CHOST", "CPORT", "RHOSTS", "RPORT"]);
+    assert_eq!(options[3]["default_value"], "21");
+    assert_eq!(options[3]["required"], true);
+    let kinds: Vec<&str> = options
+        .iter()
+        .map(|o| o["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["address", "port", "address_range", "port"]);
+}
+
+#[test]
+fn stale_catalog_is_not_used() {
+    let mut daemon = Daemon::start(&["--catalog-max-age", "0"]);
+    let result = daemon.result(
+        "module.options",
+        json!({"name": "exploit/windows/smb/ms17_010_eternalblue"}),
+    );
+    assert_eq!(result["source"], "live");
+    assert_eq!(daemon.result("catalog.list", json!({}))["fresh"], false);
+}
+
+#[test]
+fn module_validate_reports_problems() {
+    let mut daemon = Daemon::start(&[]);
+    let result = daemon.result(
+        "module.validate",
+        json!({
+            "name": "exploit/windows/smb/ms17_010_eternalblue",
+            "options": {"RHOST": "10.0.0.0/33", "LPORT": "4444", "EXITFUNC": "exit", "SSL": "true"}
+        }),
+    );
+    assert_eq!(result["valid"], false);
+    assert_eq!(
+        result["problems"],
+        json!([
+            {"problem": "invalid_address", "option": "RHOSTS", "value": "10.0.0.0/33"},
+            {"problem": "missing_required", "option": "LHOST"},
+            {
+                "problem": "not_accepted",
+                "option": "EXITFUNC",
+                "value": "exit",
+                "accepted": ["", "seh", "thread", "process", "none"]
+            },
+            {"problem": "unknown_option", "option": "SSL"}
+        ])
+    );
+}
+
+#[test]
+fn console_open_run_close() {
+    let mut daemon = Daemon::start(&[]);
+    let console = daemon.result("console.open", json!({}))["console"].clone();
+    let results = daemon.result(
+        "console.run",
+        json!({"console": console, "commands": ["sessions -l"]}),
+    );
+    assert_eq!(results[0]["command"], "sessions -l");
+    assert!(results[0]["output"]
+        .as_str()
+        .unwrap()
+        .contains("No active sessions."));
+
+    assert_eq!(
+        daemon.result("console.close", json!({"console": console}))["closed"],
+        true
+    );
+    let response = daemon.call(
+        "console.run",
+        json!({"console": console, "command": "back"}),
+    );
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn rejects_malformed_requests() {
+    let mut daemon = Daemon::start(&[]);
+    assert_eq!(daemon.send_line("{not json")["error"]["code"], -32700);
+    assert_eq!(
+        daemon.call("module.list", json!({}))["error"]["code"],
+        -32601
+    );
+    let response = daemon.call("console.run", json!({"command": "use x\nrun"}));
+    assert_eq!(response["error"]["code"], -32602);
+}
+
+#[test]
+fn policy_refuses_commands_outside_role() {
+    let mut daemon = Daemon::start(&["--role", "read-only"]);
+    let results = daemon.result("console.run", json!({"command": "sessions -l"}));
+    assert!(results[0]["output"]
+        .as_str()
+        .unwrap()
+        .contains("No active sessions."));
+    for command in ["irb", "db_nmap 10.0.0.1", "show options; load sounds"] {
+        let response = daemon.call("console.run", json!({ "command": command }));
+        assert_eq!(response["error"]["code"], -32001, "{}", command);
+    }
+
+    let mut daemon = Daemon::start(&["--role", "scanning"]);
+    let console = daemon.result("console.open", json!({}))["console"].clone();
+    let response = daemon.call(
+        "console.run",
+        json!({"console": console, "command": "use exploit/unix/ftp/vsftpd_234_backdoor"}),
+    );
+    assert!(response["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("not allowed for role scanning"));
+}
+
+#[test]
+fn scope_guard_refuses_out_of_scope_targets() {
+    let dir = std::env::temp_dir().join(format!("scope-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scope = dir.join("scope.json");
+    let audit = dir.join("audit.jsonl");
+    std::fs::write(
+        &scope,
+        r#"{"networks": ["10.0.0.0/24"], "hostnames": ["*.lab.example"], "ports": ["1-1024"]}"#,
+    )
+    .unwrap();
+    let mut daemon = Daemon::start_with_env(
+        &[],
+        &[
+            ("PROCESS_RUNNER_SCOPE", &scope),
+            ("PROCESS_RUNNER_SCOPE_AUDIT", &audit),
+        ],
+    );
+    let results = daemon.result(
+        "console.run",
+        json!({"commands": [
+            "set RHOSTS 10.0.0.1-20 web.lab.example",
+            "set RHOSTS 10.0.0.0/16",
+            "run RPORT=8080",
+        ]}),
+    );
+    assert!(results[0]["result"]["refused"].is_null());
+    assert!(results[1]["result"]["refused"]
+        .as_str()
+        .unwrap()
+        .contains("10.0.0.0/16 is out of scope"));
+    assert!(results[1]["output"]
+        .as_str()
+        .unwrap()
+        .starts_with("[-] Refused by scope guard"));
+    assert!(results[2]["result"]["refused"].is_string());
+
+    let log = std::fs::read_to_string(&audit).unwrap();
+    let commands: Vec<String> = log
+        .lines()
+        .map(|l| {
+            serde_json::from_str::<Value>(l).unwrap()["command"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(commands, ["set RHOSTS 10.0.0.0/16", "run RPORT=8080"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,181 @@
+use create_options_json::events::{parse_command_output, parse_line, parse_output, ConsoleEvent};
+use create_options_json::msf::MSFProcess;
+use create_options_json::process::Process;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+#[test]
+fn parses_status_prefixes() {
+    assert_eq!(
+        parse_line("[*] Using configured payload cmd/unix/interact"),
+        Some(ConsoleEvent::Info {
+            message: "Using configured payload cmd/unix/interact".to_string(),
+            target: None,
+        })
+    );
+    assert_eq!(
+        parse_line("[+] 10.0.0.5:21 - Backdoor service has been spawned, handling..."),
+        Some(ConsoleEvent::Success {
+            message: "Backdoor service has been spawned, handling...".to_string(),
+            target: Some("10.0.0.5:21".to_string()),
+        })
+    );
+    assert_eq!(
+        parse_line("[-] Unknown command: foo"),
+        Some(ConsoleEvent::Error {
+            message: "Unknown command: foo".to_string(),
+            target: None,
+        })
+    );
+    assert_eq!(
+        parse_line("[!] 10.0.0.5 - This module is deprecated  "),
+        Some(ConsoleEvent::Warning {
+            message: "This module is deprecated".to_string(),
+            target: Some("10.0.0.5".to_string()),
+        })
+    );
+    // the prompt may be printed in front of asynchronous status lines
+    assert!(matches!(
+        parse_line("msf6 exploit(unix/ftp/vsftpd_234_backdoor) > [*] Started reverse TCP handler on 10.0.0.2:4444"),
+        Some(ConsoleEvent::HandlerStarted { .. })
+    ));
+    assert_eq!(
+        parse_line("   RHOSTS  10.0.0.5  yes  The target host(s)"),
+        None
+    );
+    assert_eq!(parse_line(""), None);
+}
+
+#[test]
+fn parses_jobs_handlers_and_sessions() {
+    assert_eq!(
+        parse_line("[*] Exploit running as background job 3."),
+        Some(ConsoleEvent::JobStarted { job_id: 3 })
+    );
+    assert_eq!(
+        parse_line("[*] Started reverse TCP handler on 10.0.0.2:4444 "),
+        Some(ConsoleEvent::HandlerStarted {
+            handler: "reverse TCP".to_string(),
+            address: "10.0.0.2".to_string(),
+            port: Some(4444),
+        })
+    );
+    assert_eq!(
+        parse_line("[*] Started bind TCP handler against 10.0.0.5:4444"),
+        Some(ConsoleEvent::HandlerStarted {
+            handler: "bind TCP".to_string(),
+            address: "10.0.0.5".to_string(),
+            port: Some(4444),
+        })
+    );
+    assert_eq!(
+        parse_line(
+            "[*] Command shell session 1 opened (10.0.0.2:37015 -> 10.0.0.5:6200) at 2026-10-18 12:00:00 +0000"
+        ),
+        Some(ConsoleEvent::SessionOpened {
+            session_id: 1,
+            session_type: "Command shell".to_string(),
+            local: Some("10.0.0.2:37015".to_string()),
+            remote: Some("10.0.0.5:6200".to_string()),
+        })
+    );
+    assert_eq!(
+        parse_line("[*] 10.0.0.5 - Meterpreter session 2 closed.  Reason: Died"),
+        Some(ConsoleEvent::SessionClosed {
+            session_id: 2,
+            session_type: "Meterpreter".to_string(),
+            reason: Some("Died".to_string()),
+        })
+    );
+}
+
+#[test]
+fn parses_module_results() {
+    assert_eq!(
+        parse_line("[*] Exploit completed, but no session was created."),
+        Some(ConsoleEvent::ExploitCompleted {
+            session_created: false,
+        })
+    );
+    assert_eq!(
+        parse_line("[-] 10.0.0.5:445 - Exploit failed [unreachable]: Rex::ConnectionTimeout The connection timed out"),
+        Some(ConsoleEvent::ExploitFailed {
+            kind: Some("unreachable".to_string()),
+            reason: "Rex::ConnectionTimeout The connection timed out".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_line(
+            "[-] Exploit aborted due to failure: not-vulnerable: Set ForceExploit to override"
+        ),
+        Some(ConsoleEvent::ExploitFailed {
+            kind: Some("not-vulnerable".to_string()),
+            reason: "Set ForceExploit to override".to_string(),
+        })
+    );
+    assert_eq!(
+        parse_line("[*] Auxiliary module execution completed"),
+        Some(ConsoleEvent::ModuleCompleted)
+    );
+    assert_eq!(
+        parse_line("[*] Scanned  26 of 256 hosts (10% complete)"),
+        Some(ConsoleEvent::ScanProgress {
+            scanned: 26,
+            total: 256,
+        })
+    );
+}
+
+#[test]
+fn parses_output_in_order() {
+    let output = "\
+[*] Started reverse TCP handler on 10.0.0.2:4444
+   Name  Current Setting
+[*] Exploit running as background job 0.
+[+] Done";
+    let events = parse_output(output);
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[0], ConsoleEvent::HandlerStarted { .. }));
+    assert_eq!(events[1], ConsoleEvent::JobStarted { job_id: 0 });
+    let json = serde_json::to_value(&events[1]).unwrap();
+    assert_eq!(json["event"], "job_started");
+    assert_eq!(json["job_id"], 0);
+}
+
+#[test]
+fn end_of_output_marker_is_not_an_event() {
+    let builder = Process::builder(env!("CARGO_BIN_EXE_create-options-json"))
+        .args(&["replay-console", &format!("{}/msfconsole.jsonl", FIXTURES)]);
+    let mut msf = MSFProcess::try_with_builder(builder).unwrap();
+    msf.run_command("use exploit/unix/ftp/vsftpd_234_backdoor");
+    assert_eq!(
+        msf.events(),
+        [ConsoleEvent::Info {
+            message: "No payload configured, defaulting to cmd/unix/interact".to_string(),
+            target: None,
+        }]
+    );
+    msf.run_command("sessions -l");
+    assert_eq!(msf.events(), []);
+
+    // output as `sessions -l` leaves it, ending with the echo
+    let output = [
+        "\nActive sessions\n===============\n\nNo active sessions.\n\n[*] exec: ping\n "
+            .to_string(),
+    ];
+    assert_eq!(parse_command_output(&output), []);
+    let output = [
+        "[*] Exploit running as background job 1.\n[*] exec: ping".to_string(),
+        "[+] Done ".to_string(),
+    ];
+    assert_eq!(
+        parse_command_output(&output),
+        [
+            ConsoleEvent::JobStarted { job_id: 1 },
+            ConsoleEvent::Success {
+                message: "Done".to_string(),
+                target: None,
+            },
+        ]
+    );
+}
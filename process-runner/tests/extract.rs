@@ -0,0 +1,225 @@
+use create_options_json::extract::{extract_module, extract_tree, ModuleSource};
+use create_options_json::kind::OptionKind;
+use create_options_json::manifest::{file_sha1, Manifest};
+use create_options_json::rank::Rank;
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn modules() -> Vec<ModuleSource> {
+    extract_tree(&Path::new(FIXTURES).join("modules"))
+}
+
+fn module(name: &str) -> ModuleSource {
+    modules().into_iter().find(|m| m.name == name).unwrap()
+}
+
+#[test]
+fn names_modules_by_type_and_path() {
+    let names: Vec<String> = modules().into_iter().map(|m| m.name).collect();
+    assert_eq!(
+        names,
+        [
+            "auxiliary/scanner/portscan/tcp",
+            "exploit/multi/http/struts2_content_type_ognl",
+            "exploit/unix/ftp/vsftpd_234_backdoor",
+            "exploit/windows/smb/ms17_010_eternalblue",
+        ]
+    );
+}
+
+#[test]
+fn reads_update_info() {
+    let eternalblue = module("exploit/windows/smb/ms17_010_eternalblue");
+    let details = eternalblue.details();
+    assert_eq!(
+        details.description,
+        "MS17-010 EternalBlue SMB Remote Windows Kernel Pool Corruption"
+    );
+    assert_eq!(details.rank, Some(Rank::Average));
+    assert_eq!(details.check, Some(true));
+    // `Mar 14 2017` in the source
+    assert_eq!(details.disclosure_date.unwrap().to_string(), "2017-03-14");
+    assert_eq!(eternalblue.authors.len(), 3);
+    assert_eq!(eternalblue.references[1], ["CVE", "2017-0143"]);
+    assert_eq!(eternalblue.platforms, ["win"]);
+    assert_eq!(eternalblue.arches, ["x64"]);
+    assert_eq!(
+        eternalblue.notes[1],
+        (
+            "Stability".to_string(),
+            vec!["crash-os-restarts".to_string()]
+        )
+    );
+
+    let exploit = eternalblue.exploit();
+    assert_eq!(exploit.payload, "windows/x64/meterpreter/reverse_tcp");
+    assert_eq!(
+        exploit.target,
+        Some(vec!["0".to_string(), "Automatic Target".to_string()])
+    );
+
+    // heredoc description; vsftpd has no `def check`
+    let struts = module("exploit/multi/http/struts2_content_type_ognl");
+    assert!(struts.description.starts_with(
+        "This module exploits a remote code execution vulnerability in Apache Struts version 2.3.5"
+    ));
+    assert_eq!(struts.details().rank, Some(Rank::Excellent));
+    assert!(!module("exploit/unix/ftp/vsftpd_234_backdoor").check);
+}
+
+#[test]
+fn reads_registered_options() {
+    let options = |name: &str| -> Vec<(String, Option<String>, bool, Option<OptionKind>)> {
+        module(name)
+            .options
+            .into_iter()
+            .map(|p| (p.name.clone(), p.default_value.clone(), p.required, p.kind))
+            .collect()
+    };
+    let option = |name: &str, default: Option<&str>, required, kind| {
+        (
+            name.to_string(),
+            default.map(str::to_string),
+            required,
+            Some(kind),
+        )
+    };
+
+    // Tcp mixin options, with RPORT overridden by `Opt::RPORT(21)`
+    assert_eq!(
+        options("exploit/unix/ftp/vsftpd_234_backdoor"),
+        [
+            option("CHOST", None, false, OptionKind::Address),
+            option("CPORT", None, false, OptionKind::Port),
+            option("RHOSTS", None, true, OptionKind::AddressRange),
+            option("RPORT", Some("21"), true, OptionKind::Port),
+        ]
+    );
+    assert_eq!(
+        options("exploit/windows/smb/ms17_010_eternalblue"),
+        [
+            option("RHOSTS", None, true, OptionKind::AddressRange),
+            option("RPORT", Some("445"), true, OptionKind::Port),
+            option("SMBDomain", None, false, OptionKind::String),
+            option("SMBPass", None, false, OptionKind::String),
+            option("SMBUser", None, false, OptionKind::String),
+            option("VERIFY_ARCH", Some("true"), true, OptionKind::Boolean),
+            option("VERIFY_TARGET", Some("true"), true, OptionKind::Boolean),
+        ]
+    );
+
+    // OptEnum and the keyword form
+    let struts = module("exploit/multi/http/struts2_content_type_ognl");
+    let method = struts
+        .options
+        .iter()
+        .find(|p| p.name == "HTTP_METHOD")
+        .unwrap();
+    assert_eq!(method.kind(), OptionKind::Enum);
+    assert_eq!(method.accepted_values().unwrap(), ["GET", "POST", "PUT"]);
+    let timeout = struts.options.iter().find(|p| p.name == "TIMEOUT").unwrap();
+    assert_eq!(
+        (timeout.default_value.as_deref(), timeout.required),
+        (Some("20"), false)
+    );
+
+    // `deregister_options('RPORT')` drops the Tcp mixin's RPORT
+    let tcp = options("auxiliary/scanner/portscan/tcp");
+    assert!(tcp.iter().all(|(name, ..)| name != "RPORT"));
+    assert!(tcp.contains(&option("PORTS", Some("1-10000"), true, OptionKind::String)));
+}
+
+#[test]
+fn extract_writes_catalog_files() {
+    let out = std::env::temp_dir().join(format!("extract-test-{}", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .arg("extract")
+        .arg(Path::new(FIXTURES).join("modules"))
+        .args(["--out".as_ref(), out.as_os_str()])
+        .env("RUST_LOG", "off")
+        .status()
+        .expect("Failed to run extract");
+    assert!(status.success());
+    let read = |name: &str| -> Value {
+        serde_json::from_str(&std::fs::read_to_string(out.join(name)).unwrap()).unwrap()
+    };
+    assert_eq!(read("exploits.json").as_array().unwrap().len(), 3);
+    assert_eq!(
+        read("auxiliaries.json")[0]["name"],
+        "auxiliary/scanner/portscan/tcp"
+    );
+    let catalog = read("exploits_options.json");
+    let vsftpd = &catalog["exploit/unix/ftp/vsftpd_234_backdoor"];
+    assert_eq!(vsftpd["target"][1], "Automatic");
+    assert_eq!(vsftpd["options"][3]["name"], "RPORT");
+    assert_eq!(vsftpd["options"][3]["default_value"], "21");
+    assert_eq!(read("modules_info.json").as_array().unwrap().len(), 4);
+    std::fs::remove_dir_all(out).unwrap();
+}
+
+#[test]
+fn extract_writes_manifest() {
+    let out = std::env::temp_dir().join(format!("extract-manifest-{}", std::process::id()));
+    let status = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .arg("extract")
+        .arg(Path::new(FIXTURES).join("modules"))
+        .args(["--out".as_ref(), out.as_os_str()])
+        .env("RUST_LOG", "off")
+        .status()
+        .expect("Failed to run extract");
+    assert!(status.success());
+
+    let manifest = Manifest::load(&out).unwrap();
+    assert_eq!(manifest.msf_version, None);
+    assert_eq!(manifest.runner_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(manifest.command[0], "extract");
+    assert_eq!(manifest.counts["exploits"], 3);
+    assert_eq!(manifest.counts["auxiliary"], 1);
+    let files: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        files,
+        [
+            "exploits.json",
+            "auxiliaries.json",
+            "exploits_options.json",
+            "modules_info.json",
+        ]
+    );
+    assert_eq!(
+        manifest.files[0].sha1,
+        file_sha1(&out.join("exploits.json")).unwrap()
+    );
+    assert!(manifest.changed_files(&out).is_empty());
+
+    std::fs::write(out.join("auxiliaries.json"), "[]").unwrap();
+    assert_eq!(manifest.changed_files(&out), ["auxiliaries.json"]);
+    std::fs::remove_dir_all(out).unwrap();
+}
+
+#[test]
+fn survives_truncated_files() {
+    let path = Path::new(FIXTURES).join("modules/exploits/unix/ftp/vsftpd_234_backdoor.rb");
+    let src = std::fs::read_to_string(&path).unwrap();
+    let name = "exploit/unix/ftp/vsftpd_234_backdoor";
+
+    // literals cut off right after a backslash, which skips past the end
+    for tail in ["%q(abc\\", "%w[a b\\", "%(\\", "%q{", "<<'EOS"] {
+        let src = format!("{}\n  super(update_info(info, 'Name' => {}", src, tail);
+        extract_module(name, &path, &src);
+    }
+    // every prefix of a real module, cut anywhere
+    for end in (0..src.len()).filter(|&i| src.is_char_boundary(i)) {
+        extract_module(name, &path, &src[..end]);
+    }
+
+    let cut = src.find("        'Author'").unwrap();
+    let truncated = format!("{}'Description' => %q{{ cut off \\", &src[..cut]);
+    let details = extract_module(name, &path, &truncated).details();
+    assert_eq!(
+        details.description,
+        "VSFTPD v2.3.4 Backdoor Command Execution"
+    );
+}
@@ -0,0 +1,35 @@
+##
+# This module requires Metasploit: https://metasploit.com/download
+# Current source: https://github.com/rapid7/metasploit-framework
+##
+
+class MetasploitModule < Msf::Auxiliary
+  include Msf::Exploit::Remote::Tcp
+  include Msf::Auxiliary::Scanner
+  include Msf::Auxiliary::Report
+
+  def initialize
+    super(
+      'Name' => 'TCP Port Scanner',
+      'Description' => %q{
+        Enumerate open TCP services by performing a full TCP connect on each port.
+        This does not need administrative privileges on the source machine, which
+        may be useful if pivoting.
+      },
+      'Author' => [ 'hdm', 'kris katterjohn' ],
+      'License' => MSF_LICENSE
+    )
+
+    register_options(
+      [
+        OptString.new('PORTS', [true, 'Ports to scan (e.g. 22-25,80,110-900)', '1-10000']),
+        OptInt.new('TIMEOUT', [true, 'The socket connect timeout in milliseconds', 1000]),
+        OptInt.new('CONCURRENCY', [true, 'The number of concurrent ports to check per host', 10]),
+        OptInt.new('DELAY', [true, 'The delay between connections, per thread, in milliseconds', 0]),
+        OptInt.new('JITTER', [true, 'The delay jitter factor (maximum value by which to +/- DELAY) in milliseconds.', 0]),
+      ]
+    )
+
+    deregister_options('RPORT')
+  end
+end
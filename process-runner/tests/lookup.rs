@@ -0,0 +1,162 @@
+use create_options_json::lookup::{OptionsLookup, OptionsSource};
+use create_options_json::pool::ConsolePool;
+use create_options_json::process::Process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn replay_pool(size: usize, started: Arc<AtomicUsize>) -> ConsolePool {
+    ConsolePool::new("pool", size, move || {
+        started.fetch_add(1, Ordering::SeqCst);
+        Process::builder(env!("CARGO_BIN_EXE_create-options-json"))
+            .args(&["replay-console", &format!("{}/msfconsole.jsonl", FIXTURES)])
+    })
+}
+
+#[test]
+fn pool_starts_consoles_on_checkout_and_reuses_them() {
+    let started = Arc::new(AtomicUsize::new(0));
+    let pool = replay_pool(2, Arc::clone(&started));
+    assert_eq!(pool.size(), 2);
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    // checkouts go round the pool, starting each console once
+    let (first, second, third) = (pool.get(), pool.get(), pool.get());
+    assert_eq!((first.name(), second.name()), ("pool-0", "pool-1"));
+    assert!(Arc::ptr_eq(&first, &third));
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+
+    let output = pool
+        .call(|msf| {
+            msf.run_command("sessions -l");
+            msf.output().join("\n")
+        })
+        .unwrap();
+    assert!(output.contains("No active sessions."), "{}", output);
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+
+    // a console that exited is replaced on its next checkout
+    second.stop();
+    second.wait();
+    assert!(!second.is_running());
+    let (_, replaced) = (pool.get(), pool.get());
+    assert!(!Arc::ptr_eq(&second, &replaced));
+    assert_eq!(replaced.name(), "pool-1");
+    assert_eq!(started.load(Ordering::SeqCst), 3);
+
+    pool.shutdown();
+    assert!(!first.is_running() && !replaced.is_running());
+}
+
+#[test]
+fn pool_has_at_least_one_console() {
+    let pool = replay_pool(0, Arc::new(AtomicUsize::new(0)));
+    assert_eq!(pool.size(), 1);
+    assert!(Arc::ptr_eq(&pool.get(), &pool.get()));
+}
+
+#[test]
+fn lookup_prefers_a_fresh_catalog() {
+    let dir = std::env::temp_dir().join(format!("lookup-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let catalog = dir.join("catalog.json");
+    std::fs::copy(format!("{}/catalog.json", FIXTURES), &catalog).unwrap();
+    let started = Arc::new(AtomicUsize::new(0));
+    let pool = Arc::new(replay_pool(1, Arc::clone(&started)));
+    let lookup = OptionsLookup::new(&catalog, Duration::from_secs(60), Arc::clone(&pool));
+
+    // hit: answered from the catalog without starting a console
+    let hit = lookup
+        .lookup("exploit/windows/smb/ms17_010_eternalblue")
+        .unwrap();
+    assert_eq!(hit.source, OptionsSource::Catalog);
+    assert_eq!(hit.module.payload, "windows/x64/meterpreter/reverse_tcp");
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    // miss: asked of a pooled console
+    let miss = lookup
+        .lookup("exploit/unix/ftp/vsftpd_234_backdoor")
+        .unwrap();
+    assert_eq!(miss.source, OptionsSource::Live);
+    assert_eq!(miss.module.payload, "cmd/unix/interact");
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+
+    assert!(matches!(
+        lookup.lookup("exploit/x; irb"),
+        Err(e) if e.starts_with("Invalid module name")
+    ));
+
+    // the catalog is re-read when the file changes
+    std::fs::write(&catalog, "{}").unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&catalog)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(1))
+        .unwrap();
+    let reloaded = lookup.load_catalog().unwrap();
+    assert!(reloaded
+        .get("exploit/windows/smb/ms17_010_eternalblue")
+        .is_none());
+    pool.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn lookup_ignores_stale_or_missing_catalogs() {
+    let pool = Arc::new(replay_pool(1, Arc::new(AtomicUsize::new(0))));
+    let stale = OptionsLookup::new(
+        format!("{}/catalog.json", FIXTURES),
+        Duration::ZERO,
+        Arc::clone(&pool),
+    );
+    assert!(stale.load_catalog().is_some());
+    assert!(stale.catalog().is_none());
+    assert_eq!(
+        stale
+            .lookup("exploit/unix/ftp/vsftpd_234_backdoor")
+            .unwrap()
+            .source,
+        OptionsSource::Live
+    );
+
+    let missing = OptionsLookup::new("/nonexistent/catalog.json", Duration::MAX, pool);
+    assert!(missing.load_catalog().is_none());
+    assert!(missing.catalog().is_none());
+}
+
+#[test]
+fn live_lookups_are_cached_until_the_catalog_changes() {
+    let dir = std::env::temp_dir().join(format!("lookup-cache-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let catalog = dir.join("catalog.json");
+    std::fs::write(&catalog, "{}").unwrap();
+    let started = Arc::new(AtomicUsize::new(0));
+    let pool = Arc::new(replay_pool(1, Arc::clone(&started)));
+    let lookup = OptionsLookup::new(&catalog, Duration::from_secs(60), Arc::clone(&pool));
+    let name = "exploit/unix/ftp/vsftpd_234_backdoor";
+
+    assert_eq!(lookup.lookup(name).unwrap().source, OptionsSource::Live);
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+    // with the console gone, a second lookup would have to start another
+    pool.shutdown();
+    let cached = lookup.lookup(name).unwrap();
+    assert_eq!(cached.source, OptionsSource::Live);
+    assert_eq!(cached.module.payload, "cmd/unix/interact");
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+
+    // a new catalog drops the cached answers
+    std::fs::File::options()
+        .write(true)
+        .open(&catalog)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(1))
+        .unwrap();
+    lookup.lookup(name).unwrap();
+    assert_eq!(started.load(Ordering::SeqCst), 2);
+    pool.shutdown();
+    let _ = std::fs::remove_dir_all(&dir);
+}
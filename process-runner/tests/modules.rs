@@ -0,0 +1,279 @@
+use create_options_json::msf::{ExploitDetails, MSFProcess};
+use create_options_json::query::{ModuleQuery, SortKey};
+use create_options_json::rank::Rank;
+use serde_json::json;
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn details(name: &str, date: Option<&str>, rank: Rank, check: Option<bool>) -> ExploitDetails {
+    ExploitDetails {
+        name: name.to_string(),
+        disclosure_date: date.map(|d| d.parse().unwrap()),
+        rank: Some(rank),
+        check,
+        description: String::new(),
+    }
+}
+
+fn modules() -> Vec<ExploitDetails> {
+    vec![
+        details(
+            "exploit/windows/smb/ms17_010_eternalblue",
+            Some("2017-03-14"),
+            Rank::Average,
+            Some(true),
+        ),
+        details(
+            "exploit/windows/smb/psexec",
+            Some("1999-01-01"),
+            Rank::Manual,
+            Some(false),
+        ),
+        details(
+            "exploit/multi/http/struts2_content_type_ognl",
+            Some("2017-03-07"),
+            Rank::Excellent,
+            Some(true),
+        ),
+        details("exploit/multi/handler", None, Rank::Manual, None),
+        details(
+            "exploit/unix/ftp/vsftpd_234_backdoor",
+            Some("2011-07-03"),
+            Rank::Excellent,
+            Some(false),
+        ),
+    ]
+}
+
+fn names(modules: &[ExploitDetails]) -> Vec<&str> {
+    modules.iter().map(|m| m.name.as_str()).collect()
+}
+
+#[test]
+fn ranks_are_ordered_and_parsed_leniently() {
+    assert!(Rank::Manual < Rank::Low && Rank::Great < Rank::Excellent);
+    assert_eq!("Average".parse(), Ok(Rank::Average));
+    assert_eq!("ExcellentRanking".parse(), Ok(Rank::Excellent));
+    assert!("Yes".parse::<Rank>().is_err());
+    assert_eq!(Rank::default(), Rank::Normal);
+}
+
+#[test]
+fn reads_and_writes_the_existing_row_format() {
+    // rows as earlier versions wrote them
+    let old: Vec<ExploitDetails> = serde_json::from_value(json!([
+        {
+            "name": "exploit/windows/smb/ms17_010_eternalblue",
+            "disclosure_date": "2017-03-14",
+            "rank": "average",
+            "check": true,
+            "description": "MS17-010 EternalBlue"
+        },
+        {
+            "name": "exploit/multi/handler",
+            "disclosure_date": ".",
+            "rank": "Manual",
+            "check": false,
+            "description": "Generic Payload Handler"
+        },
+        {
+            "name": "exploit/aix/local/ibstat_path",
+            "disclosure_date": "2013-09-24",
+            "rank": "Yes",
+            "check": "Yes",
+            "description": "ibstat $PATH Privilege Escalation"
+        }
+    ]))
+    .unwrap();
+    assert_eq!(old[0].rank, Some(Rank::Average));
+    assert_eq!(old[1].disclosure_date, None);
+    assert_eq!(old[1].rank, Some(Rank::Manual));
+    assert_eq!(old[1].check, Some(false));
+    assert_eq!(old[2].rank, None);
+    assert_eq!(old[2].check, Some(true));
+
+    // and write them back unchanged, unknown values included
+    let rows = json!([
+        {
+            "name": "exploit/windows/smb/ms17_010_eternalblue",
+            "disclosure_date": "2017-03-14",
+            "rank": "average",
+            "check": true,
+            "description": "MS17-010 EternalBlue"
+        },
+        {
+            "name": "exploit/multi/handler",
+            "disclosure_date": ".",
+            "rank": "manual",
+            "check": false,
+            "description": "Generic Payload Handler"
+        }
+    ]);
+    let loaded: Vec<ExploitDetails> = serde_json::from_value(rows.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&loaded).unwrap(), rows);
+    assert_eq!(
+        serde_json::to_value(&old[2]).unwrap(),
+        json!({
+            "name": "exploit/aix/local/ibstat_path",
+            "disclosure_date": "2013-09-24",
+            "rank": "",
+            "check": true,
+            "description": "ibstat $PATH Privilege Escalation"
+        })
+    );
+
+    let missing: ExploitDetails =
+        serde_json::from_value(json!({"name": "exploit/x", "description": ""})).unwrap();
+    assert_eq!(
+        (missing.disclosure_date, missing.rank, missing.check),
+        (None, None, None)
+    );
+    let written = serde_json::to_value(&missing).unwrap();
+    assert_eq!(
+        (
+            &written["disclosure_date"],
+            &written["rank"],
+            &written["check"]
+        ),
+        (&json!("."), &json!(""), &json!(""))
+    );
+}
+
+#[test]
+fn unknown_values_survive_a_round_trip() {
+    let mut unknown = details("exploit/x", None, Rank::Normal, None);
+    unknown.rank = None;
+    let known = details("exploit/y", Some("2017-03-14"), Rank::Great, Some(false));
+    let text = serde_json::to_string(&[&unknown, &known]).unwrap();
+    let reloaded: Vec<ExploitDetails> = serde_json::from_str(&text).unwrap();
+    let fields: Vec<_> = reloaded
+        .iter()
+        .map(|d| (d.disclosure_date, d.rank, d.check))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            (None, None, None),
+            ("2017-03-14".parse().ok(), Some(Rank::Great), Some(false)),
+        ]
+    );
+    // and again, so nothing drifts after the first write
+    assert_eq!(serde_json::to_string(&reloaded).unwrap(), text);
+}
+
+#[test]
+fn filters_and_sorts_modules() {
+    let query = ModuleQuery {
+        min_rank: Some(Rank::Average),
+        check: Some(Some(true)),
+        sort: SortKey::Date,
+        reverse: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        names(&query.apply(modules())),
+        [
+            "exploit/windows/smb/ms17_010_eternalblue",
+            "exploit/multi/http/struts2_content_type_ognl"
+        ]
+    );
+
+    // modules without a date are left out of date ranges, and sort first
+    let query = ModuleQuery {
+        disclosed_since: "2011-01-01".parse().ok(),
+        disclosed_until: "2017-03-07".parse().ok(),
+        ..Default::default()
+    };
+    assert_eq!(
+        names(&query.apply(modules())),
+        [
+            "exploit/multi/http/struts2_content_type_ognl",
+            "exploit/unix/ftp/vsftpd_234_backdoor"
+        ]
+    );
+    let query = ModuleQuery {
+        sort: SortKey::Date,
+        ..Default::default()
+    };
+    assert_eq!(query.apply(modules())[0].name, "exploit/multi/handler");
+
+    // ties are broken by name
+    let query = ModuleQuery {
+        max_rank: Some(Rank::Manual),
+        sort: SortKey::Rank,
+        ..Default::default()
+    };
+    assert_eq!(
+        names(&query.apply(modules())),
+        ["exploit/multi/handler", "exploit/windows/smb/psexec"]
+    );
+    let query = ModuleQuery {
+        check: Some(None),
+        ..Default::default()
+    };
+    assert_eq!(names(&query.apply(modules())), ["exploit/multi/handler"]);
+}
+
+#[test]
+fn modules_command_filters_module_lists() {
+    let output = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .arg("modules")
+        .args(["--modules", &format!("{}/scraped/exploits.json", FIXTURES)])
+        .args(["--min-rank", "excellent", "--check", "yes"])
+        .args(["--sort", "date", "--reverse"])
+        .output()
+        .expect("Failed to run modules");
+    assert!(output.status.success());
+    let modules: Vec<ExploitDetails> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!modules.is_empty());
+    assert!(modules
+        .iter()
+        .all(|m| m.rank == Some(Rank::Excellent) && m.check == Some(true)));
+    assert!(modules
+        .windows(2)
+        .all(|w| w[0].disclosure_date >= w[1].disclosure_date));
+
+    let status = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .args(["modules", "--min-rank", "legendary"])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(2));
+}
+
+#[test]
+fn module_tables_end_at_the_first_blank_line() {
+    // `show exploits` as `run_command` returns it, with the hint and the
+    // ping echo after the table
+    let output = [
+        "",
+        "Exploits",
+        "========",
+        "",
+        "   #  Name                                     Disclosure Date  Rank       Check  Description",
+        "   -  ----                                     ---------------  ----       -----  -----------",
+        "   0  exploit/aix/local/ibstat_path            2013-09-24       excellent  Yes    ibstat $PATH Privilege Escalation",
+        "   1  exploit/aix/rpc_cmsd_opcode21            2009-10-07       great      No     AIX Calendar Manager Service Daemon (rpc.cmsd) Opcode 21 Buffer Overflow",
+        "",
+        "",
+        "Interact with a module by name or index. For example info 1, use 1 or use exploit/aix/rpc_cmsd_opcode21",
+        "",
+        "[*] exec: ping",
+        "",
+    ];
+    let output: Vec<String> = output.iter().map(|l| format!("{} ", l)).collect();
+    let modules = MSFProcess::extract_exploit_details(&output.join("\n"));
+    assert_eq!(
+        names(&modules),
+        [
+            "exploit/aix/local/ibstat_path",
+            "exploit/aix/rpc_cmsd_opcode21"
+        ]
+    );
+    assert_eq!(
+        modules[1].description,
+        "AIX Calendar Manager Service Daemon (rpc.cmsd) Opcode 21 Buffer Overflow"
+    );
+    assert_eq!(modules[1].check, Some(false));
+}
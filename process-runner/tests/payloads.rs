@@ -0,0 +1,125 @@
+use create_options_json::catalog::{Catalog, CatalogEntry, PayloadCatalog, PAYLOADS_FILE};
+use create_options_json::kind::OptionKind;
+use create_options_json::msf::Parameter;
+
+fn option(name: &str, default: &str, description: &str) -> Parameter {
+    Parameter::new(
+        name.to_string(),
+        Some(default.to_string()),
+        true,
+        description.to_string(),
+    )
+}
+
+fn reverse_tcp(exitfunc: &str) -> Vec<Parameter> {
+    vec![
+        option(
+            "EXITFUNC",
+            exitfunc,
+            "Exit technique (Accepted: '', seh, thread, process, none)",
+        ),
+        option(
+            "LHOST",
+            "${LOCAL_IP}",
+            "The listen address (an interface may be specified)",
+        ),
+        option("LPORT", "4444", "The listen port"),
+    ]
+}
+
+fn payloads() -> PayloadCatalog {
+    PayloadCatalog::from([(
+        "windows/x64/meterpreter/reverse_tcp".to_string(),
+        reverse_tcp("process"),
+    )])
+}
+
+fn entry(payload_options: Vec<Parameter>) -> CatalogEntry {
+    CatalogEntry {
+        payload: "windows/x64/meterpreter/reverse_tcp".to_string(),
+        payload_options: Some(payload_options),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn refers_to_payload_schema_with_overrides() {
+    let payloads = payloads();
+    let schema = &payloads["windows/x64/meterpreter/reverse_tcp"];
+
+    // eternalblue sets EXITFUNC to thread
+    let mut eternalblue = entry(reverse_tcp("thread"));
+    eternalblue.reference_payload(schema);
+    assert!(eternalblue.payload_options.is_none());
+    let json = serde_json::to_value(&eternalblue).unwrap();
+    assert_eq!(json["payload_overrides"]["EXITFUNC"], "thread");
+    assert_eq!(json["payload_overrides"].as_object().unwrap().len(), 1);
+
+    let mut same = entry(reverse_tcp("process"));
+    same.reference_payload(schema);
+    assert!(same.payload_options.is_none());
+    assert!(serde_json::to_value(&same)
+        .unwrap()
+        .get("payload_overrides")
+        .is_none());
+
+    eternalblue.resolve_payload(&payloads);
+    let options = eternalblue.payload_options.unwrap();
+    assert_eq!(options.len(), 3);
+    assert_eq!(options[0].default_value.as_deref(), Some("thread"));
+    assert_eq!(options[2].default_value.as_deref(), Some("4444"));
+
+    // an extra option means a different schema, so it stays inline
+    let mut custom = reverse_tcp("process");
+    custom.push(option(
+        "AutoLoadStdapi",
+        "true",
+        "Automatically load stdapi",
+    ));
+    let mut inline = entry(custom);
+    inline.reference_payload(schema);
+    assert_eq!(inline.payload_options.unwrap().len(), 4);
+}
+
+#[test]
+fn catalog_load_resolves_payload_references() {
+    let dir = std::env::temp_dir().join(format!("payloads-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let payloads = payloads();
+    let mut eternalblue = entry(reverse_tcp("thread"));
+    eternalblue.reference_payload(&payloads["windows/x64/meterpreter/reverse_tcp"]);
+    let mut unknown = entry(reverse_tcp("thread"));
+    unknown.payload = "windows/shell_reverse_tcp".to_string();
+    unknown.payload_options = None;
+    let catalog = std::collections::BTreeMap::from([
+        ("exploit/windows/smb/ms17_010_eternalblue", eternalblue),
+        ("exploit/windows/smb/psexec", unknown),
+    ]);
+    std::fs::write(
+        dir.join("exploits_options.json"),
+        serde_json::to_string(&catalog).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join(PAYLOADS_FILE),
+        serde_json::to_string(&payloads).unwrap(),
+    )
+    .unwrap();
+
+    let catalog = Catalog::load(dir.join("exploits_options.json")).unwrap();
+    let exploit = catalog
+        .get("exploit/windows/smb/ms17_010_eternalblue")
+        .unwrap();
+    let options = exploit.payload_options.unwrap();
+    let exitfunc = &options[0];
+    assert_eq!(exitfunc.default_value.as_deref(), Some("thread"));
+    assert_eq!(exitfunc.kind(), OptionKind::Enum);
+    assert_eq!(options[1].kind(), OptionKind::Address);
+    // payloads missing from the payload catalog have no options
+    assert!(catalog
+        .get("exploit/windows/smb/psexec")
+        .unwrap()
+        .payload_options
+        .is_none());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,114 @@
+use create_options_json::policy::{Policy, Profile};
+
+fn allowed(policy: &Policy, command: &str) -> bool {
+    policy.check(command).is_ok()
+}
+
+#[test]
+fn read_only_allows_reading_only() {
+    let policy = Policy::new("read-only", Profile::read_only());
+    for command in [
+        "show options",
+        "search type:exploit smb",
+        "info exploit/windows/smb/psexec",
+        "use exploit/windows/smb/psexec",
+        "back",
+        "sessions",
+        "sessions -l",
+        "jobs -l",
+        "SHOW exploits",
+    ] {
+        assert!(allowed(&policy, command), "{}", command);
+    }
+    for command in [
+        "irb",
+        "load sounds",
+        "db_nmap 10.0.0.1",
+        "set RHOSTS 10.0.0.1",
+        "run",
+        "sessions -i 1",
+        "jobs -k 1",
+        "ls /",
+        "show options; irb",
+    ] {
+        let err = policy.check(command).unwrap_err();
+        assert!(err.contains("not allowed for role read-only"), "{}", err);
+    }
+}
+
+#[test]
+fn scanning_only_loads_scanner_and_gather_modules() {
+    let policy = Policy::new("scanning", Profile::scanning());
+    for command in [
+        "use auxiliary/scanner/portscan/tcp",
+        "use auxiliary/gather/search_email_collector",
+        "set RHOSTS 10.0.0.0/24",
+        "setg THREADS 10",
+        "run",
+        "check",
+        "jobs -k 1",
+        "info exploit/windows/smb/psexec",
+    ] {
+        assert!(allowed(&policy, command), "{}", command);
+    }
+    for command in [
+        "use exploit/windows/smb/psexec",
+        "use auxiliary/admin/smb/psexec_ntdsgrab",
+        "sessions -i 1",
+        "irb",
+    ] {
+        assert!(!allowed(&policy, command), "{}", command);
+    }
+}
+
+#[test]
+fn full_allows_everything_but_line_breaks() {
+    let policy = Policy::new("full", Profile::full());
+    for command in ["irb", "exploit -j", "sessions -i 1"] {
+        assert!(allowed(&policy, command), "{}", command);
+    }
+    assert!(!allowed(&policy, "show options\nirb"));
+}
+
+#[test]
+fn refuses_commands_hidden_after_a_line_break() {
+    for policy in [
+        Policy::new("read-only", Profile::read_only()),
+        Policy::new("scanning", Profile::scanning()),
+    ] {
+        for command in [
+            "show options\nirb",
+            "show options\r\nload sounds",
+            "info\rsessions -i 1",
+        ] {
+            let err = policy.check(command).unwrap_err();
+            assert!(err.contains("line break"), "{}: {}", command, err);
+        }
+    }
+}
+
+#[test]
+fn custom_profiles_apply_deny_rules_first() {
+    let dir = std::env::temp_dir().join(format!("policy-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("profiles.json");
+    std::fs::write(
+        &path,
+        r#"{"profiles": {"triage": {
+            "allow": [{"verb": "*"}],
+            "deny": [{"verb": "irb"}, {"verb": "use", "pattern": "^use\\s+exploit/"}]
+        }}}"#,
+    )
+    .unwrap();
+    let policy = Policy::for_role("triage", Some(&path)).unwrap();
+    assert!(allowed(&policy, "use auxiliary/scanner/portscan/tcp"));
+    assert!(policy
+        .check("use exploit/windows/smb/psexec")
+        .unwrap_err()
+        .contains("is denied for role triage"));
+    assert!(!allowed(&policy, "irb"));
+    // built-in roles are still available next to custom ones
+    assert!(Policy::for_role("scanning", Some(&path)).is_ok());
+    assert!(Policy::for_role("admin", Some(&path)).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+}
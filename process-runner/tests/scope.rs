@@ -0,0 +1,107 @@
+use create_options_json::msf::MSFProcess;
+use create_options_json::process::Process;
+use create_options_json::scope::{Scope, ScopeGuard};
+use serde_json::Value;
+use std::sync::Arc;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn scope() -> Scope {
+    Scope {
+        networks: vec!["10.0.0.0/24".parse().unwrap()],
+        hostnames: vec!["*.lab.example".to_string()],
+        ports: vec![(1, 1024)],
+    }
+}
+
+#[test]
+fn refuses_targets_hidden_after_a_line_break() {
+    let scope = scope();
+    assert!(scope.check_command("set RHOSTS 10.0.0.5").is_ok());
+    assert!(scope
+        .check_command("show options; set RHOSTS 8.8.8.8")
+        .is_err());
+    for line in [
+        "show options\nset RHOSTS 8.8.8.8",
+        "show options\r\nrun RHOSTS=8.8.8.8",
+        "set RHOSTS 10.0.0.5\rset RHOSTS 8.8.8.8",
+    ] {
+        let err = scope.check_command(line).unwrap_err();
+        assert!(err.contains("line break"), "{}", err);
+    }
+}
+
+#[test]
+fn checks_positional_run_targets() {
+    let scope = scope();
+    for line in [
+        "run 10.0.0.1",
+        "exploit -j -z 10.0.0.1 10.0.0.2",
+        "check 10.0.0.0/28",
+        "run -p windows/meterpreter/reverse_tcp -t 0 10.0.0.1",
+        "run -o RHOSTS=10.0.0.1,RPORT=80",
+        "exploit http://10.0.0.1:80/login?next=/",
+        "run https://host.lab.example/",
+    ] {
+        assert!(scope.check_command(line).is_ok(), "{}", line);
+    }
+    for line in [
+        "run 8.8.8.8",
+        "exploit http://8.8.8.8/",
+        "exploit http://10.0.0.1:8080/",
+        "exploit http://admin@8.8.8.8/?a=b",
+        "run http://[2001:db8::1]/",
+        "rexploit -j 10.0.0.1 8.8.8.8",
+        "run -o RPORT=80,RHOSTS=8.8.8.8",
+        "run example.com",
+        "set RHOSTS http://8.8.8.8/",
+    ] {
+        assert!(scope.check_command(line).is_err(), "{}", line);
+    }
+}
+
+#[test]
+fn checks_connect_and_refuses_unchecked_tools() {
+    let scope = scope();
+    assert!(scope.check_command("connect 10.0.0.1 22").is_ok());
+    assert!(scope.check_command("connect -s -w 5 10.0.0.1 443").is_ok());
+    assert!(scope.check_command("connect 8.8.8.8 22").is_err());
+    assert!(scope.check_command("connect -w 5 10.0.0.1 8080").is_err());
+    for line in [
+        "db_nmap -sV 8.8.8.8",
+        "db_nmap 10.0.0.1",
+        "nmap -p 80 8.8.8.8",
+        "ping -c 1 8.8.8.8",
+        "curl http://8.8.8.8/",
+    ] {
+        let err = scope.check_command(line).unwrap_err();
+        assert!(err.contains("cannot be checked"), "{}", err);
+    }
+    // the bare ping msfconsole is sent to find the end of output
+    assert!(scope.check_command("ping").is_ok());
+}
+
+#[test]
+fn console_refuses_and_audits_commands_with_line_breaks() {
+    let dir = std::env::temp_dir().join(format!("scope-newline-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let audit = dir.join("audit.jsonl");
+    let builder = Process::builder(env!("CARGO_BIN_EXE_create-options-json"))
+        .args(&["replay-console", &format!("{}/msfconsole.jsonl", FIXTURES)])
+        .scope_guard(Arc::new(ScopeGuard::new(scope(), &audit)));
+    let mut msf = MSFProcess::try_with_builder(builder).unwrap();
+
+    let command = "show options\nset RHOSTS 8.8.8.8";
+    let result = msf.run_command(command);
+    assert!(result.refused.unwrap().contains("line break"));
+    assert!(msf.output()[0].starts_with("[-] Refused by console"));
+
+    let log = std::fs::read_to_string(&audit).unwrap();
+    let records: Vec<Value> = log
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["command"], command);
+    let _ = std::fs::remove_dir_all(&dir);
+}
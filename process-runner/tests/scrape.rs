@@ -0,0 +1,58 @@
+use std::os::unix::fs::PermissionsExt;
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const BIN: &str = env!("CARGO_BIN_EXE_create-options-json");
+
+fn scrape(args: &[&str]) -> Command {
+    let mut command = Command::new(BIN);
+    command.args(args).env("RUST_LOG", "off");
+    command
+}
+
+#[test]
+fn rejects_unknown_options() {
+    let output = scrape(&["--canonicl"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Usage: create-options-json"), "{}", stderr);
+
+    let output = scrape(&["two"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn write_errors_are_returned_not_panics() {
+    // msfconsole is the replay backend, and exploits.json cannot be created
+    let dir = std::env::temp_dir().join(format!("scrape-test-{}", std::process::id()));
+    let bin = dir.join("bin");
+    let out = dir.join("out");
+    std::fs::create_dir_all(&bin).unwrap();
+    std::fs::create_dir_all(out.join("exploits.json")).unwrap();
+    let msfconsole = bin.join("msfconsole");
+    std::fs::write(
+        &msfconsole,
+        format!(
+            "#!/bin/sh\nexec '{}' replay-console '{}/msfconsole.jsonl'\n",
+            BIN, FIXTURES
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&msfconsole, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        bin.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+
+    let output = scrape(&["1", "1", "--canonical"])
+        .current_dir(&out)
+        .env("PATH", path)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr);
+    assert!(!stderr.contains("panicked"), "{}", stderr);
+    assert!(stderr.contains("Is a directory"), "{}", stderr);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,282 @@
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+use tungstenite::Message;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+/// `serve` running against the replay backend.
+struct Server {
+    child: Child,
+    http: u16,
+    ws: u16,
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+impl Server {
+    fn start(extra_args: &[&str], env: &[(&str, &Path)]) -> Self {
+        let (http, ws) = (free_port(), free_port());
+        let child = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+            .arg("serve")
+            .args(["--replay", &format!("{}/msfconsole.jsonl", FIXTURES)])
+            .args(["--catalog", &format!("{}/catalog.json", FIXTURES)])
+            .args(["--http", &format!("127.0.0.1:{}", http)])
+            .args(["--ws", &format!("127.0.0.1:{}", ws)])
+            .args(extra_args)
+            .envs(env.iter().copied())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Failed to start server");
+        let server = Self { child, http, ws };
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while TcpStream::connect(("127.0.0.1", http)).is_err() {
+            assert!(Instant::now() < deadline, "Server did not start");
+            thread::sleep(Duration::from_millis(50));
+        }
+        server
+    }
+
+    /// Sends one request and returns the status and body.
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.http)).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(30)))
+            .unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n\
+             Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            method,
+            path,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+        (status, body.to_string())
+    }
+
+    fn post(&self, path: &str, body: Value) -> (u16, String) {
+        self.request("POST", path, &body.to_string())
+    }
+
+    fn start_console(&self, source: &str) {
+        let (status, body) =
+            self.post("/start", json!({"source": source, "command": "msfconsole"}));
+        assert_eq!(
+            (status, body.as_str()),
+            (200, &*format!("{}: Metasploit process starting...", source))
+        );
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn start_command_stop() {
+    let server = Server::start(&[], &[]);
+    assert_eq!(
+        server.post("/command", json!({"source": "a", "command": "sessions -l"})),
+        (400, "a: Metasploit is not running".to_string())
+    );
+    server.start_console("a");
+    assert_eq!(
+        server.post("/start", json!({"source": "a", "command": "msfconsole"})),
+        (500, "a: Metasploit is already running".to_string())
+    );
+    assert_eq!(
+        server.post("/start", json!({"source": "b", "command": "bash"})),
+        (400, "Please enter a correct command.".to_string())
+    );
+
+    let (status, body) = server.request("GET", "/processes", "");
+    assert_eq!(status, 200);
+    let body: Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(body["Active Running Processes"], json!(["a"]));
+
+    assert_eq!(
+        server.post("/command", json!({"source": "a", "command": "sessions -l"})),
+        (200, "Success".to_string())
+    );
+    assert_eq!(
+        server.post(
+            "/command",
+            json!({"source": "a", "command": "show options\nirb"})
+        ),
+        (
+            400,
+            "Command contains a line break: \"show options\\nirb\"".to_string()
+        )
+    );
+    assert_eq!(
+        server.post("/command", json!({"source": "a"})),
+        (400, "No Source or Command".to_string())
+    );
+
+    assert_eq!(
+        server.post("/stop", json!({"source": "a"})),
+        (200, "a: Metasploit stopped".to_string())
+    );
+    assert_eq!(
+        server.post("/stop", json!({"source": "a"})),
+        (400, "a: Metasploit is not running".to_string())
+    );
+}
+
+#[test]
+fn rejects_unknown_routes_and_bad_bodies() {
+    let server = Server::start(&[], &[]);
+    assert_eq!(
+        server.request("GET", "/nope", ""),
+        (404, "Cannot GET /nope".to_string())
+    );
+    let (status, body) = server.request("POST", "/command", "{not json");
+    assert_eq!(status, 400);
+    assert!(body.starts_with("Invalid JSON body"), "{}", body);
+    assert_eq!(
+        server.post("/module-options", json!({})),
+        (400, "No module name provided".to_string())
+    );
+}
+
+#[test]
+fn streams_command_output_to_websockets() {
+    let server = Server::start(&["--role", "full"], &[]);
+    server.start_console("a");
+    let (mut socket, _) =
+        tungstenite::connect(format!("ws://127.0.0.1:{}/?source=a", server.ws)).unwrap();
+    // the server registers the client after the handshake
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(
+        server.post("/command", json!({"source": "a", "command": "sessions -l"})),
+        (200, "Success".to_string())
+    );
+
+    let mut read_output = |command: &str| loop {
+        let Message::Text(text) = socket.read().unwrap() else {
+            continue;
+        };
+        let message: Value = serde_json::from_str(&text).unwrap();
+        if message["command"] == command {
+            break message;
+        }
+    };
+    let message = read_output("sessions -l");
+    assert_eq!(message["type"], "output");
+    assert_eq!(message["source"], "a");
+    assert!(message["output"]
+        .as_str()
+        .unwrap()
+        .contains("No active sessions."));
+    assert!(message["result"]["refused"].is_null());
+
+    // the echo of a ping is not sent as an event
+    assert_eq!(
+        server.post("/command", json!({"source": "a", "command": "ping"})),
+        (200, "Success".to_string())
+    );
+    let message = read_output("ping");
+    assert!(message["output"]
+        .as_str()
+        .unwrap()
+        .contains("[*] exec: ping"));
+    assert_eq!(message["events"], json!([]));
+}
+
+#[test]
+fn policy_refuses_commands_outside_role() {
+    // without --role or PROCESS_RUNNER_ROLE, consoles are read-only
+    let server = Server::start(&[], &[]);
+    server.start_console("a");
+    for command in ["irb", "load msgrpc", "shell"] {
+        let (status, body) = server.post("/command", json!({"source": "a", "command": command}));
+        assert_eq!(status, 403, "{}", body);
+        assert!(body.contains("role read-only"), "{}", body);
+    }
+    let (status, body) = server.post(
+        "/radial-command",
+        json!({
+            "source": "a",
+            "radialCommandData": {
+                "selectedExploit": "0 exploit/unix/ftp/vsftpd_234_backdoor",
+                "destinationIpAddr": "10.0.0.5"
+            }
+        }),
+    );
+    assert_eq!(status, 403, "{}", body);
+
+    let server = Server::start(&["--role", "full"], &[]);
+    server.start_console("a");
+    assert_eq!(
+        server.post("/command", json!({"source": "a", "command": "irb"})),
+        (200, "Success".to_string())
+    );
+}
+
+#[test]
+fn radial_command_reports_scope_refusals() {
+    let dir = std::env::temp_dir().join(format!("server-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let scope = dir.join("scope.json");
+    let audit = dir.join("audit.jsonl");
+    std::fs::write(&scope, r#"{"networks": ["10.0.0.0/24"]}"#).unwrap();
+    let server = Server::start(
+        &["--role", "full"],
+        &[
+            ("PROCESS_RUNNER_SCOPE", &scope),
+            ("PROCESS_RUNNER_SCOPE_AUDIT", &audit),
+        ],
+    );
+    server.start_console("a");
+    let radial = |rhost: &str| {
+        server.post(
+            "/radial-command",
+            json!({
+                "source": "a",
+                "radialCommandData": {
+                    "selectedExploit": "0 exploit/unix/ftp/vsftpd_234_backdoor",
+                    "destinationIpAddr": rhost
+                }
+            }),
+        )
+    };
+
+    assert_eq!(radial("10.0.0.5"), (200, "Success".to_string()));
+    let (status, body) = radial("192.168.1.5");
+    assert_eq!(status, 403);
+    assert!(body.contains("192.168.1.5 is out of scope"), "{}", body);
+
+    // `exploit` is not sent after the refused `set RHOST`
+    let log = std::fs::read_to_string(&audit).unwrap();
+    let commands: Vec<String> = log
+        .lines()
+        .map(|l| {
+            serde_json::from_str::<Value>(l).unwrap()["command"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(commands, ["set RHOST 192.168.1.5"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,161 @@
+use create_options_json::msf::{Exploit, MSFProcess};
+
+/// `show options` for vsftpd_234_backdoor, as msfconsole prints it.
+const SHOW_OPTIONS: [&str; 29] = [
+    "",
+    "Module options (exploit/unix/ftp/vsftpd_234_backdoor):",
+    "",
+    "   Name     Current Setting  Required  Description",
+    "   ----     ---------------  --------  -----------",
+    "   CHOST                     no        The local client address",
+    "   CPORT                     no        The local client port",
+    "   RHOSTS                    yes       The target host(s), see https://docs.metasploit.com/docs/using-metasploit/basics/using-metasploit.html",
+    "   RPORT    21               yes       The target port (TCP)",
+    "",
+    "",
+    "Payload options (cmd/unix/interact):",
+    "",
+    "   Name  Current Setting  Required  Description",
+    "   ----  ---------------  --------  -----------",
+    "",
+    "",
+    "Exploit target:",
+    "",
+    "   Id  Name",
+    "   --  ----",
+    "   0   Automatic",
+    "",
+    "",
+    "",
+    "View the full module info with the info, or info -d command.",
+    "",
+    "[*] exec: ping",
+    "",
+];
+
+fn exploit() -> Exploit {
+    Exploit {
+        name: "exploit/unix/ftp/vsftpd_234_backdoor".to_string(),
+        payload: String::new(),
+        payload_options: None,
+        options: None,
+        target: None,
+    }
+}
+
+fn check_vsftpd(exploit: &Exploit) {
+    let options = exploit.options.as_ref().unwrap();
+    let names: Vec<&str> = options.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["CHOST", "CPORT", "RHOSTS", "RPORT"]);
+    assert_eq!(options[3].default_value.as_deref(), Some("21"));
+    assert!(options[3].required);
+    assert_eq!(options[3].description, "The target port (TCP)");
+    assert_eq!(options[0].default_value, None);
+    assert_eq!(exploit.payload, "cmd/unix/interact");
+    assert!(exploit.payload_options.is_none());
+    // the hint after three blank lines is not part of the target table
+    assert_eq!(
+        exploit.target,
+        Some(vec!["0".to_string(), "Automatic".to_string()])
+    );
+}
+
+#[test]
+fn parses_console_output_with_trailing_spaces() {
+    // `run_command` appends a space to every line it reads, so blank lines
+    // arrive as " " and the output ends with the echo of the ping
+    let output: Vec<String> = SHOW_OPTIONS.iter().map(|l| format!("{} ", l)).collect();
+    let mut exploit = exploit();
+    MSFProcess::parse_show_options(&mut exploit, &output.join("\n"));
+    check_vsftpd(&exploit);
+}
+
+#[test]
+fn parses_output_without_end_marker() {
+    // as recorded in transcripts or saved from a terminal: no trailing
+    // spaces, and nothing after the last table
+    let mut exploit = exploit();
+    MSFProcess::parse_show_options(&mut exploit, &SHOW_OPTIONS[..22].join("\n"));
+    check_vsftpd(&exploit);
+}
+
+#[test]
+fn table_without_title_does_not_panic() {
+    let mut exploit = exploit();
+    MSFProcess::parse_show_options(
+        &mut exploit,
+        "   ----     ---------------  --------  -----------\n   RPORT    21  yes  The target port",
+    );
+    assert!(exploit.options.is_none());
+    MSFProcess::parse_show_options(&mut exploit, "");
+    assert!(exploit.target.is_none());
+}
+
+/// `show options` for ms17_010_eternalblue from a console that prints no
+/// hint after the tables, as `run_command` returns it: a space after every
+/// line and the echo of the ping right after the last table.
+const ETERNALBLUE: &str = "
+Module options (exploit/windows/smb/ms17_010_eternalblue):
+
+   Name           Current Setting  Required  Description
+   ----           ---------------  --------  -----------
+   RHOSTS                          yes       The target host(s), see https://docs.metasploit.com/docs/using-metasploit/basics/using-metasploit.html
+   RPORT          445              yes       The target port (TCP)
+   SMBDomain                       no        (Optional) The Windows domain to use for authentication. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.
+   SMBPass                         no        (Optional) The password for the specified username
+   SMBUser                         no        (Optional) The username to authenticate as
+   VERIFY_ARCH    true             yes       Check if remote architecture matches exploit Target. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.
+   VERIFY_TARGET  true             yes       Check if remote OS matches exploit Target. Only affects Windows Server 2008 R2, Windows 7, Windows Embedded Standard 7 target machines.
+
+
+Payload options (windows/x64/meterpreter/reverse_tcp):
+
+   Name      Current Setting  Required  Description
+   ----      ---------------  --------  -----------
+   EXITFUNC  thread           yes       Exit technique (Accepted: '', seh, thread, process, none)
+   LHOST     192.168.1.10     yes       The listen address (an interface may be specified)
+   LPORT     4444             yes       The listen port
+
+
+Exploit target:
+
+   Id  Name
+   --  ----
+   0   Automatic Target
+
+[*] exec: ping
+";
+
+#[test]
+fn splits_every_table_of_a_module_with_payload_options() {
+    let output: Vec<String> = ETERNALBLUE.lines().map(|l| format!("{} ", l)).collect();
+    let mut exploit = exploit();
+    MSFProcess::parse_show_options(&mut exploit, &output.join("\n"));
+
+    let options = exploit.options.unwrap();
+    assert_eq!(options.len(), 7);
+    assert_eq!(options[6].name, "VERIFY_TARGET");
+    assert_eq!(options[6].default_value.as_deref(), Some("true"));
+    assert!(options[6].description.ends_with("target machines."));
+    assert_eq!(exploit.payload, "windows/x64/meterpreter/reverse_tcp");
+    let payload_options = exploit.payload_options.unwrap();
+    let names: Vec<&str> = payload_options.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["EXITFUNC", "LHOST", "LPORT"]);
+    // cells are trimmed, so the default has no trailing space
+    assert_eq!(payload_options[2].default_value.as_deref(), Some("4444"));
+    // only the ping echo is dropped from the table left open at the end
+    assert_eq!(
+        exploit.target,
+        Some(vec!["0".to_string(), "Automatic Target".to_string()])
+    );
+}
+
+#[test]
+fn tables_end_at_two_or_more_blank_lines() {
+    // three blank lines between tables, as some consoles print them
+    let output = ETERNALBLUE.replace("\n\n\nPayload options", "\n\n\n\nPayload options");
+    let mut exploit = exploit();
+    MSFProcess::parse_show_options(&mut exploit, &output);
+    assert_eq!(exploit.options.unwrap().len(), 7);
+    assert_eq!(exploit.payload_options.unwrap().len(), 3);
+}
@@ -0,0 +1,62 @@
+use create_options_json::buffer::{BufferLimits, OverflowPolicy};
+use create_options_json::process::Process;
+
+/// Reads stdout until the process has written `lines` lines.
+fn read_lines(process: &mut Process, lines: usize) -> Vec<String> {
+    let mut output = Vec::new();
+    while output.len() < lines {
+        let text = process.read();
+        output.extend(text.lines().map(|l| l.trim().to_string()));
+    }
+    output
+}
+
+#[test]
+fn applies_resource_limits_and_nice() {
+    let mut process = Process::builder("sh")
+        .args(&["-c", "ulimit -n; ulimit -t; ulimit -v; nice"])
+        .open_files_limit(64)
+        .cpu_time_limit(30)
+        .memory_limit(1024 * 1024 * 1024)
+        .nice(5)
+        .spawn()
+        .unwrap();
+    process.start_reader();
+    assert_eq!(read_lines(&mut process, 4), ["64", "30", "1048576", "5"]);
+}
+
+// The only test that touches the environment, so the variables are not seen
+// by anything else in this binary.
+#[test]
+fn from_env_only_overrides_what_is_set() {
+    let limits = Process::builder("sh")
+        .buffer_limits(BufferLimits::bounded(1024, OverflowPolicy::Block))
+        .from_env();
+    let limits = limits.get_buffer_limits();
+    assert_eq!(limits.max_bytes, Some(1024));
+    assert!(matches!(limits.policy, OverflowPolicy::Block));
+
+    let dir = std::env::temp_dir().join(format!("spawn-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("PROCESS_RUNNER_BUFFER_POLICY", "drop-oldest");
+    std::env::set_var("PROCESS_RUNNER_CWD", &dir);
+    std::env::set_var("PROCESS_RUNNER_NICE", "3");
+    let builder = Process::builder("sh")
+        .args(&["-c", "pwd; nice"])
+        .buffer_limits(BufferLimits::bounded(1024, OverflowPolicy::Block))
+        .from_env();
+    std::env::remove_var("PROCESS_RUNNER_BUFFER_POLICY");
+    std::env::remove_var("PROCESS_RUNNER_CWD");
+    std::env::remove_var("PROCESS_RUNNER_NICE");
+
+    let limits = builder.get_buffer_limits();
+    assert_eq!(limits.max_bytes, Some(1024));
+    assert!(matches!(limits.policy, OverflowPolicy::DropOldest));
+
+    let mut process = builder.spawn().unwrap();
+    process.start_reader();
+    let output = read_lines(&mut process, 2);
+    assert_eq!(output[0], dir.canonicalize().unwrap().to_str().unwrap());
+    assert_eq!(output[1], "3");
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,165 @@
+use create_options_json::replay::{serve_replay, ReplayConsole};
+use create_options_json::transcript::{
+    read_transcript, render, Stream, Transcript, TranscriptRecord,
+};
+use std::path::{Path, PathBuf};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("transcript-test-{}-{}", std::process::id(), name))
+}
+
+fn record(seq: u64, mono_us: u64, stream: Stream, text: &str) -> TranscriptRecord {
+    TranscriptRecord {
+        seq,
+        mono_us,
+        wall: "2026-10-18T12:00:01.250Z".parse().unwrap(),
+        stream,
+        text: text.to_string(),
+    }
+}
+
+#[test]
+fn writes_and_reads_back_records() {
+    let path = temp_path("roundtrip.jsonl");
+    let transcript = Transcript::create(&path).unwrap();
+    assert_eq!(transcript.path(), path);
+    let written = [
+        (Stream::Meta, "started msfconsole (pid 1)"),
+        (Stream::Stdin, "set PASSWORD \"a b\""),
+        (Stream::Stdout, "PASSWORD => a b"),
+        (Stream::Stdout, ""),
+        (Stream::Stderr, "warning: \u{1b}[33mcolour\u{1b}[0m ✓"),
+    ];
+    for (stream, text) in written {
+        transcript.record(stream, text);
+    }
+
+    // one JSON object per line, flushed as it is recorded
+    let file = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(file.lines().count(), written.len());
+    let records = read_transcript(&path).unwrap();
+    let read: Vec<(Stream, &str)> = records
+        .iter()
+        .map(|r| (r.stream, r.text.as_str()))
+        .collect();
+    assert_eq!(read, written);
+    let seqs: Vec<u64> = records.iter().map(|r| r.seq).collect();
+    assert_eq!(seqs, [0, 1, 2, 3, 4]);
+    assert!(records.windows(2).all(|w| w[0].mono_us <= w[1].mono_us));
+    assert!(records.windows(2).all(|w| w[0].wall <= w[1].wall));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn reading_orders_by_seq_and_reports_bad_lines() {
+    let path = temp_path("unordered.jsonl");
+    let lines: Vec<String> = [
+        record(1, 20, Stream::Stdout, "second"),
+        record(0, 10, Stream::Stdin, "first"),
+    ]
+    .iter()
+    .map(|r| serde_json::to_string(r).unwrap())
+    .collect();
+    std::fs::write(&path, format!("{}\n\n{}\n", lines[0], lines[1])).unwrap();
+    let texts: Vec<String> = read_transcript(&path)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.text)
+        .collect();
+    assert_eq!(texts, ["first", "second"]);
+
+    std::fs::write(&path, format!("{}\n{{\"seq\": 1}}\n", lines[0])).unwrap();
+    let e = read_transcript(&path).unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+    assert!(
+        e.to_string().starts_with(&format!("{}:2:", path.display())),
+        "{}",
+        e
+    );
+    assert!(read_transcript(Path::new("/nonexistent/transcript.jsonl")).is_err());
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn renders_a_console_log() {
+    colored::control::set_override(false);
+    let records = [
+        record(0, 1_500, Stream::Meta, "started msfconsole (pid 1)"),
+        record(1, 250_000, Stream::Stdin, "sessions -l"),
+        record(2, 1_250_000, Stream::Stdout, "No active sessions."),
+        record(3, 12_345_678, Stream::Stderr, "ping: usage error"),
+    ];
+    let mut out = Vec::new();
+    render(&records, &mut out, false).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[+     0.002s] # started msfconsole (pid 1)\n\
+         [+     0.250s] > sessions -l\n\
+         [+     1.250s]   No active sessions.\n\
+         [+    12.346s] ! ping: usage error\n"
+    );
+
+    let mut out = Vec::new();
+    render(&records[1..2], &mut out, true).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[2026-10-18 12:00:01.250] > sessions -l\n"
+    );
+}
+
+#[test]
+fn replays_a_recorded_console() {
+    let records = read_transcript(&Path::new(FIXTURES).join("msfconsole.jsonl")).unwrap();
+    let mut console = ReplayConsole::new(records);
+    let startup = console.startup();
+    assert!(startup.iter().all(|r| r.stream != Stream::Meta));
+    assert!(startup[1].text.contains("metasploit v6.4.43-dev"));
+
+    let texts = |records: Vec<TranscriptRecord>| -> Vec<String> {
+        records.into_iter().map(|r| r.text).collect()
+    };
+    assert_eq!(
+        texts(console.respond("use exploit/unix/ftp/vsftpd_234_backdoor")),
+        ["[*] No payload configured, defaulting to cmd/unix/interact"]
+    );
+    assert_eq!(
+        texts(console.respond("ping")),
+        [
+            "[*] exec: ping",
+            "",
+            "ping: usage error: Destination address required"
+        ]
+    );
+    assert_eq!(texts(console.respond("irb")), ["[-] Unknown command: irb."]);
+    // commands seen earlier replay again
+    assert_eq!(
+        texts(console.respond("use exploit/unix/ftp/vsftpd_234_backdoor")).len(),
+        1
+    );
+}
+
+#[test]
+fn recorded_transcripts_replay_the_same_output() {
+    // record a session, then serve it back from the file
+    let path = temp_path("session.jsonl");
+    let transcript = Transcript::create(&path).unwrap();
+    transcript.record(Stream::Meta, "started msfconsole (pid 1)");
+    transcript.record(Stream::Stdout, "msf6 banner");
+    transcript.record(Stream::Stdin, "version");
+    transcript.record(Stream::Stdout, "Framework: 6.4.43-dev");
+    transcript.record(Stream::Stderr, "warning: deprecated");
+    transcript.record(Stream::Stdin, "back");
+
+    let console = ReplayConsole::new(read_transcript(&path).unwrap());
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let input = "version\n\nback\nexit\nversion\n".as_bytes();
+    serve_replay(console, input, &mut out, &mut err).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "msf6 banner\nFramework: 6.4.43-dev\n"
+    );
+    assert_eq!(String::from_utf8(err).unwrap(), "warning: deprecated\n");
+    let _ = std::fs::remove_file(&path);
+}
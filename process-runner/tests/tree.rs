@@ -0,0 +1,129 @@
+use create_options_json::msf::ExploitDetails;
+use create_options_json::rank::Rank;
+use create_options_json::tree::{self, TreeNode, TreeOptions};
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn details(name: &str, rank: Rank) -> ExploitDetails {
+    ExploitDetails {
+        name: name.to_string(),
+        disclosure_date: None,
+        rank: Some(rank),
+        check: Some(false),
+        description: String::new(),
+    }
+}
+
+fn modules() -> Vec<ExploitDetails> {
+    vec![
+        details("exploit/windows/smb/ms17_010_eternalblue", Rank::Average),
+        details("exploit/windows/smb/psexec", Rank::Manual),
+        details("exploit/unix/ftp/vsftpd_234_backdoor", Rank::Excellent),
+        details("auxiliary/scanner/portscan/tcp", Rank::Normal),
+        details("payload/windows/x64/meterpreter/reverse_tcp", Rank::Normal),
+    ]
+}
+
+fn child<'a>(node: &'a TreeNode, name: &str) -> &'a TreeNode {
+    node.children
+        .iter()
+        .find(|c| c.name == name)
+        .unwrap_or_else(|| panic!("{} has no child {}", node.path, name))
+}
+
+fn names(node: &TreeNode) -> Vec<&str> {
+    node.children.iter().map(|c| c.name.as_str()).collect()
+}
+
+#[test]
+fn groups_modules_by_path_segments() {
+    let modules = modules();
+    let root = tree::build(&modules, &TreeOptions::default());
+    assert_eq!(root.modules, 5);
+    assert_eq!(root.child_count, 3);
+    assert_eq!(names(&root), ["auxiliary", "exploit", "payload"]);
+    assert_eq!(root.ranks[&Rank::Normal], 2);
+
+    let exploit = child(&root, "exploit");
+    assert_eq!(exploit.modules, 3);
+    assert_eq!(names(exploit), ["unix", "windows"]);
+    // ordered from least to most reliable
+    let ranks: Vec<(Rank, usize)> = exploit.ranks.clone().into_iter().collect();
+    assert_eq!(
+        ranks,
+        [(Rank::Manual, 1), (Rank::Average, 1), (Rank::Excellent, 1)]
+    );
+
+    let smb = child(child(exploit, "windows"), "smb");
+    assert_eq!(smb.path, "exploit/windows/smb");
+    assert_eq!(smb.child_count, 2);
+    assert!(smb.module.is_none());
+    let eternalblue = child(smb, "ms17_010_eternalblue");
+    assert_eq!(eternalblue.path, "exploit/windows/smb/ms17_010_eternalblue");
+    assert_eq!(eternalblue.child_count, 0);
+    assert_eq!(
+        eternalblue.module.as_ref().unwrap().rank,
+        Some(Rank::Average)
+    );
+
+    // leaves carry their metadata, groups do not
+    let json = serde_json::to_value(eternalblue).unwrap();
+    assert_eq!(
+        json["module"]["name"],
+        "exploit/windows/smb/ms17_010_eternalblue"
+    );
+    assert!(json.get("children").is_none());
+    assert!(serde_json::to_value(smb).unwrap().get("module").is_none());
+}
+
+#[test]
+fn limits_depth_and_collapses_single_children() {
+    let modules = modules();
+    let options = TreeOptions {
+        max_depth: Some(1),
+        collapse: false,
+    };
+    let root = tree::build(&modules, &options);
+    let exploit = child(&root, "exploit");
+    assert!(exploit.children.is_empty());
+    assert_eq!(exploit.child_count, 2);
+    assert_eq!(exploit.modules, 3);
+
+    let options = TreeOptions {
+        max_depth: None,
+        collapse: true,
+    };
+    let root = tree::build(&modules, &options);
+    assert_eq!(names(&root), ["auxiliary", "exploit", "payload"]);
+    assert_eq!(names(child(&root, "auxiliary")), ["scanner/portscan"]);
+    let exploit = child(&root, "exploit");
+    assert_eq!(names(exploit), ["unix/ftp", "windows/smb"]);
+    let smb = child(exploit, "windows/smb");
+    assert_eq!(smb.path, "exploit/windows/smb");
+    assert_eq!(names(smb), ["ms17_010_eternalblue", "psexec"]);
+    // a lone leaf keeps its own node
+    let ftp = child(exploit, "unix/ftp");
+    assert_eq!(names(ftp), ["vsftpd_234_backdoor"]);
+    let payload = child(&root, "payload");
+    assert_eq!(names(payload), ["windows/x64/meterpreter"]);
+    assert_eq!(
+        child(payload, "windows/x64/meterpreter").children[0].path,
+        "payload/windows/x64/meterpreter/reverse_tcp"
+    );
+}
+
+#[test]
+fn tree_command_reads_module_lists() {
+    let output = Command::new(env!("CARGO_BIN_EXE_create-options-json"))
+        .arg("tree")
+        .args(["--modules", &format!("{}/scraped/exploits.json", FIXTURES)])
+        .args(["--depth", "2", "--collapse"])
+        .output()
+        .expect("Failed to run tree");
+    assert!(output.status.success());
+    let root: TreeNode = serde_json::from_slice(&output.stdout).unwrap();
+    let exploit = child(&root, "exploit");
+    assert_eq!(exploit.modules, root.modules);
+    assert!(exploit.children.iter().all(|c| c.children.is_empty()));
+}
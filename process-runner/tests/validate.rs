@@ -0,0 +1,157 @@
+use create_options_json::catalog::CatalogEntry;
+use create_options_json::msf::Parameter;
+use create_options_json::validate::{validate, Problem};
+use std::collections::HashMap;
+
+fn option(name: &str, default: Option<&str>, required: bool, description: &str) -> Parameter {
+    Parameter::new(
+        name.to_string(),
+        default.map(str::to_string),
+        required,
+        description.to_string(),
+    )
+}
+
+fn entry() -> CatalogEntry {
+    CatalogEntry {
+        payload: "cmd/unix/reverse".to_string(),
+        payload_options: Some(vec![
+            option("LHOST", None, true, "The listen address"),
+            option("LPORT", Some("4444"), true, "The listen port"),
+        ]),
+        options: Some(vec![
+            option("RHOSTS", None, true, "The target host(s)"),
+            option("RPORT", Some("25"), true, "The target port (TCP)"),
+            option("SSL", Some("false"), false, "Negotiate SSL"),
+            option(
+                "MODE",
+                Some("auto"),
+                false,
+                "How to connect (Accepted: auto, manual)",
+            ),
+            option("MAILFROM", None, false, "The FROM address of the e-mail"),
+            option("USERNAME", None, false, "The account to log in with"),
+            option("SMTPHOST", None, false, "The relay to send through"),
+            option("TIMEOUT", Some("10"), false, "Connect timeout"),
+        ]),
+        ..Default::default()
+    }
+}
+
+fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(n, v)| (n.to_string(), v.to_string()))
+        .collect()
+}
+
+fn invalid_address(option: &str, value: &str) -> Problem {
+    Problem::InvalidAddress {
+        option: option.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[test]
+fn accepts_valid_values() {
+    let (problems, warnings) = validate(
+        &entry(),
+        &values(&[
+            ("rhost", "10.0.0.1-9 192.168.1.0/24,host.lab"),
+            ("RPORT", "587"),
+            ("SSL", "yes"),
+            ("MODE", "MANUAL"),
+            ("MAILFROM", "a@b.com"),
+            ("USERNAME", "admin"),
+            ("LHOST", "eth0"),
+            ("VERBOSE", "true"),
+        ]),
+    );
+    assert_eq!(problems, []);
+    assert_eq!(warnings, []);
+}
+
+#[test]
+fn refuses_values_of_certain_kinds() {
+    let (problems, warnings) = validate(
+        &entry(),
+        &values(&[
+            ("RHOSTS", "10.0.0.300"),
+            ("RPORT", "smtp"),
+            ("SSL", "maybe"),
+            ("MODE", "fast"),
+            ("LHOST", "10.0.0.1/24"),
+            ("NOPE", "1"),
+        ]),
+    );
+    assert_eq!(
+        problems,
+        [
+            invalid_address("RHOSTS", "10.0.0.300"),
+            Problem::InvalidPort {
+                option: "RPORT".to_string(),
+                value: "smtp".to_string(),
+            },
+            Problem::InvalidBoolean {
+                option: "SSL".to_string(),
+                value: "maybe".to_string(),
+            },
+            Problem::NotAccepted {
+                option: "MODE".to_string(),
+                value: "fast".to_string(),
+                accepted: vec!["auto".to_string(), "manual".to_string()],
+            },
+            invalid_address("LHOST", "10.0.0.1/24"),
+            Problem::UnknownOption {
+                option: "NOPE".to_string(),
+            },
+        ]
+    );
+    assert_eq!(warnings, []);
+}
+
+#[test]
+fn only_warns_about_guessed_kinds() {
+    // SMTPHOST is guessed to be an address from its name
+    let (problems, warnings) = validate(
+        &entry(),
+        &values(&[
+            ("RHOSTS", "10.0.0.1"),
+            ("LHOST", "10.0.0.2"),
+            ("SMTPHOST", "relay:25"),
+        ]),
+    );
+    assert_eq!(problems, []);
+    assert_eq!(warnings, [invalid_address("SMTPHOST", "relay:25")]);
+}
+
+#[test]
+fn checks_payload_options_only_for_the_default_payload() {
+    let (problems, _) = validate(
+        &entry(),
+        &values(&[("RHOSTS", "10.0.0.1"), ("LPORT", "99999")]),
+    );
+    assert_eq!(
+        problems,
+        [
+            Problem::MissingRequired {
+                option: "LHOST".to_string(),
+            },
+            Problem::InvalidPort {
+                option: "LPORT".to_string(),
+                value: "99999".to_string(),
+            },
+        ]
+    );
+
+    let (problems, _) = validate(
+        &entry(),
+        &values(&[
+            ("RHOSTS", "10.0.0.1"),
+            ("PAYLOAD", "cmd/unix/bind"),
+            ("LPORT", "99999"),
+            ("RANDOM", "1"),
+        ]),
+    );
+    assert_eq!(problems, []);
+}